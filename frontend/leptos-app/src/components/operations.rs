@@ -0,0 +1,118 @@
+use server_fn::{codec::JsonEncoding, BoxedStream, ServerFnError, Websocket};
+use leptos::*;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+// Cross-chain pallet events shown in the operations feed
+const OPERATION_EVENTS: [&str; 3] = ["OperationSubmitted", "OperationResultReceived", "OperationRefunded"];
+
+// Most recent events kept in the feed
+const FEED_LENGTH: usize = 50;
+
+// A cross-chain liquidity operation event from a finalized block
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OperationEvent {
+    pub block: u64,
+    pub name: String,
+    pub fields: String,
+}
+
+// Streams the operation events of the node at NODE_URL as blocks finalize
+#[server(protocol = Websocket<JsonEncoding, JsonEncoding>)]
+async fn operation_events(
+    _input: BoxedStream<(), ServerFnError>,
+) -> Result<BoxedStream<OperationEvent, ServerFnError>, ServerFnError> {
+    use futures::{channel::mpsc, SinkExt};
+    use subxt::{OnlineClient, PolkadotConfig};
+
+    let url = std::env::var("NODE_URL").unwrap_or_else(|_| "ws://127.0.0.1:9944".into());
+    let client = OnlineClient::<PolkadotConfig>::from_url(url).await.map_err(ServerFnError::new)?;
+    let mut blocks = client.blocks().subscribe_finalized().await.map_err(ServerFnError::new)?;
+
+    // Create a channel of outgoing websocket messages
+    let (mut tx, rx) = mpsc::channel(16);
+
+    tokio::spawn(async move {
+        while let Some(block) = blocks.next().await {
+            let events = match block {
+                Ok(block) => block.events().await.map(|events| (block.number() as u64, events)),
+                Err(e) => Err(e),
+            };
+            let (number, events) = match events {
+                Ok(events) => events,
+                Err(e) => {
+                    let _ = tx.send(Err(ServerFnError::new(e))).await;
+                    return;
+                }
+            };
+
+            for event in events.iter().flatten() {
+                if event.pallet_name() != "CrossChain" || !OPERATION_EVENTS.contains(&event.variant_name()) {
+                    continue;
+                }
+                let fields = match event.field_values() {
+                    Ok(fields) => fields.to_string(),
+                    Err(e) => e.to_string(),
+                };
+                let event = OperationEvent { block: number, name: event.variant_name().to_string(), fields };
+                // The client closed the feed
+                if tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(rx.into())
+}
+
+// Feed of cross-chain operations: escrowed on submission, then settled by
+// their result or refunded
+#[component]
+pub fn OperationFeed() -> impl IntoView {
+    use futures::channel::mpsc;
+
+    // Nothing is sent to the server; the sender only keeps the socket open
+    let (tx, rx) = mpsc::channel::<Result<(), ServerFnError>>(1);
+    let events = RwSignal::new(Vec::<OperationEvent>::new());
+
+    // Only listen for operation events on the client
+    if cfg!(feature = "hydrate") {
+        spawn_local(async move {
+            let _open = tx;
+            match operation_events(rx.into()).await {
+                Ok(mut incoming) => {
+                    while let Some(event) = incoming.next().await {
+                        match event {
+                            Ok(event) => events.update(|events| {
+                                events.insert(0, event);
+                                events.truncate(FEED_LENGTH);
+                            }),
+                            Err(e) => leptos::logging::warn!("{e}"),
+                        }
+                    }
+                }
+                Err(e) => leptos::logging::warn!("{e}"),
+            }
+        });
+    }
+
+    view! {
+        <div class="operation-feed">
+            <h2>"Cross-Chain Operations"</h2>
+            <ul>
+                <For
+                    each=move || events.get()
+                    key=|event| (event.block, event.name.clone(), event.fields.clone())
+                    children=|event| view! {
+                        <li class=format!("operation {}", event.name)>
+                            <span class="block">"#"{event.block}</span>
+                            <span class="name">{event.name}</span>
+                            <span class="fields">{event.fields}</span>
+                        </li>
+                    }
+                />
+            </ul>
+        </div>
+    }
+}
@@ -0,0 +1,138 @@
+//! Schnorr adaptor signatures over sr25519
+//!
+//! An adaptor (pre-)signature is an sr25519 signature that is missing a secret scalar `t`.
+//! Anyone holding the adaptor point `T = t·G` can check the pre-signature is valid, but only
+//! someone knowing `t` can complete it. Once the completed signature is published, `t` can be
+//! extracted from the difference between the two.
+//!
+//! Completed signatures are ordinary sr25519 signatures under the `substrate` signing context,
+//! so the pallet verifies them with `sp_io::crypto::sr25519_verify`.
+//!
+//! A scriptless swap between Alice (funds on chain A) and Bob (funds on chain B) runs as:
+//!
+//! 1. Alice picks `t` and shares `T`. Each party locks funds with `lock_adaptor_swap`, naming
+//!    the other as beneficiary and their own key as signer. Bob's leg must expire first.
+//! 2. Alice sends Bob a pre-signature of her leg's claim message, Bob sends Alice a
+//!    pre-signature of his. Both use `T` and each checks the other's with
+//!    [`verify_pre_signature`].
+//! 3. Alice completes Bob's pre-signature with `t` and claims on chain B.
+//! 4. Bob reads the signature from chain B, runs [`extract_secret`], completes Alice's
+//!    pre-signature and claims on chain A.
+//!
+//! Neither chain sees a hash lock or any other value linking the two legs.
+
+use codec::{Decode, Encode};
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT,
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+};
+#[cfg(feature = "std")]
+use rand_core::{CryptoRng, RngCore};
+use schnorrkel::{context::SigningTranscript, signing_context, Keypair, PublicKey};
+use sp_runtime::RuntimeDebug;
+
+/// Signing context used by Substrate for sr25519 signatures
+pub const SIGNING_CTX: &[u8] = b"substrate";
+
+/// Marker bit schnorrkel sets on the last byte of a signature
+const SIGNATURE_MARKER: u8 = 0x80;
+
+/// Adaptor pre-signature
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct PreSignature {
+    /// Signer's nonce commitment `R' = r·G`, excluding the adaptor point
+    pub nonce_commitment: [u8; 32],
+    /// Pre-signature scalar `s' = r + c·x`
+    pub s: [u8; 32],
+}
+
+/// Adaptor point `T = t·G` for a secret `t`
+pub fn adaptor_point(secret: &Scalar) -> CompressedRistretto {
+    (secret * RISTRETTO_BASEPOINT_POINT).compress()
+}
+
+/// Challenge `c = H(P, R, m)` computed exactly as schnorrkel does for sr25519
+fn challenge(public: &PublicKey, nonce: &CompressedRistretto, message: &[u8]) -> Scalar {
+    let mut transcript = signing_context(SIGNING_CTX).bytes(message);
+    transcript.proto_name(b"Schnorr-sig");
+    transcript.commit_point(b"sign:pk", public.as_compressed());
+    transcript.commit_point(b"sign:R", nonce);
+    transcript.challenge_scalar(b"sign:c")
+}
+
+/// Pre-sign `message` with `keypair` under `adaptor`
+///
+/// The signature nonce is `R = R' + T`, so the pre-signature becomes a valid signature once
+/// the adaptor secret is added to `s'`. Pre-signing happens off-chain, so it is only available
+/// with `std`.
+#[cfg(feature = "std")]
+pub fn pre_sign<R: RngCore + CryptoRng>(
+    keypair: &Keypair,
+    message: &[u8],
+    adaptor: &CompressedRistretto,
+    rng: &mut R,
+) -> Option<PreSignature> {
+    let adaptor = adaptor.decompress()?;
+    let mut key_bytes = [0u8; 32];
+    key_bytes.copy_from_slice(&keypair.secret.to_bytes()[..32]);
+    let key: Scalar = Option::from(Scalar::from_canonical_bytes(key_bytes))?;
+
+    let nonce = Scalar::random(rng);
+    let nonce_commitment = nonce * RISTRETTO_BASEPOINT_POINT;
+    let c = challenge(&keypair.public, &(nonce_commitment + adaptor).compress(), message);
+
+    Some(PreSignature {
+        nonce_commitment: nonce_commitment.compress().to_bytes(),
+        s: (nonce + c * key).to_bytes(),
+    })
+}
+
+/// Check that `pre_signature` completes to a valid signature of `message` by `public` once
+/// the secret behind `adaptor` is added
+pub fn verify_pre_signature(
+    public: &PublicKey,
+    message: &[u8],
+    adaptor: &CompressedRistretto,
+    pre_signature: &PreSignature,
+) -> bool {
+    let (Some(adaptor), Some(nonce_commitment), Some(s)) = (
+        adaptor.decompress(),
+        CompressedRistretto(pre_signature.nonce_commitment).decompress(),
+        Option::<Scalar>::from(Scalar::from_canonical_bytes(pre_signature.s)),
+    ) else {
+        return false;
+    };
+
+    let c = challenge(public, &(nonce_commitment + adaptor).compress(), message);
+    s * RISTRETTO_BASEPOINT_POINT == nonce_commitment + c * public.as_point()
+}
+
+/// Complete `pre_signature` with the adaptor `secret` into sr25519 signature bytes
+pub fn complete(pre_signature: &PreSignature, secret: &Scalar) -> Option<[u8; 64]> {
+    let nonce_commitment = CompressedRistretto(pre_signature.nonce_commitment).decompress()?;
+    let s = Option::<Scalar>::from(Scalar::from_canonical_bytes(pre_signature.s))? + secret;
+    let nonce: RistrettoPoint = nonce_commitment + secret * RISTRETTO_BASEPOINT_POINT;
+
+    let mut signature = [0u8; 64];
+    signature[..32].copy_from_slice(nonce.compress().as_bytes());
+    signature[32..].copy_from_slice(s.as_bytes());
+    signature[63] |= SIGNATURE_MARKER;
+    Some(signature)
+}
+
+/// Extract the adaptor secret from a pre-signature and its completed signature
+pub fn extract_secret(pre_signature: &PreSignature, signature: &[u8; 64]) -> Option<Scalar> {
+    let mut s_bytes = [0u8; 32];
+    s_bytes.copy_from_slice(&signature[32..]);
+    s_bytes[31] &= !SIGNATURE_MARKER;
+
+    let s: Scalar = Option::from(Scalar::from_canonical_bytes(s_bytes))?;
+    let s_pre: Scalar = Option::from(Scalar::from_canonical_bytes(pre_signature.s))?;
+    let secret = s - s_pre;
+
+    // The published nonce must be `R' + T` for the secret to belong to this pre-signature
+    let nonce_commitment = CompressedRistretto(pre_signature.nonce_commitment).decompress()?;
+    let nonce = (nonce_commitment + secret * RISTRETTO_BASEPOINT_POINT).compress();
+    (nonce.as_bytes()[..] == signature[..32]).then_some(secret)
+}
@@ -2,16 +2,50 @@
 //!
 //! This module implements atomic swaps between different chains in the Matrix-Magiq
 //! ecosystem (NRSH, ELXR, IMRT) with quantum-resistant cryptography.
+//!
+//! A swap has two legs, one on each parachain, linked by a shared swap ID and hash lock:
+//!
+//! 1. `initiate_swap` on the source chain locks the initiator's funds and sends an
+//!    [`SwapMessage::Initiated`] notice to the target chain via `pallet-cross-chain`.
+//! 2. `participate_swap` on the target chain locks the counterparty's funds with a
+//!    timelock that ends `SafetyMargin` blocks before the initiator's.
+//! 3. The initiator reveals the secret with `claim_swap` on the target chain. The secret
+//!    is relayed back to the source chain, which settles the initiator's leg.
+//!
+//! When the counterparty is not known in advance, the initiator can `post_offer` instead.
+//! Anyone can `accept_offer`, which starts a swap with them as counterparty. A taker who then
+//! fails to participate within `AcceptanceTimeout` only holds the maker's funds until then. Offers posted
+//! with `post_partial_offer` can be taken in parts with `fill_offer`; each fill starts its
+//! own swap locked with a hash lock chosen by the taker. The taker then holds the secret, so
+//! the roles are reversed: the taker reveals it by claiming the maker's leg, which expires
+//! `SafetyMargin` blocks before the taker's. Open offers are listed a page at a time through
+//! the [`AtomicSwapApi`] runtime API.
+//!
+//! Offers, swap legs and adaptor swap legs are removed from storage once they are taken in
+//! full, canceled, claimed or refunded; their outcome is recorded in the pallet's events.
+//!
+//! For swaps that must not be linkable across chains, `lock_adaptor_swap` locks a leg that is
+//! released by an sr25519 adaptor signature instead of a hash lock; see [`adaptor`].
+//!
+//! Timelocks are measured with `TimelockProvider` (the relay chain block number) so both
+//! legs share one clock.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use frame_support::{
-    dispatch::DispatchResult,
-    pallet_prelude::*,
-    traits::{Get, Currency},
+pub use pallet::*;
+
+pub mod adaptor;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+use sp_runtime::{
+    helpers_128bit::multiply_by_rational_with_rounding,
+    traits::{AtLeast32BitUnsigned, SaturatedConversion},
+    Rounding, RuntimeDebug,
 };
-use frame_system::pallet_prelude::*;
-use sp_runtime::{traits::Hash, RuntimeDebug};
 use sp_std::prelude::*;
 use codec::{Decode, Encode, MaxEncodedLen};
 use scale_info::TypeInfo;
@@ -33,8 +67,17 @@ pub enum SwapStatus {
     Canceled,
 }
 
+/// Leg of a cross-chain swap held on this chain
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum SwapLeg {
+    /// Source leg, funded by the initiator on the source parachain
+    Initiator,
+    /// Target leg, funded by the counterparty on the target parachain
+    Participant,
+}
+
 /// Atomic swap data
-#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
 pub struct AtomicSwap<AccountId, AssetId, Balance, BlockNumber, Hash> {
     /// Swap ID
     pub id: Hash,
@@ -60,15 +103,185 @@ pub struct AtomicSwap<AccountId, AssetId, Balance, BlockNumber, Hash> {
     pub created_at: BlockNumber,
     /// Expiration block
     pub expires_at: BlockNumber,
+    /// Block by which the counterparty must lock the target leg, after which the initiator
+    /// leg can be refunded early
+    pub participate_by: BlockNumber,
+    /// Swap status
+    pub status: SwapStatus,
+    /// Leg held on this chain
+    pub leg: SwapLeg,
+    /// Leg whose owner knows the secret and claims the other leg first
+    pub revealer: SwapLeg,
+}
+
+impl<AccountId, AssetId, Balance, BlockNumber, Hash> AtomicSwap<AccountId, AssetId, Balance, BlockNumber, Hash> {
+    /// Parachain holding the other leg of the swap
+    pub fn remote_parachain_id(&self) -> u32 {
+        match self.leg {
+            SwapLeg::Initiator => self.target_parachain_id,
+            SwapLeg::Participant => self.source_parachain_id,
+        }
+    }
+}
+
+/// Leg of a scriptless swap, released by a signature from `signer`
+///
+/// See the [`adaptor`] module for how the two legs are tied together off-chain.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct AdaptorSwap<AccountId, AssetId, Balance, BlockNumber, Hash> {
+    /// Swap ID
+    pub id: Hash,
+    /// Account that locked the funds
+    pub depositor: AccountId,
+    /// Account receiving the funds on claim
+    pub beneficiary: AccountId,
+    /// Locked asset
+    pub asset: AssetId,
+    /// Locked amount
+    pub amount: Balance,
+    /// sr25519 key whose signature of the claim message releases the funds
+    pub signer: [u8; 32],
+    /// Creation block
+    pub created_at: BlockNumber,
+    /// Expiration block
+    pub expires_at: BlockNumber,
     /// Swap status
     pub status: SwapStatus,
 }
 
-/// Quantum-resistant hash function using CRYSTALS-Dilithium
+/// Message `signer` signs to release an adaptor swap leg to its beneficiary
+pub fn adaptor_claim_message<AccountId: Encode, Hash: Encode>(
+    parachain_id: u32,
+    swap_id: &Hash,
+    beneficiary: &AccountId,
+) -> Vec<u8> {
+    (b"matrix-magiq/adaptor-swap", parachain_id, swap_id, beneficiary).encode()
+}
+
+/// Swap offer status
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum OfferStatus {
+    /// Open for any counterparty to accept
+    Open,
+    /// Fully taken by one or more swaps
+    Accepted,
+    /// Canceled and refunded to the maker
+    Canceled,
+}
+
+/// Swap offer posted without a known counterparty
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct SwapOffer<AccountId, AssetId, Balance, BlockNumber, Hash> {
+    /// Offer ID
+    pub id: Hash,
+    /// Maker account, the initiator of the resulting swap
+    pub maker: AccountId,
+    /// Source asset
+    pub source_asset: AssetId,
+    /// Target asset
+    pub target_asset: AssetId,
+    /// Source amount
+    pub source_amount: Balance,
+    /// Target amount
+    pub target_amount: Balance,
+    /// Target parachain ID
+    pub target_parachain_id: u32,
+    /// Source amount not yet taken
+    pub remaining_amount: Balance,
+    /// Smallest source amount a single fill may take, unless it takes the remainder
+    pub min_fill: Balance,
+    /// Hash lock of the swap started on acceptance, or `None` if each taker brings their own
+    /// with `fill_offer`
+    pub hash_lock: Option<[u8; 64]>,
+    /// Timelock of each resulting swap, counted from acceptance
+    pub timelock: BlockNumber,
+    /// Creation block
+    pub created_at: BlockNumber,
+    /// Block after which the offer can no longer be accepted
+    pub expires_at: BlockNumber,
+    /// Offer status
+    pub status: OfferStatus,
+}
+
+/// Most stored offers scanned for one page of [`AtomicSwapApi::open_offers`]
+pub const MAX_OFFERS_PAGE: u32 = 100;
+
+/// A page of open swap offers
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct OffersPage<AccountId, AssetId, Balance, BlockNumber, Hash> {
+    /// Open offers matching the filter
+    pub offers: Vec<SwapOffer<AccountId, AssetId, Balance, BlockNumber, Hash>>,
+    /// Offer after which the next page starts, or `None` on the last page
+    pub next: Option<Hash>,
+}
+
+/// Filter for listing open swap offers
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, Default)]
+pub struct OfferFilter<AccountId, AssetId> {
+    /// Only offers from this maker
+    pub maker: Option<AccountId>,
+    /// Only offers selling this asset
+    pub source_asset: Option<AssetId>,
+    /// Only offers buying this asset
+    pub target_asset: Option<AssetId>,
+    /// Only offers settling on this parachain
+    pub target_parachain_id: Option<u32>,
+}
+
+impl<AccountId: PartialEq, AssetId: PartialEq> OfferFilter<AccountId, AssetId> {
+    /// Whether `offer` matches every criterion set on this filter
+    pub fn matches<Balance, BlockNumber, Hash>(
+        &self,
+        offer: &SwapOffer<AccountId, AssetId, Balance, BlockNumber, Hash>,
+    ) -> bool {
+        self.maker.as_ref().is_none_or(|maker| *maker == offer.maker) &&
+            self.source_asset.as_ref().is_none_or(|asset| *asset == offer.source_asset) &&
+            self.target_asset.as_ref().is_none_or(|asset| *asset == offer.target_asset) &&
+            self.target_parachain_id.is_none_or(|id| id == offer.target_parachain_id)
+    }
+}
+
+/// Target amount owed for taking `fill` out of an offer of `source_amount` for `target_amount`
+///
+/// Rounds up so that the taker never pays less than the maker's price, whatever the fill size.
+pub fn fill_target_amount<Balance>(fill: Balance, source_amount: Balance, target_amount: Balance) -> Option<Balance>
+where
+    Balance: AtLeast32BitUnsigned + Copy,
+{
+    multiply_by_rational_with_rounding(
+        fill.saturated_into(),
+        target_amount.saturated_into(),
+        source_amount.saturated_into(),
+        Rounding::Up,
+    )
+    .and_then(|amount| amount.try_into().ok())
+}
+
+/// Coordination messages exchanged between the two legs of a swap
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum SwapMessage<AccountId, AssetId, Balance, BlockNumber, Hash> {
+    /// A swap was initiated on the source parachain
+    Initiated(AtomicSwap<AccountId, AssetId, Balance, BlockNumber, Hash>),
+    /// The counterparty locked the target leg
+    Participated {
+        /// Swap ID
+        swap_id: Hash,
+        /// Expiration block of the target leg
+        expires_at: BlockNumber,
+    },
+    /// The secret was revealed on one chain
+    SecretRevealed {
+        /// Swap ID
+        swap_id: Hash,
+        /// Revealed secret
+        secret: [u8; 32],
+    },
+}
+
+/// Quantum-resistant hash function
 pub fn quantum_resistant_hash(data: &[u8]) -> [u8; 64] {
-    // Implementation would use CRYSTALS-Dilithium
-    // This is a placeholder for the actual implementation
-    [0u8; 64]
+    // A 512-bit hash keeps 256 bits of preimage security against Grover's algorithm
+    sp_io::hashing::keccak_512(data)
 }
 
 /// Generate a secret and hash pair for atomic swaps
@@ -84,45 +297,1060 @@ pub fn generate_swap_secret() -> ([u8; 32], [u8; 64]) {
 
 /// Verify a hash and secret pair
 pub fn verify_swap_secret(secret: &[u8; 32], hash: &[u8; 64]) -> bool {
-    // Verify the hash matches the secret
-    // This is a placeholder for the actual implementation
-    true
-}
-
-/// Initiate an atomic swap
-pub fn initiate_swap<T: frame_system::Config>(
-    swap: AtomicSwap<T::AccountId, T::AssetId, T::Balance, T::BlockNumber, T::Hash>,
-) -> DispatchResult {
-    // Implementation would lock funds and initiate the swap
-    // This is a placeholder for the actual implementation
-    Ok(())
-}
-
-/// Participate in an atomic swap
-pub fn participate_swap<T: frame_system::Config>(
-    swap_id: T::Hash,
-    counterparty: T::AccountId,
-) -> DispatchResult {
-    // Implementation would lock counterparty funds
-    // This is a placeholder for the actual implementation
-    Ok(())
-}
-
-/// Claim an atomic swap using the secret
-pub fn claim_swap<T: frame_system::Config>(
-    swap_id: T::Hash,
-    secret: [u8; 32],
-) -> DispatchResult {
-    // Implementation would release funds to the claimer
-    // This is a placeholder for the actual implementation
-    Ok(())
-}
-
-/// Refund an expired atomic swap
-pub fn refund_swap<T: frame_system::Config>(
-    swap_id: T::Hash,
-) -> DispatchResult {
-    // Implementation would refund locked funds after expiration
-    // This is a placeholder for the actual implementation
-    Ok(())
+    quantum_resistant_hash(secret) == *hash
+}
+
+#[frame_support::pallet]
+#[allow(clippy::too_many_arguments)]
+pub mod pallet {
+    use super::*;
+    use frame_support::{
+        pallet_prelude::*,
+        traits::tokens::{
+            fungibles::{Inspect, Mutate},
+            Preservation,
+        },
+        PalletId,
+    };
+    use frame_system::pallet_prelude::*;
+    use pallet_cross_chain::{CrossChainMessenger, SwapMessageHandler, XcmMessageType};
+    use sp_core::sr25519;
+    use sp_runtime::traits::{
+        AccountIdConversion, BlockNumberProvider, Hash, Saturating, Zero,
+    };
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        /// The overarching event type
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Assets locked by swaps
+        type Assets: Mutate<Self::AccountId>;
+
+        /// Messenger used to reach the other leg of a swap
+        type Messenger: CrossChainMessenger;
+
+        /// Parachain ID of this chain
+        type SelfParaId: Get<u32>;
+
+        /// Clock shared by all parachains, used for timelocks
+        type TimelockProvider: BlockNumberProvider<BlockNumber = BlockNumberFor<Self>>;
+
+        /// Pallet ID, used to derive the escrow account
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+
+        /// Minimum time a leg must remain claimable
+        #[pallet::constant]
+        type MinTimelock: Get<BlockNumberFor<Self>>;
+
+        /// Maximum timelock of the initiator leg
+        #[pallet::constant]
+        type MaxTimelock: Get<BlockNumberFor<Self>>;
+
+        /// Blocks between the participant leg and initiator leg expiring
+        #[pallet::constant]
+        type SafetyMargin: Get<BlockNumberFor<Self>>;
+
+        /// Maximum time an offer stays open
+        #[pallet::constant]
+        type MaxOfferLifetime: Get<BlockNumberFor<Self>>;
+
+        /// Time a taker has to lock the target leg of a swap started from an offer
+        #[pallet::constant]
+        type AcceptanceTimeout: Get<BlockNumberFor<Self>>;
+    }
+
+    /// Asset identifier type
+    pub type AssetIdOf<T> = <<T as Config>::Assets as Inspect<<T as frame_system::Config>::AccountId>>::AssetId;
+
+    /// Alias for balance type
+    pub type BalanceOf<T> = <<T as Config>::Assets as Inspect<<T as frame_system::Config>::AccountId>>::Balance;
+
+    /// Atomic swap for this runtime
+    pub type SwapOf<T> = AtomicSwap<
+        <T as frame_system::Config>::AccountId,
+        AssetIdOf<T>,
+        BalanceOf<T>,
+        BlockNumberFor<T>,
+        <T as frame_system::Config>::Hash,
+    >;
+
+    /// Swap message for this runtime
+    pub type SwapMessageOf<T> = SwapMessage<
+        <T as frame_system::Config>::AccountId,
+        AssetIdOf<T>,
+        BalanceOf<T>,
+        BlockNumberFor<T>,
+        <T as frame_system::Config>::Hash,
+    >;
+
+    /// Swap offer for this runtime
+    pub type OfferOf<T> = SwapOffer<
+        <T as frame_system::Config>::AccountId,
+        AssetIdOf<T>,
+        BalanceOf<T>,
+        BlockNumberFor<T>,
+        <T as frame_system::Config>::Hash,
+    >;
+
+    /// Page of swap offers for this runtime
+    pub type OffersPageOf<T> = OffersPage<
+        <T as frame_system::Config>::AccountId,
+        AssetIdOf<T>,
+        BalanceOf<T>,
+        BlockNumberFor<T>,
+        <T as frame_system::Config>::Hash,
+    >;
+
+    /// Scriptless swap leg for this runtime
+    pub type AdaptorSwapOf<T> = AdaptorSwap<
+        <T as frame_system::Config>::AccountId,
+        AssetIdOf<T>,
+        BalanceOf<T>,
+        BlockNumberFor<T>,
+        <T as frame_system::Config>::Hash,
+    >;
+
+    /// Swap legs locked on this chain, until claimed or refunded
+    #[pallet::storage]
+    pub type Swaps<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, SwapOf<T>>;
+
+    /// Swaps initiated on other chains awaiting participation on this chain
+    #[pallet::storage]
+    pub type SwapNotices<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, SwapOf<T>>;
+
+    /// Swap offers awaiting a counterparty, until taken in full or canceled
+    #[pallet::storage]
+    pub type Offers<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, OfferOf<T>>;
+
+    /// Scriptless swap legs locked on this chain, until claimed or refunded
+    #[pallet::storage]
+    pub type AdaptorSwaps<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, AdaptorSwapOf<T>>;
+
+    /// Nonce used to derive swap IDs
+    #[pallet::storage]
+    pub type NextSwapNonce<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// A swap was initiated on this chain
+        SwapInitiated {
+            swap_id: T::Hash,
+            initiator: T::AccountId,
+            counterparty: T::AccountId,
+            target_parachain_id: u32,
+            expires_at: BlockNumberFor<T>,
+        },
+
+        /// A swap initiated on another chain is awaiting participation
+        SwapNoticeReceived {
+            swap_id: T::Hash,
+            source_parachain_id: u32,
+            counterparty: T::AccountId,
+        },
+
+        /// A scriptless swap leg was locked
+        AdaptorSwapLocked {
+            swap_id: T::Hash,
+            depositor: T::AccountId,
+            beneficiary: T::AccountId,
+            expires_at: BlockNumberFor<T>,
+        },
+
+        /// A scriptless swap leg was claimed; the adaptor secret can be extracted from `signature`
+        AdaptorSwapClaimed {
+            swap_id: T::Hash,
+            beneficiary: T::AccountId,
+            signature: [u8; 64],
+        },
+
+        /// A scriptless swap leg was refunded after expiration
+        AdaptorSwapRefunded {
+            swap_id: T::Hash,
+            who: T::AccountId,
+        },
+
+        /// A swap offer was posted
+        OfferPosted {
+            offer_id: T::Hash,
+            maker: T::AccountId,
+            source_asset: AssetIdOf<T>,
+            target_asset: AssetIdOf<T>,
+            source_amount: BalanceOf<T>,
+            target_amount: BalanceOf<T>,
+            target_parachain_id: u32,
+            expires_at: BlockNumberFor<T>,
+        },
+
+        /// Part or all of a swap offer was taken, starting a swap
+        OfferFilled {
+            offer_id: T::Hash,
+            swap_id: T::Hash,
+            taker: T::AccountId,
+            source_amount: BalanceOf<T>,
+            target_amount: BalanceOf<T>,
+            remaining_amount: BalanceOf<T>,
+        },
+
+        /// A swap offer was canceled and refunded
+        OfferCanceled {
+            offer_id: T::Hash,
+        },
+
+        /// The counterparty locked the target leg on this chain
+        SwapParticipated {
+            swap_id: T::Hash,
+            counterparty: T::AccountId,
+            expires_at: BlockNumberFor<T>,
+        },
+
+        /// The counterparty locked the target leg on the remote chain
+        CounterpartyLocked {
+            swap_id: T::Hash,
+            expires_at: BlockNumberFor<T>,
+        },
+
+        /// A swap leg was claimed with the secret
+        SwapClaimed {
+            swap_id: T::Hash,
+            beneficiary: T::AccountId,
+            secret: [u8; 32],
+        },
+
+        /// A swap leg was refunded after expiration
+        SwapRefunded {
+            swap_id: T::Hash,
+            who: T::AccountId,
+        },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Swap not found
+        SwapNotFound,
+        /// Swap already exists
+        SwapAlreadyExists,
+        /// Caller is not the swap counterparty
+        NotCounterparty,
+        /// Secret does not match the hash lock
+        InvalidSecret,
+        /// Swap expired
+        SwapExpired,
+        /// Swap has not expired yet
+        SwapNotExpired,
+        /// Swap is not in a valid state for this operation
+        InvalidSwapState,
+        /// Timelock is shorter than the safety margins allow
+        TimelockTooShort,
+        /// Timelock is longer than allowed
+        TimelockTooLong,
+        /// Source and target parachains are the same
+        SameParachain,
+        /// Message came from a parachain not involved in the swap
+        UnexpectedParachain,
+        /// Offer not found
+        OfferNotFound,
+        /// Offer is no longer open
+        OfferNotOpen,
+        /// Offer expired
+        OfferExpired,
+        /// Offer lifetime is outside the allowed range
+        InvalidOfferLifetime,
+        /// Maker cannot accept their own offer
+        CannotAcceptOwnOffer,
+        /// Only the maker can cancel an offer before it expires
+        NotOfferMaker,
+        /// Fill amount is zero, below the offer's minimum fill or above its remainder
+        InvalidFillAmount,
+        /// Offer must be taken whole with `accept_offer`, or in parts with `fill_offer`
+        WrongOfferKind,
+        /// Arithmetic overflow
+        Overflow,
+        /// Signature does not release the swap
+        InvalidSignature,
+        /// Message could not be decoded
+        MalformedMessage,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn integrity_test() {
+            assert!(
+                T::MaxTimelock::get() >= T::SafetyMargin::get().saturating_add(T::MinTimelock::get()),
+                "MaxTimelock must leave room for SafetyMargin and MinTimelock",
+            );
+            assert!(
+                T::AcceptanceTimeout::get() > T::SafetyMargin::get(),
+                "AcceptanceTimeout must leave room for the participation notice to arrive",
+            );
+            assert!(
+                T::MaxTimelock::get() >= T::AcceptanceTimeout::get().saturating_add(T::MinTimelock::get()),
+                "MaxTimelock must leave room for AcceptanceTimeout and MinTimelock",
+            );
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Initiate an atomic swap
+        ///
+        /// Locks `source_amount` of the initiator's funds and notifies the target chain.
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn initiate_swap(
+            origin: OriginFor<T>,
+            counterparty: T::AccountId,
+            source_asset: AssetIdOf<T>,
+            target_asset: AssetIdOf<T>,
+            source_amount: BalanceOf<T>,
+            target_amount: BalanceOf<T>,
+            target_parachain_id: u32,
+            hash_lock: [u8; 64],
+            timelock: BlockNumberFor<T>,
+        ) -> DispatchResult {
+            let initiator = ensure_signed(origin)?;
+            Self::ensure_valid_terms(target_parachain_id, timelock)?;
+            let swap_id = Self::next_swap_id(&initiator, &hash_lock);
+
+            T::Assets::transfer(
+                source_asset.clone(),
+                &initiator,
+                &Self::account_id(),
+                source_amount,
+                Preservation::Expendable,
+            )?;
+            let now = T::TimelockProvider::current_block_number();
+            let expires_at = now.saturating_add(timelock);
+            Self::start_swap(AtomicSwap {
+                id: swap_id,
+                initiator,
+                counterparty,
+                source_asset,
+                target_asset,
+                source_amount,
+                target_amount,
+                source_parachain_id: T::SelfParaId::get(),
+                target_parachain_id,
+                hash_lock,
+                created_at: now,
+                expires_at,
+                participate_by: expires_at,
+                status: SwapStatus::Pending,
+                leg: SwapLeg::Initiator,
+                revealer: SwapLeg::Initiator,
+            })
+        }
+
+        /// Participate in an atomic swap
+        ///
+        /// Locks `target_amount` of the counterparty's funds on the target chain. The leg
+        /// expires `SafetyMargin` blocks before the initiator leg if the initiator holds the
+        /// secret, or `SafetyMargin` blocks after it if the counterparty does.
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn participate_swap(origin: OriginFor<T>, swap_id: T::Hash) -> DispatchResult {
+            let counterparty = ensure_signed(origin)?;
+            let notice = SwapNotices::<T>::get(swap_id).ok_or(Error::<T>::SwapNotFound)?;
+            ensure!(notice.counterparty == counterparty, Error::<T>::NotCounterparty);
+            ensure!(!Swaps::<T>::contains_key(swap_id), Error::<T>::SwapAlreadyExists);
+
+            let now = T::TimelockProvider::current_block_number();
+            // Leave the participation notice time to reach the source chain before the
+            // initiator leg can be refunded early
+            ensure!(
+                now.saturating_add(T::SafetyMargin::get()) <= notice.participate_by,
+                Error::<T>::SwapExpired
+            );
+            // The leg claimed first must stay claimable for at least `MinTimelock`
+            let expires_at = match notice.revealer {
+                SwapLeg::Initiator => notice.expires_at.saturating_sub(T::SafetyMargin::get()),
+                SwapLeg::Participant => notice.expires_at.saturating_add(T::SafetyMargin::get()),
+            };
+            ensure!(
+                expires_at.min(notice.expires_at) >= now.saturating_add(T::MinTimelock::get()),
+                Error::<T>::TimelockTooShort
+            );
+
+            T::Assets::transfer(
+                notice.target_asset.clone(),
+                &counterparty,
+                &Self::account_id(),
+                notice.target_amount,
+                Preservation::Expendable,
+            )?;
+
+            let source_parachain_id = notice.source_parachain_id;
+            SwapNotices::<T>::remove(swap_id);
+            Swaps::<T>::insert(swap_id, AtomicSwap {
+                created_at: now,
+                expires_at,
+                status: SwapStatus::InProgress,
+                leg: SwapLeg::Participant,
+                ..notice
+            });
+
+            T::Messenger::send_message(
+                source_parachain_id,
+                XcmMessageType::AtomicSwap,
+                SwapMessageOf::<T>::Participated { swap_id, expires_at }.encode(),
+            )?;
+
+            Self::deposit_event(Event::SwapParticipated { swap_id, counterparty, expires_at });
+
+            Ok(())
+        }
+
+        /// Claim an atomic swap using the secret
+        ///
+        /// Pays out the leg held on this chain and relays the secret to the other chain.
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn claim_swap(
+            origin: OriginFor<T>,
+            swap_id: T::Hash,
+            secret: [u8; 32],
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            let remote_parachain_id = Self::do_claim(swap_id, secret)?;
+
+            T::Messenger::send_message(
+                remote_parachain_id,
+                XcmMessageType::AtomicSwap,
+                SwapMessageOf::<T>::SecretRevealed { swap_id, secret }.encode(),
+            )
+        }
+
+        /// Refund an expired atomic swap
+        ///
+        /// An initiator leg the counterparty never locked against can be refunded once its
+        /// `participate_by` block is reached.
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn refund_swap(origin: OriginFor<T>, swap_id: T::Hash) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            Swaps::<T>::try_mutate_exists(swap_id, |maybe_swap| -> DispatchResult {
+                let swap = maybe_swap.take().ok_or(Error::<T>::SwapNotFound)?;
+                ensure!(
+                    matches!(swap.status, SwapStatus::Pending | SwapStatus::InProgress),
+                    Error::<T>::InvalidSwapState
+                );
+                let refund_from = match swap.status {
+                    SwapStatus::Pending => swap.participate_by.min(swap.expires_at),
+                    _ => swap.expires_at,
+                };
+                ensure!(
+                    T::TimelockProvider::current_block_number() >= refund_from,
+                    Error::<T>::SwapNotExpired
+                );
+
+                let (asset, amount, who) = match swap.leg {
+                    SwapLeg::Initiator => (swap.source_asset, swap.source_amount, swap.initiator),
+                    SwapLeg::Participant => (swap.target_asset, swap.target_amount, swap.counterparty),
+                };
+                T::Assets::transfer(asset, &Self::account_id(), &who, amount, Preservation::Expendable)?;
+
+                Self::deposit_event(Event::SwapRefunded { swap_id, who });
+                Ok(())
+            })
+        }
+
+        /// Post a swap offer that any counterparty can accept
+        ///
+        /// Locks `source_amount` of the maker's funds until the offer is accepted or canceled.
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn post_offer(
+            origin: OriginFor<T>,
+            source_asset: AssetIdOf<T>,
+            target_asset: AssetIdOf<T>,
+            source_amount: BalanceOf<T>,
+            target_amount: BalanceOf<T>,
+            target_parachain_id: u32,
+            hash_lock: [u8; 64],
+            timelock: BlockNumberFor<T>,
+            lifetime: BlockNumberFor<T>,
+        ) -> DispatchResult {
+            let maker = ensure_signed(origin)?;
+            Self::ensure_valid_offer_terms(target_parachain_id, timelock)?;
+            ensure!(
+                !lifetime.is_zero() && lifetime <= T::MaxOfferLifetime::get(),
+                Error::<T>::InvalidOfferLifetime
+            );
+            let offer_id = Self::next_swap_id(&maker, &hash_lock);
+
+            T::Assets::transfer(
+                source_asset.clone(),
+                &maker,
+                &Self::account_id(),
+                source_amount,
+                Preservation::Expendable,
+            )?;
+            let now = T::TimelockProvider::current_block_number();
+            let expires_at = now.saturating_add(lifetime);
+            Offers::<T>::insert(offer_id, SwapOffer {
+                id: offer_id,
+                maker: maker.clone(),
+                source_asset: source_asset.clone(),
+                target_asset: target_asset.clone(),
+                source_amount,
+                target_amount,
+                target_parachain_id,
+                remaining_amount: source_amount,
+                min_fill: source_amount,
+                hash_lock: Some(hash_lock),
+                timelock,
+                created_at: now,
+                expires_at,
+                status: OfferStatus::Open,
+            });
+
+            Self::deposit_event(Event::OfferPosted {
+                offer_id,
+                maker,
+                source_asset,
+                target_asset,
+                source_amount,
+                target_amount,
+                target_parachain_id,
+                expires_at,
+            });
+
+            Ok(())
+        }
+
+        /// Post a swap offer that several counterparties can take in parts
+        ///
+        /// Each fill starts its own swap, locked with a hash lock supplied by the taker. Every
+        /// fill must take at least `min_fill` unless it takes the remainder.
+        #[pallet::call_index(7)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn post_partial_offer(
+            origin: OriginFor<T>,
+            source_asset: AssetIdOf<T>,
+            target_asset: AssetIdOf<T>,
+            source_amount: BalanceOf<T>,
+            target_amount: BalanceOf<T>,
+            min_fill: BalanceOf<T>,
+            target_parachain_id: u32,
+            timelock: BlockNumberFor<T>,
+            lifetime: BlockNumberFor<T>,
+        ) -> DispatchResult {
+            let maker = ensure_signed(origin)?;
+            Self::ensure_valid_offer_terms(target_parachain_id, timelock)?;
+            ensure!(
+                !lifetime.is_zero() && lifetime <= T::MaxOfferLifetime::get(),
+                Error::<T>::InvalidOfferLifetime
+            );
+            ensure!(
+                !min_fill.is_zero() && min_fill <= source_amount,
+                Error::<T>::InvalidFillAmount
+            );
+            let offer_id = Self::next_swap_id(&maker, &(source_asset.clone(), source_amount, min_fill));
+
+            T::Assets::transfer(
+                source_asset.clone(),
+                &maker,
+                &Self::account_id(),
+                source_amount,
+                Preservation::Expendable,
+            )?;
+            let now = T::TimelockProvider::current_block_number();
+            let expires_at = now.saturating_add(lifetime);
+            Offers::<T>::insert(offer_id, SwapOffer {
+                id: offer_id,
+                maker: maker.clone(),
+                source_asset: source_asset.clone(),
+                target_asset: target_asset.clone(),
+                source_amount,
+                target_amount,
+                target_parachain_id,
+                remaining_amount: source_amount,
+                min_fill,
+                hash_lock: None,
+                timelock,
+                created_at: now,
+                expires_at,
+                status: OfferStatus::Open,
+            });
+
+            Self::deposit_event(Event::OfferPosted {
+                offer_id,
+                maker,
+                source_asset,
+                target_asset,
+                source_amount,
+                target_amount,
+                target_parachain_id,
+                expires_at,
+            });
+
+            Ok(())
+        }
+
+        /// Accept the whole remainder of an open swap offer
+        ///
+        /// Starts a swap with the caller as counterparty, who then participates on the
+        /// target chain.
+        #[pallet::call_index(5)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn accept_offer(origin: OriginFor<T>, offer_id: T::Hash) -> DispatchResult {
+            let taker = ensure_signed(origin)?;
+            let offer = Offers::<T>::get(offer_id).ok_or(Error::<T>::OfferNotFound)?;
+            let hash_lock = offer.hash_lock.ok_or(Error::<T>::WrongOfferKind)?;
+            Self::do_fill(taker, offer_id, offer.remaining_amount, hash_lock, SwapLeg::Initiator)
+        }
+
+        /// Take `amount` of the source asset out of an open partially fillable offer
+        ///
+        /// The caller owes a proportional share of the target amount, rounded up. The swap is
+        /// locked with `hash_lock`, whose secret the caller reveals by claiming the maker's leg
+        /// once they have locked their own.
+        #[pallet::call_index(8)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn fill_offer(
+            origin: OriginFor<T>,
+            offer_id: T::Hash,
+            amount: BalanceOf<T>,
+            hash_lock: [u8; 64],
+        ) -> DispatchResult {
+            let taker = ensure_signed(origin)?;
+            let offer = Offers::<T>::get(offer_id).ok_or(Error::<T>::OfferNotFound)?;
+            ensure!(offer.hash_lock.is_none(), Error::<T>::WrongOfferKind);
+            Self::do_fill(taker, offer_id, amount, hash_lock, SwapLeg::Participant)
+        }
+
+        /// Cancel an open swap offer and refund the maker
+        ///
+        /// The maker can cancel at any time; anyone can cancel once the offer expired.
+        #[pallet::call_index(6)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn cancel_offer(origin: OriginFor<T>, offer_id: T::Hash) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Offers::<T>::try_mutate_exists(offer_id, |maybe_offer| -> DispatchResult {
+                let offer = maybe_offer.take().ok_or(Error::<T>::OfferNotFound)?;
+                ensure!(offer.status == OfferStatus::Open, Error::<T>::OfferNotOpen);
+                ensure!(
+                    who == offer.maker || T::TimelockProvider::current_block_number() >= offer.expires_at,
+                    Error::<T>::NotOfferMaker
+                );
+
+                T::Assets::transfer(
+                    offer.source_asset,
+                    &Self::account_id(),
+                    &offer.maker,
+                    offer.remaining_amount,
+                    Preservation::Expendable,
+                )?;
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::OfferCanceled { offer_id });
+            Ok(())
+        }
+
+        /// Lock funds for one leg of a scriptless swap
+        ///
+        /// The funds go to `beneficiary` on presentation of a signature of the claim message by
+        /// `signer`, or back to the caller after `timelock`.
+        #[pallet::call_index(9)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn lock_adaptor_swap(
+            origin: OriginFor<T>,
+            beneficiary: T::AccountId,
+            asset: AssetIdOf<T>,
+            amount: BalanceOf<T>,
+            signer: [u8; 32],
+            timelock: BlockNumberFor<T>,
+        ) -> DispatchResult {
+            let depositor = ensure_signed(origin)?;
+            ensure!(timelock >= T::MinTimelock::get(), Error::<T>::TimelockTooShort);
+            ensure!(timelock <= T::MaxTimelock::get(), Error::<T>::TimelockTooLong);
+            let swap_id = Self::next_swap_id(&depositor, &signer);
+
+            T::Assets::transfer(
+                asset.clone(),
+                &depositor,
+                &Self::account_id(),
+                amount,
+                Preservation::Expendable,
+            )?;
+            let now = T::TimelockProvider::current_block_number();
+            let expires_at = now.saturating_add(timelock);
+            AdaptorSwaps::<T>::insert(swap_id, AdaptorSwap {
+                id: swap_id,
+                depositor: depositor.clone(),
+                beneficiary: beneficiary.clone(),
+                asset,
+                amount,
+                signer,
+                created_at: now,
+                expires_at,
+                status: SwapStatus::Pending,
+            });
+
+            Self::deposit_event(Event::AdaptorSwapLocked { swap_id, depositor, beneficiary, expires_at });
+            Ok(())
+        }
+
+        /// Claim a scriptless swap leg with the signer's completed signature
+        #[pallet::call_index(10)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn claim_adaptor_swap(
+            origin: OriginFor<T>,
+            swap_id: T::Hash,
+            signature: [u8; 64],
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            AdaptorSwaps::<T>::try_mutate_exists(swap_id, |maybe_swap| -> DispatchResult {
+                let swap = maybe_swap.take().ok_or(Error::<T>::SwapNotFound)?;
+                ensure!(swap.status == SwapStatus::Pending, Error::<T>::InvalidSwapState);
+                ensure!(
+                    T::TimelockProvider::current_block_number() < swap.expires_at,
+                    Error::<T>::SwapExpired
+                );
+
+                let message = adaptor_claim_message(T::SelfParaId::get(), &swap_id, &swap.beneficiary);
+                ensure!(
+                    sp_io::crypto::sr25519_verify(
+                        &sr25519::Signature::from_raw(signature),
+                        &message,
+                        &sr25519::Public::from_raw(swap.signer),
+                    ),
+                    Error::<T>::InvalidSignature
+                );
+
+                T::Assets::transfer(
+                    swap.asset,
+                    &Self::account_id(),
+                    &swap.beneficiary,
+                    swap.amount,
+                    Preservation::Expendable,
+                )?;
+
+                Self::deposit_event(Event::AdaptorSwapClaimed {
+                    swap_id,
+                    beneficiary: swap.beneficiary,
+                    signature,
+                });
+                Ok(())
+            })
+        }
+
+        /// Refund an expired scriptless swap leg
+        #[pallet::call_index(11)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn refund_adaptor_swap(origin: OriginFor<T>, swap_id: T::Hash) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            AdaptorSwaps::<T>::try_mutate_exists(swap_id, |maybe_swap| -> DispatchResult {
+                let swap = maybe_swap.take().ok_or(Error::<T>::SwapNotFound)?;
+                ensure!(swap.status == SwapStatus::Pending, Error::<T>::InvalidSwapState);
+                ensure!(
+                    T::TimelockProvider::current_block_number() >= swap.expires_at,
+                    Error::<T>::SwapNotExpired
+                );
+
+                T::Assets::transfer(
+                    swap.asset,
+                    &Self::account_id(),
+                    &swap.depositor,
+                    swap.amount,
+                    Preservation::Expendable,
+                )?;
+
+                Self::deposit_event(Event::AdaptorSwapRefunded { swap_id, who: swap.depositor });
+                Ok(())
+            })
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Escrow account holding locked swap funds
+        pub fn account_id() -> T::AccountId {
+            T::PalletId::get().into_account_truncating()
+        }
+
+        /// Open, unexpired offers matching `filter` among the next `limit` stored offers after
+        /// `start_after`
+        ///
+        /// At most [`MAX_OFFERS_PAGE`] offers are scanned, so a page may hold fewer matches than
+        /// remain; keep listing from `next` until it is `None`.
+        pub fn open_offers(
+            filter: OfferFilter<T::AccountId, AssetIdOf<T>>,
+            start_after: Option<T::Hash>,
+            limit: u32,
+        ) -> OffersPageOf<T> {
+            let now = T::TimelockProvider::current_block_number();
+            let mut stored = match start_after {
+                Some(offer_id) => Offers::<T>::iter_from(Offers::<T>::hashed_key_for(offer_id)),
+                None => Offers::<T>::iter(),
+            };
+            let scanned: Vec<OfferOf<T>> = stored
+                .by_ref()
+                .take(limit.clamp(1, MAX_OFFERS_PAGE) as usize)
+                .map(|(_, offer)| offer)
+                .collect();
+            let next = match stored.next() {
+                Some(_) => scanned.last().map(|offer| offer.id),
+                None => None,
+            };
+
+            let offers = scanned
+                .into_iter()
+                .filter(|offer| offer.status == OfferStatus::Open && now < offer.expires_at)
+                .filter(|offer| filter.matches(offer))
+                .collect();
+            OffersPage { offers, next }
+        }
+
+        /// Check the target chain and initiator timelock of a new swap
+        fn ensure_valid_terms(target_parachain_id: u32, timelock: BlockNumberFor<T>) -> DispatchResult {
+            ensure!(target_parachain_id != T::SelfParaId::get(), Error::<T>::SameParachain);
+            ensure!(
+                timelock >= T::SafetyMargin::get().saturating_add(T::MinTimelock::get()),
+                Error::<T>::TimelockTooShort
+            );
+            ensure!(timelock <= T::MaxTimelock::get(), Error::<T>::TimelockTooLong);
+            Ok(())
+        }
+
+        /// Check the target chain and swap timelock of a new offer
+        ///
+        /// The taker may lock the target leg as late as `AcceptanceTimeout` after the fill, which
+        /// must still leave that leg `MinTimelock` to run.
+        fn ensure_valid_offer_terms(target_parachain_id: u32, timelock: BlockNumberFor<T>) -> DispatchResult {
+            Self::ensure_valid_terms(target_parachain_id, timelock)?;
+            ensure!(
+                timelock >= T::AcceptanceTimeout::get().saturating_add(T::MinTimelock::get()),
+                Error::<T>::TimelockTooShort
+            );
+            Ok(())
+        }
+
+        /// Derive a fresh swap ID
+        fn next_swap_id(initiator: &T::AccountId, salt: &impl Encode) -> T::Hash {
+            let nonce = NextSwapNonce::<T>::mutate(|nonce| {
+                let current = *nonce;
+                *nonce = nonce.wrapping_add(1);
+                current
+            });
+            T::Hashing::hash_of(&(T::SelfParaId::get(), initiator, salt, nonce))
+        }
+
+        /// Start a swap taking `amount` out of an offer, with `taker` as counterparty
+        fn do_fill(
+            taker: T::AccountId,
+            offer_id: T::Hash,
+            amount: BalanceOf<T>,
+            hash_lock: [u8; 64],
+            revealer: SwapLeg,
+        ) -> DispatchResult {
+            let now = T::TimelockProvider::current_block_number();
+
+            let (offer, target_amount) =
+                Offers::<T>::try_mutate_exists(offer_id, |maybe_offer| -> Result<_, DispatchError> {
+                    let offer = maybe_offer.as_mut().ok_or(Error::<T>::OfferNotFound)?;
+                    ensure!(offer.status == OfferStatus::Open, Error::<T>::OfferNotOpen);
+                    ensure!(now < offer.expires_at, Error::<T>::OfferExpired);
+                    ensure!(offer.maker != taker, Error::<T>::CannotAcceptOwnOffer);
+                    ensure!(
+                        !amount.is_zero() &&
+                            amount <= offer.remaining_amount &&
+                            (amount >= offer.min_fill || amount == offer.remaining_amount),
+                        Error::<T>::InvalidFillAmount
+                    );
+
+                    let target_amount = fill_target_amount(amount, offer.source_amount, offer.target_amount)
+                        .ok_or(Error::<T>::Overflow)?;
+                    let filled = offer.clone();
+
+                    // An offer taken in full is done with
+                    offer.remaining_amount = offer.remaining_amount.saturating_sub(amount);
+                    if offer.remaining_amount.is_zero() {
+                        *maybe_offer = None;
+                    }
+                    Ok((filled, target_amount))
+                })?;
+
+            let swap_id = Self::next_swap_id(&offer.maker, &hash_lock);
+            Self::start_swap(AtomicSwap {
+                id: swap_id,
+                initiator: offer.maker,
+                counterparty: taker.clone(),
+                source_asset: offer.source_asset,
+                target_asset: offer.target_asset,
+                source_amount: amount,
+                target_amount,
+                source_parachain_id: T::SelfParaId::get(),
+                target_parachain_id: offer.target_parachain_id,
+                hash_lock,
+                created_at: now,
+                expires_at: now.saturating_add(offer.timelock),
+                participate_by: now.saturating_add(T::AcceptanceTimeout::get()),
+                status: SwapStatus::Pending,
+                leg: SwapLeg::Initiator,
+                revealer,
+            })?;
+
+            Self::deposit_event(Event::OfferFilled {
+                offer_id,
+                swap_id,
+                taker,
+                source_amount: amount,
+                target_amount,
+                remaining_amount: offer.remaining_amount.saturating_sub(amount),
+            });
+            Ok(())
+        }
+
+        /// Record a funded initiator leg and notify the target chain
+        fn start_swap(swap: SwapOf<T>) -> DispatchResult {
+            ensure!(!Swaps::<T>::contains_key(swap.id), Error::<T>::SwapAlreadyExists);
+            Swaps::<T>::insert(swap.id, &swap);
+
+            T::Messenger::send_message(
+                swap.target_parachain_id,
+                XcmMessageType::AtomicSwap,
+                SwapMessageOf::<T>::Initiated(swap.clone()).encode(),
+            )?;
+
+            Self::deposit_event(Event::SwapInitiated {
+                swap_id: swap.id,
+                initiator: swap.initiator,
+                counterparty: swap.counterparty,
+                target_parachain_id: swap.target_parachain_id,
+                expires_at: swap.expires_at,
+            });
+            Ok(())
+        }
+
+        /// Pay out the leg held on this chain, returning the parachain holding the other leg
+        fn do_claim(swap_id: T::Hash, secret: [u8; 32]) -> Result<u32, DispatchError> {
+            Swaps::<T>::try_mutate_exists(swap_id, |maybe_swap| {
+                let swap = maybe_swap.take().ok_or(Error::<T>::SwapNotFound)?;
+                ensure!(
+                    matches!(swap.status, SwapStatus::Pending | SwapStatus::InProgress),
+                    Error::<T>::InvalidSwapState
+                );
+                // A counterparty holding the secret must lock their leg before claiming this one
+                ensure!(
+                    swap.revealer == SwapLeg::Initiator || swap.status == SwapStatus::InProgress,
+                    Error::<T>::InvalidSwapState
+                );
+                ensure!(verify_swap_secret(&secret, &swap.hash_lock), Error::<T>::InvalidSecret);
+                ensure!(
+                    T::TimelockProvider::current_block_number() < swap.expires_at,
+                    Error::<T>::SwapExpired
+                );
+
+                let remote_parachain_id = swap.remote_parachain_id();
+                let (asset, amount, beneficiary) = match swap.leg {
+                    SwapLeg::Initiator => (swap.source_asset, swap.source_amount, swap.counterparty),
+                    SwapLeg::Participant => (swap.target_asset, swap.target_amount, swap.initiator),
+                };
+                T::Assets::transfer(asset, &Self::account_id(), &beneficiary, amount, Preservation::Expendable)?;
+
+                Self::deposit_event(Event::SwapClaimed { swap_id, beneficiary, secret });
+                Ok(remote_parachain_id)
+            })
+        }
+    }
+
+    impl<T: Config> SwapMessageHandler for Pallet<T> {
+        fn handle_swap_message(source_parachain_id: u32, message_data: &[u8]) -> DispatchResult {
+            let message = SwapMessageOf::<T>::decode(&mut &message_data[..])
+                .map_err(|_| Error::<T>::MalformedMessage)?;
+
+            match message {
+                SwapMessage::Initiated(swap) => {
+                    ensure!(
+                        swap.leg == SwapLeg::Initiator &&
+                            swap.source_parachain_id == source_parachain_id &&
+                            swap.target_parachain_id == T::SelfParaId::get(),
+                        Error::<T>::UnexpectedParachain
+                    );
+                    ensure!(
+                        !SwapNotices::<T>::contains_key(swap.id) && !Swaps::<T>::contains_key(swap.id),
+                        Error::<T>::SwapAlreadyExists
+                    );
+
+                    SwapNotices::<T>::insert(swap.id, &swap);
+                    Self::deposit_event(Event::SwapNoticeReceived {
+                        swap_id: swap.id,
+                        source_parachain_id,
+                        counterparty: swap.counterparty,
+                    });
+                },
+                SwapMessage::Participated { swap_id, expires_at } => {
+                    Swaps::<T>::try_mutate(swap_id, |maybe_swap| -> DispatchResult {
+                        let swap = maybe_swap.as_mut().ok_or(Error::<T>::SwapNotFound)?;
+                        ensure!(
+                            swap.leg == SwapLeg::Initiator && swap.target_parachain_id == source_parachain_id,
+                            Error::<T>::UnexpectedParachain
+                        );
+                        ensure!(swap.status == SwapStatus::Pending, Error::<T>::InvalidSwapState);
+                        // The leg claimed second must outlive the leg claimed first by long enough
+                        // for the relay to carry the secret over
+                        match swap.revealer {
+                            SwapLeg::Initiator => ensure!(
+                                expires_at.saturating_add(T::SafetyMargin::get()) <= swap.expires_at,
+                                Error::<T>::TimelockTooLong
+                            ),
+                            SwapLeg::Participant => ensure!(
+                                expires_at >= swap.expires_at.saturating_add(T::SafetyMargin::get()),
+                                Error::<T>::TimelockTooShort
+                            ),
+                        }
+
+                        swap.status = SwapStatus::InProgress;
+                        Ok(())
+                    })?;
+
+                    Self::deposit_event(Event::CounterpartyLocked { swap_id, expires_at });
+                },
+                SwapMessage::SecretRevealed { swap_id, secret } => {
+                    // The leg on this chain exists before the secret can be revealed, so a missing
+                    // one was already settled by a direct claim on this chain
+                    let Some(swap) = Swaps::<T>::get(swap_id) else {
+                        return Ok(());
+                    };
+                    ensure!(
+                        swap.remote_parachain_id() == source_parachain_id,
+                        Error::<T>::UnexpectedParachain
+                    );
+
+                    Self::do_claim(swap_id, secret)?;
+                },
+            }
+
+            Ok(())
+        }
+    }
+}
+
+sp_api::decl_runtime_apis! {
+    /// Runtime API for discovering atomic swap counterparties
+    pub trait AtomicSwapApi<AccountId, AssetId, Balance, BlockNumber, Hash> where
+        AccountId: codec::Codec,
+        AssetId: codec::Codec,
+        Balance: codec::Codec,
+        BlockNumber: codec::Codec,
+        Hash: codec::Codec,
+    {
+        /// Open, unexpired offers matching `filter` among the next `limit` stored offers after
+        /// `start_after`, at most [`MAX_OFFERS_PAGE`]
+        fn open_offers(
+            filter: OfferFilter<AccountId, AssetId>,
+            start_after: Option<Hash>,
+            limit: u32,
+        ) -> OffersPage<AccountId, AssetId, Balance, BlockNumber, Hash>;
+    }
 }
@@ -0,0 +1,180 @@
+//! Test environment for the atomic swap pallet
+//!
+//! Each [`Chain`] is a separate externalities instance standing in for one parachain. Swap
+//! messages sent by either chain are queued in an outbox and delivered with [`relay`], and both
+//! chains read the same relay chain block number from [`RelayBlock`].
+
+use crate as pallet_atomic_swap;
+use crate::*;
+
+use frame_support::{
+    construct_runtime, derive_impl, parameter_types,
+    traits::{AsEnsureOriginWithArg, ConstU32, ConstU64},
+    PalletId,
+};
+use pallet_cross_chain::{CrossChainMessenger, SwapMessageHandler, XcmMessageType};
+use sp_runtime::{traits::BlockNumberProvider, BuildStorage, DispatchResult};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+construct_runtime!(
+    pub enum Test {
+        System: frame_system,
+        Balances: pallet_balances,
+        Assets: pallet_assets,
+        Swap: pallet_atomic_swap,
+    }
+);
+
+pub type AccountId = u64;
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+pub const CHARLIE: AccountId = 3;
+
+/// Asset sold by makers on the source chain
+pub const SOURCE_ASSET: u32 = 1;
+/// Asset paid by takers on the target chain
+pub const TARGET_ASSET: u32 = 2;
+
+pub const SOURCE_PARA: u32 = 2000;
+pub const TARGET_PARA: u32 = 2001;
+
+pub const INITIAL_BALANCE: u64 = 1_000_000;
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+    type AccountData = pallet_balances::AccountData<u64>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig as pallet_balances::DefaultConfig)]
+impl pallet_balances::Config for Test {
+    type AccountStore = System;
+}
+
+impl pallet_assets::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Balance = u64;
+    type AssetId = u32;
+    type AssetIdParameter = u32;
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
+    type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+    type AssetDeposit = ConstU64<1>;
+    type AssetAccountDeposit = ConstU64<1>;
+    type MetadataDepositBase = ConstU64<1>;
+    type MetadataDepositPerByte = ConstU64<1>;
+    type ApprovalDeposit = ConstU64<1>;
+    type StringLimit = ConstU32<50>;
+    type Freezer = ();
+    type WeightInfo = ();
+    type CallbackHandle = ();
+    type Extra = ();
+    type RemoveItemsLimit = ConstU32<5>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = ();
+}
+
+parameter_types! {
+    pub const AtomicSwapPalletId: PalletId = PalletId(*b"mm/aswap");
+    pub static SelfParaId: u32 = SOURCE_PARA;
+    pub static RelayBlock: u64 = 1;
+    /// Messages sent by any chain: source, target, type and payload
+    static Outbox: Vec<(u32, u32, XcmMessageType, Vec<u8>)> = Vec::new();
+}
+
+/// Queues messages in the [`Outbox`] until [`relay`] delivers them
+pub struct MockMessenger;
+impl CrossChainMessenger for MockMessenger {
+    fn send_message(target_parachain_id: u32, message_type: XcmMessageType, message_data: Vec<u8>) -> DispatchResult {
+        Outbox::mutate(|outbox| outbox.push((SelfParaId::get(), target_parachain_id, message_type, message_data)));
+        Ok(())
+    }
+}
+
+/// Relay chain clock shared by both chains
+pub struct MockRelayClock;
+impl BlockNumberProvider for MockRelayClock {
+    type BlockNumber = u64;
+
+    fn current_block_number() -> u64 {
+        RelayBlock::get()
+    }
+}
+
+impl Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Assets = Assets;
+    type Messenger = MockMessenger;
+    type SelfParaId = SelfParaId;
+    type TimelockProvider = MockRelayClock;
+    type PalletId = AtomicSwapPalletId;
+    type MinTimelock = ConstU64<10>;
+    type MaxTimelock = ConstU64<1_000>;
+    type SafetyMargin = ConstU64<5>;
+    type MaxOfferLifetime = ConstU64<100>;
+    type AcceptanceTimeout = ConstU64<20>;
+}
+
+/// One parachain holding a leg of each swap
+pub struct Chain {
+    pub para_id: u32,
+    ext: sp_io::TestExternalities,
+}
+
+impl Chain {
+    /// Chain where ALICE, BOB and CHARLIE each hold `INITIAL_BALANCE` of both assets
+    pub fn new(para_id: u32) -> Self {
+        let accounts = [ALICE, BOB, CHARLIE];
+        let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        pallet_assets::GenesisConfig::<Test> {
+            assets: vec![(SOURCE_ASSET, ALICE, true, 1), (TARGET_ASSET, ALICE, true, 1)],
+            metadata: vec![],
+            accounts: [SOURCE_ASSET, TARGET_ASSET]
+                .into_iter()
+                .flat_map(|asset| accounts.map(|who| (asset, who, INITIAL_BALANCE)))
+                .collect(),
+        }
+        .assimilate_storage(&mut storage)
+        .unwrap();
+
+        let mut ext = sp_io::TestExternalities::new(storage);
+        ext.execute_with(|| System::set_block_number(1));
+        Self { para_id, ext }
+    }
+
+    /// Run `f` as this chain
+    pub fn execute_with<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        SelfParaId::set(self.para_id);
+        self.ext.execute_with(f)
+    }
+}
+
+/// Source and target chains of a swap, starting at relay block 1 with an empty outbox
+pub fn new_test_chains() -> (Chain, Chain) {
+    RelayBlock::set(1);
+    Outbox::take();
+    (Chain::new(SOURCE_PARA), Chain::new(TARGET_PARA))
+}
+
+/// Deliver every queued swap message to its target chain
+pub fn relay(chains: &mut [&mut Chain]) {
+    while !Outbox::get().is_empty() {
+        for (source, target, message_type, data) in Outbox::take() {
+            assert_eq!(message_type, XcmMessageType::AtomicSwap);
+            let chain = chains
+                .iter_mut()
+                .find(|chain| chain.para_id == target)
+                .expect("message sent to an unknown chain");
+            chain
+                .execute_with(|| Swap::handle_swap_message(source, &data))
+                .expect("swap message rejected");
+        }
+    }
+}
+
+/// Move the shared relay chain clock forward by `blocks`
+pub fn advance_relay(blocks: u64) {
+    RelayBlock::mutate(|block| *block += blocks);
+}
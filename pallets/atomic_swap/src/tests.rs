@@ -0,0 +1,656 @@
+use crate::{mock::*, *};
+use frame_support::{assert_noop, assert_ok, traits::fungibles::Inspect};
+
+const SECRET: [u8; 32] = [7; 32];
+const SOURCE_AMOUNT: u64 = 1_000;
+const TARGET_AMOUNT: u64 = 2_000;
+const TIMELOCK: u64 = 100;
+
+fn balance(asset: u32, who: AccountId) -> u64 {
+    <Assets as Inspect<AccountId>>::balance(asset, &who)
+}
+
+fn last_swap_id() -> <Test as frame_system::Config>::Hash {
+    System::events()
+        .into_iter()
+        .rev()
+        .find_map(|record| match record.event {
+            RuntimeEvent::Swap(Event::SwapInitiated { swap_id, .. }) => Some(swap_id),
+            _ => None,
+        })
+        .expect("no swap initiated")
+}
+
+fn last_offer_id() -> <Test as frame_system::Config>::Hash {
+    System::events()
+        .into_iter()
+        .rev()
+        .find_map(|record| match record.event {
+            RuntimeEvent::Swap(Event::OfferPosted { offer_id, .. }) => Some(offer_id),
+            _ => None,
+        })
+        .expect("no offer posted")
+}
+
+/// ALICE initiates a swap of SOURCE_AMOUNT for TARGET_AMOUNT with BOB; returns the swap ID
+fn initiated_swap(source: &mut Chain, target: &mut Chain) -> <Test as frame_system::Config>::Hash {
+    let swap_id = source.execute_with(|| {
+        assert_ok!(Swap::initiate_swap(
+            RuntimeOrigin::signed(ALICE),
+            BOB,
+            SOURCE_ASSET,
+            TARGET_ASSET,
+            SOURCE_AMOUNT,
+            TARGET_AMOUNT,
+            TARGET_PARA,
+            quantum_resistant_hash(&SECRET),
+            TIMELOCK,
+        ));
+        assert_eq!(balance(SOURCE_ASSET, ALICE), INITIAL_BALANCE - SOURCE_AMOUNT);
+        last_swap_id()
+    });
+    relay(&mut [source, target]);
+    swap_id
+}
+
+fn claimed_secret(swap_id: <Test as frame_system::Config>::Hash) -> Option<[u8; 32]> {
+    System::events().into_iter().find_map(|record| match record.event {
+        RuntimeEvent::Swap(Event::SwapClaimed { swap_id: id, secret, .. }) if id == swap_id => Some(secret),
+        _ => None,
+    })
+}
+
+#[test]
+fn claim_relays_the_secret_to_the_initiator_leg() {
+    let (mut source, mut target) = new_test_chains();
+    let swap_id = initiated_swap(&mut source, &mut target);
+
+    target.execute_with(|| {
+        assert_eq!(SwapNotices::<Test>::get(swap_id).unwrap().counterparty, BOB);
+        assert_noop!(
+            Swap::participate_swap(RuntimeOrigin::signed(CHARLIE), swap_id),
+            Error::<Test>::NotCounterparty
+        );
+        assert_ok!(Swap::participate_swap(RuntimeOrigin::signed(BOB), swap_id));
+        // The participant leg expires a safety margin before the initiator leg
+        assert_eq!(Swaps::<Test>::get(swap_id).unwrap().expires_at, 1 + TIMELOCK - 5);
+    });
+    relay(&mut [&mut source, &mut target]);
+
+    target.execute_with(|| {
+        assert_ok!(Swap::claim_swap(RuntimeOrigin::signed(ALICE), swap_id, SECRET));
+        assert!(!Swaps::<Test>::contains_key(swap_id));
+        assert_eq!(balance(TARGET_ASSET, ALICE), INITIAL_BALANCE + TARGET_AMOUNT);
+        assert_eq!(claimed_secret(swap_id), Some(SECRET));
+    });
+    relay(&mut [&mut source, &mut target]);
+
+    // The relayed secret releases the initiator leg to BOB
+    source.execute_with(|| {
+        assert!(!Swaps::<Test>::contains_key(swap_id));
+        assert_eq!(balance(SOURCE_ASSET, BOB), INITIAL_BALANCE + SOURCE_AMOUNT);
+        assert_eq!(balance(SOURCE_ASSET, Swap::account_id()), 0);
+        assert_eq!(claimed_secret(swap_id), Some(SECRET));
+    });
+}
+
+#[test]
+fn claim_rejects_the_wrong_secret() {
+    let (mut source, mut target) = new_test_chains();
+    let swap_id = initiated_swap(&mut source, &mut target);
+
+    target.execute_with(|| {
+        assert_ok!(Swap::participate_swap(RuntimeOrigin::signed(BOB), swap_id));
+        assert_noop!(
+            Swap::claim_swap(RuntimeOrigin::signed(ALICE), swap_id, [8; 32]),
+            Error::<Test>::InvalidSecret
+        );
+    });
+    source.execute_with(|| {
+        assert_noop!(
+            Swap::claim_swap(RuntimeOrigin::signed(BOB), swap_id, [8; 32]),
+            Error::<Test>::InvalidSecret
+        );
+    });
+}
+
+#[test]
+fn expired_legs_cannot_be_claimed_and_are_refunded() {
+    let (mut source, mut target) = new_test_chains();
+    let swap_id = initiated_swap(&mut source, &mut target);
+
+    target.execute_with(|| {
+        assert_ok!(Swap::participate_swap(RuntimeOrigin::signed(BOB), swap_id));
+    });
+    relay(&mut [&mut source, &mut target]);
+
+    target.execute_with(|| {
+        assert_noop!(
+            Swap::refund_swap(RuntimeOrigin::signed(BOB), swap_id),
+            Error::<Test>::SwapNotExpired
+        );
+        advance_relay(TIMELOCK - 5);
+        assert_noop!(
+            Swap::claim_swap(RuntimeOrigin::signed(ALICE), swap_id, SECRET),
+            Error::<Test>::SwapExpired
+        );
+        assert_ok!(Swap::refund_swap(RuntimeOrigin::signed(CHARLIE), swap_id));
+        assert_eq!(balance(TARGET_ASSET, BOB), INITIAL_BALANCE);
+    });
+
+    source.execute_with(|| {
+        assert_noop!(
+            Swap::refund_swap(RuntimeOrigin::signed(ALICE), swap_id),
+            Error::<Test>::SwapNotExpired
+        );
+        advance_relay(5);
+        assert_noop!(
+            Swap::claim_swap(RuntimeOrigin::signed(BOB), swap_id, SECRET),
+            Error::<Test>::SwapExpired
+        );
+        assert_ok!(Swap::refund_swap(RuntimeOrigin::signed(ALICE), swap_id));
+        assert!(!Swaps::<Test>::contains_key(swap_id));
+        assert_eq!(balance(SOURCE_ASSET, ALICE), INITIAL_BALANCE);
+        assert_noop!(
+            Swap::refund_swap(RuntimeOrigin::signed(ALICE), swap_id),
+            Error::<Test>::SwapNotFound
+        );
+    });
+}
+
+#[test]
+fn unparticipated_swap_is_refunded_after_the_timelock() {
+    let (mut source, mut target) = new_test_chains();
+    let swap_id = initiated_swap(&mut source, &mut target);
+
+    source.execute_with(|| {
+        advance_relay(TIMELOCK - 1);
+        assert_noop!(
+            Swap::refund_swap(RuntimeOrigin::signed(ALICE), swap_id),
+            Error::<Test>::SwapNotExpired
+        );
+        advance_relay(1);
+        assert_ok!(Swap::refund_swap(RuntimeOrigin::signed(ALICE), swap_id));
+        assert_eq!(balance(SOURCE_ASSET, ALICE), INITIAL_BALANCE);
+    });
+
+    // Participation is closed a safety margin before the initiator leg expires
+    target.execute_with(|| {
+        assert_noop!(
+            Swap::participate_swap(RuntimeOrigin::signed(BOB), swap_id),
+            Error::<Test>::SwapExpired
+        );
+    });
+}
+
+/// ALICE offers SOURCE_AMOUNT for TARGET_AMOUNT and BOB accepts; returns the swap ID
+fn accepted_offer(source: &mut Chain, target: &mut Chain) -> <Test as frame_system::Config>::Hash {
+    let swap_id = source.execute_with(|| {
+        assert_ok!(Swap::post_offer(
+            RuntimeOrigin::signed(ALICE),
+            SOURCE_ASSET,
+            TARGET_ASSET,
+            SOURCE_AMOUNT,
+            TARGET_AMOUNT,
+            TARGET_PARA,
+            quantum_resistant_hash(&SECRET),
+            TIMELOCK,
+            50,
+        ));
+        assert_ok!(Swap::accept_offer(RuntimeOrigin::signed(BOB), last_offer_id()));
+        last_swap_id()
+    });
+    relay(&mut [source, target]);
+    swap_id
+}
+
+#[test]
+fn accepted_offer_settles_both_legs() {
+    let (mut source, mut target) = new_test_chains();
+    let swap_id = accepted_offer(&mut source, &mut target);
+
+    target.execute_with(|| {
+        assert_ok!(Swap::participate_swap(RuntimeOrigin::signed(BOB), swap_id));
+    });
+    relay(&mut [&mut source, &mut target]);
+    source.execute_with(|| {
+        assert_eq!(Swaps::<Test>::get(swap_id).unwrap().status, SwapStatus::InProgress);
+    });
+
+    target.execute_with(|| {
+        assert_ok!(Swap::claim_swap(RuntimeOrigin::signed(ALICE), swap_id, SECRET));
+        assert_eq!(balance(TARGET_ASSET, ALICE), INITIAL_BALANCE + TARGET_AMOUNT);
+        assert_eq!(balance(TARGET_ASSET, BOB), INITIAL_BALANCE - TARGET_AMOUNT);
+    });
+    relay(&mut [&mut source, &mut target]);
+
+    source.execute_with(|| {
+        assert!(!Swaps::<Test>::contains_key(swap_id));
+        assert_eq!(balance(SOURCE_ASSET, ALICE), INITIAL_BALANCE - SOURCE_AMOUNT);
+        assert_eq!(balance(SOURCE_ASSET, BOB), INITIAL_BALANCE + SOURCE_AMOUNT);
+    });
+}
+
+#[test]
+fn maker_is_refunded_when_taker_does_not_participate() {
+    let (mut source, mut target) = new_test_chains();
+    let swap_id = accepted_offer(&mut source, &mut target);
+
+    source.execute_with(|| {
+        advance_relay(19);
+        assert_noop!(
+            Swap::refund_swap(RuntimeOrigin::signed(CHARLIE), swap_id),
+            Error::<Test>::SwapNotExpired
+        );
+
+        // The acceptance timeout ends long before the swap timelock
+        advance_relay(1);
+        assert_ok!(Swap::refund_swap(RuntimeOrigin::signed(CHARLIE), swap_id));
+        assert!(!Swaps::<Test>::contains_key(swap_id));
+        assert_eq!(balance(SOURCE_ASSET, ALICE), INITIAL_BALANCE);
+    });
+}
+
+#[test]
+fn participation_closes_a_safety_margin_before_the_acceptance_timeout() {
+    let (mut source, mut target) = new_test_chains();
+    let swap_id = accepted_offer(&mut source, &mut target);
+
+    target.execute_with(|| {
+        advance_relay(16);
+        assert_noop!(
+            Swap::participate_swap(RuntimeOrigin::signed(BOB), swap_id),
+            Error::<Test>::SwapExpired
+        );
+    });
+}
+
+#[test]
+fn participated_swap_is_not_refunded_early() {
+    let (mut source, mut target) = new_test_chains();
+    let swap_id = accepted_offer(&mut source, &mut target);
+
+    target.execute_with(|| {
+        advance_relay(15);
+        assert_ok!(Swap::participate_swap(RuntimeOrigin::signed(BOB), swap_id));
+    });
+    relay(&mut [&mut source, &mut target]);
+
+    source.execute_with(|| {
+        advance_relay(5);
+        assert_noop!(
+            Swap::refund_swap(RuntimeOrigin::signed(ALICE), swap_id),
+            Error::<Test>::SwapNotExpired
+        );
+
+        advance_relay(TIMELOCK);
+        assert_ok!(Swap::refund_swap(RuntimeOrigin::signed(ALICE), swap_id));
+    });
+}
+
+#[test]
+fn offer_timelock_must_cover_the_acceptance_timeout() {
+    let (mut source, _) = new_test_chains();
+
+    source.execute_with(|| {
+        assert_noop!(
+            Swap::post_offer(
+                RuntimeOrigin::signed(ALICE),
+                SOURCE_ASSET,
+                TARGET_ASSET,
+                SOURCE_AMOUNT,
+                TARGET_AMOUNT,
+                TARGET_PARA,
+                quantum_resistant_hash(&SECRET),
+                29,
+                50,
+            ),
+            Error::<Test>::TimelockTooShort
+        );
+    });
+}
+
+/// ALICE offers SOURCE_AMOUNT in fills of at least 100 and BOB takes `amount` with his own hash
+/// lock; returns the offer and swap IDs
+fn filled_offer(
+    source: &mut Chain,
+    target: &mut Chain,
+    amount: u64,
+) -> (<Test as frame_system::Config>::Hash, <Test as frame_system::Config>::Hash) {
+    let ids = source.execute_with(|| {
+        assert_ok!(Swap::post_partial_offer(
+            RuntimeOrigin::signed(ALICE),
+            SOURCE_ASSET,
+            TARGET_ASSET,
+            SOURCE_AMOUNT,
+            TARGET_AMOUNT,
+            100,
+            TARGET_PARA,
+            TIMELOCK,
+            50,
+        ));
+        let offer_id = last_offer_id();
+        assert_ok!(Swap::fill_offer(
+            RuntimeOrigin::signed(BOB),
+            offer_id,
+            amount,
+            quantum_resistant_hash(&SECRET),
+        ));
+        (offer_id, last_swap_id())
+    });
+    relay(&mut [source, target]);
+    ids
+}
+
+#[test]
+fn taker_reveals_their_own_secret_on_partial_fill() {
+    let (mut source, mut target) = new_test_chains();
+    let (offer_id, swap_id) = filled_offer(&mut source, &mut target, 400);
+
+    source.execute_with(|| {
+        assert_eq!(Offers::<Test>::get(offer_id).unwrap().remaining_amount, 600);
+        // The taker cannot take the maker's funds before locking their own
+        assert_noop!(
+            Swap::claim_swap(RuntimeOrigin::signed(BOB), swap_id, SECRET),
+            Error::<Test>::InvalidSwapState
+        );
+    });
+
+    target.execute_with(|| {
+        assert_ok!(Swap::participate_swap(RuntimeOrigin::signed(BOB), swap_id));
+        // The taker's leg outlives the maker's, since the taker claims first
+        let swap = Swaps::<Test>::get(swap_id).unwrap();
+        assert_eq!(swap.expires_at, 1 + TIMELOCK + 5);
+        assert_eq!(balance(TARGET_ASSET, BOB), INITIAL_BALANCE - 800);
+    });
+    relay(&mut [&mut source, &mut target]);
+
+    source.execute_with(|| {
+        assert_ok!(Swap::claim_swap(RuntimeOrigin::signed(BOB), swap_id, SECRET));
+        assert_eq!(balance(SOURCE_ASSET, BOB), INITIAL_BALANCE + 400);
+    });
+    relay(&mut [&mut source, &mut target]);
+
+    target.execute_with(|| {
+        assert!(!Swaps::<Test>::contains_key(swap_id));
+        assert_eq!(balance(TARGET_ASSET, ALICE), INITIAL_BALANCE + 800);
+    });
+}
+
+#[test]
+fn fills_use_each_takers_hash_lock() {
+    let (mut source, mut target) = new_test_chains();
+    let (offer_id, first) = filled_offer(&mut source, &mut target, 400);
+
+    source.execute_with(|| {
+        let charlie_lock = quantum_resistant_hash(&[9; 32]);
+        assert_ok!(Swap::fill_offer(RuntimeOrigin::signed(CHARLIE), offer_id, 600, charlie_lock));
+        let second = last_swap_id();
+
+        assert_eq!(Swaps::<Test>::get(first).unwrap().hash_lock, quantum_resistant_hash(&SECRET));
+        assert_eq!(Swaps::<Test>::get(second).unwrap().hash_lock, charlie_lock);
+        assert!(!Offers::<Test>::contains_key(offer_id));
+    });
+}
+
+#[test]
+fn offers_are_taken_only_the_way_they_were_posted() {
+    let (mut source, mut target) = new_test_chains();
+    let (partial, _) = filled_offer(&mut source, &mut target, 100);
+
+    source.execute_with(|| {
+        assert_noop!(Swap::accept_offer(RuntimeOrigin::signed(CHARLIE), partial), Error::<Test>::WrongOfferKind);
+
+        assert_ok!(Swap::post_offer(
+            RuntimeOrigin::signed(ALICE),
+            SOURCE_ASSET,
+            TARGET_ASSET,
+            SOURCE_AMOUNT,
+            TARGET_AMOUNT,
+            TARGET_PARA,
+            quantum_resistant_hash(&SECRET),
+            TIMELOCK,
+            50,
+        ));
+        assert_noop!(
+            Swap::fill_offer(RuntimeOrigin::signed(CHARLIE), last_offer_id(), 100, [0; 64]),
+            Error::<Test>::WrongOfferKind
+        );
+    });
+}
+
+/// `maker` offers SOURCE_AMOUNT of SOURCE_ASSET; returns the offer ID
+fn posted_offer(maker: AccountId) -> <Test as frame_system::Config>::Hash {
+    assert_ok!(Swap::post_offer(
+        RuntimeOrigin::signed(maker),
+        SOURCE_ASSET,
+        TARGET_ASSET,
+        SOURCE_AMOUNT,
+        TARGET_AMOUNT,
+        TARGET_PARA,
+        quantum_resistant_hash(&SECRET),
+        TIMELOCK,
+        50,
+    ));
+    last_offer_id()
+}
+
+#[test]
+fn canceled_offers_are_removed() {
+    let (mut source, _) = new_test_chains();
+
+    source.execute_with(|| {
+        let offer_id = posted_offer(ALICE);
+        assert_noop!(Swap::cancel_offer(RuntimeOrigin::signed(BOB), offer_id), Error::<Test>::NotOfferMaker);
+        assert_ok!(Swap::cancel_offer(RuntimeOrigin::signed(ALICE), offer_id));
+        assert!(!Offers::<Test>::contains_key(offer_id));
+        assert_eq!(balance(SOURCE_ASSET, ALICE), INITIAL_BALANCE);
+        assert_noop!(Swap::cancel_offer(RuntimeOrigin::signed(ALICE), offer_id), Error::<Test>::OfferNotFound);
+    });
+}
+
+#[test]
+fn open_offers_are_listed_a_page_at_a_time() {
+    let (mut source, _) = new_test_chains();
+
+    source.execute_with(|| {
+        let mut posted = vec![posted_offer(ALICE), posted_offer(BOB), posted_offer(ALICE)];
+        posted.sort();
+
+        // Walk the pages two stored offers at a time
+        let mut listed = Vec::new();
+        let mut start_after = None;
+        loop {
+            let page = Swap::open_offers(OfferFilter::default(), start_after, 2);
+            assert!(page.offers.len() <= 2);
+            listed.extend(page.offers.into_iter().map(|offer| offer.id));
+            match page.next {
+                Some(next) => start_after = Some(next),
+                None => break,
+            }
+        }
+        listed.sort();
+        assert_eq!(listed, posted);
+
+        // Pages scan at least one offer
+        let page = Swap::open_offers(OfferFilter::default(), None, 0);
+        assert_eq!(page.offers.len(), 1);
+        assert_eq!(page.next, Some(page.offers[0].id));
+
+        // Only offers matching the filter are listed, and pages are capped at MAX_OFFERS_PAGE
+        let alice = OfferFilter { maker: Some(ALICE), ..Default::default() };
+        let page = Swap::open_offers(alice.clone(), None, MAX_OFFERS_PAGE + 1);
+        assert_eq!(page.offers.len(), 2);
+        assert_eq!(page.next, None);
+        assert!(page.offers.iter().all(|offer| offer.maker == ALICE));
+
+        // Neither expired nor removed offers are listed
+        assert_ok!(Swap::cancel_offer(RuntimeOrigin::signed(ALICE), page.offers[0].id));
+        assert_eq!(Swap::open_offers(alice, None, 3).offers.len(), 1);
+        advance_relay(50);
+        assert!(Swap::open_offers(OfferFilter::default(), None, 3).offers.is_empty());
+    });
+}
+
+mod adaptor_swap {
+    use super::*;
+    use crate::adaptor::{self, PreSignature};
+    use curve25519_dalek::scalar::Scalar;
+    use rand_core::OsRng;
+    use schnorrkel::{ExpansionMode, Keypair, MiniSecretKey};
+
+    const ALICE_AMOUNT: u64 = 500;
+    const BOB_AMOUNT: u64 = 700;
+
+    fn keypair(seed: u8) -> Keypair {
+        MiniSecretKey::from_bytes(&[seed; 32]).unwrap().expand_to_keypair(ExpansionMode::Ed25519)
+    }
+
+    fn last_adaptor_swap_id() -> <Test as frame_system::Config>::Hash {
+        System::events()
+            .into_iter()
+            .rev()
+            .find_map(|record| match record.event {
+                RuntimeEvent::Swap(Event::AdaptorSwapLocked { swap_id, .. }) => Some(swap_id),
+                _ => None,
+            })
+            .expect("no adaptor swap locked")
+    }
+
+    fn claim_signature(swap_id: <Test as frame_system::Config>::Hash) -> [u8; 64] {
+        System::events()
+            .into_iter()
+            .find_map(|record| match record.event {
+                RuntimeEvent::Swap(Event::AdaptorSwapClaimed { swap_id: claimed, signature, .. })
+                    if claimed == swap_id =>
+                    Some(signature),
+                _ => None,
+            })
+            .expect("adaptor swap not claimed")
+    }
+
+    /// Lock `amount` of `asset` from `depositor` for `beneficiary`, released by `signer`
+    fn lock(
+        chain: &mut Chain,
+        depositor: AccountId,
+        beneficiary: AccountId,
+        asset: u32,
+        amount: u64,
+        signer: &Keypair,
+        timelock: u64,
+    ) -> <Test as frame_system::Config>::Hash {
+        chain.execute_with(|| {
+            assert_ok!(Swap::lock_adaptor_swap(
+                RuntimeOrigin::signed(depositor),
+                beneficiary,
+                asset,
+                amount,
+                signer.public.to_bytes(),
+                timelock,
+            ));
+            last_adaptor_swap_id()
+        })
+    }
+
+    fn pre_sign(
+        chain: &mut Chain,
+        signer: &Keypair,
+        swap_id: <Test as frame_system::Config>::Hash,
+        beneficiary: AccountId,
+        adaptor_point: &curve25519_dalek::ristretto::CompressedRistretto,
+    ) -> (Vec<u8>, PreSignature) {
+        let message = adaptor_claim_message(chain.para_id, &swap_id, &beneficiary);
+        let pre_signature = adaptor::pre_sign(signer, &message, adaptor_point, &mut OsRng).unwrap();
+        (message, pre_signature)
+    }
+
+    #[test]
+    fn scriptless_swap_settles_both_chains() {
+        let (mut chain_a, mut chain_b) = new_test_chains();
+        let (alice_key, bob_key) = (keypair(1), keypair(2));
+
+        // 1. Alice picks the adaptor secret; both lock, Bob's leg expiring first
+        let secret = Scalar::random(&mut OsRng);
+        let adaptor_point = adaptor::adaptor_point(&secret);
+        let alice_leg = lock(&mut chain_a, ALICE, BOB, SOURCE_ASSET, ALICE_AMOUNT, &alice_key, 60);
+        let bob_leg = lock(&mut chain_b, BOB, ALICE, TARGET_ASSET, BOB_AMOUNT, &bob_key, 30);
+
+        // 2. Each pre-signs their own leg's claim message and checks the other's
+        let (alice_message, alice_pre) = pre_sign(&mut chain_a, &alice_key, alice_leg, BOB, &adaptor_point);
+        let (bob_message, bob_pre) = pre_sign(&mut chain_b, &bob_key, bob_leg, ALICE, &adaptor_point);
+        assert!(adaptor::verify_pre_signature(&alice_key.public, &alice_message, &adaptor_point, &alice_pre));
+        assert!(adaptor::verify_pre_signature(&bob_key.public, &bob_message, &adaptor_point, &bob_pre));
+
+        // A pre-signature alone does not release the funds
+        chain_b.execute_with(|| {
+            let mut incomplete = [0u8; 64];
+            incomplete[..32].copy_from_slice(&bob_pre.nonce_commitment);
+            incomplete[32..].copy_from_slice(&bob_pre.s);
+            incomplete[63] |= 0x80;
+            assert_noop!(
+                Swap::claim_adaptor_swap(RuntimeOrigin::signed(ALICE), bob_leg, incomplete),
+                Error::<Test>::InvalidSignature
+            );
+        });
+
+        // 3. Alice completes Bob's pre-signature and claims on chain B
+        let bob_signature = chain_b.execute_with(|| {
+            let signature = adaptor::complete(&bob_pre, &secret).unwrap();
+            assert_ok!(Swap::claim_adaptor_swap(RuntimeOrigin::signed(ALICE), bob_leg, signature));
+            assert_eq!(balance(TARGET_ASSET, ALICE), INITIAL_BALANCE + BOB_AMOUNT);
+            claim_signature(bob_leg)
+        });
+
+        // 4. Bob extracts the secret from the published signature and claims on chain A
+        let extracted = adaptor::extract_secret(&bob_pre, &bob_signature).unwrap();
+        assert_eq!(extracted, secret);
+        chain_a.execute_with(|| {
+            let signature = adaptor::complete(&alice_pre, &extracted).unwrap();
+            assert_ok!(Swap::claim_adaptor_swap(RuntimeOrigin::signed(BOB), alice_leg, signature));
+            assert_eq!(balance(SOURCE_ASSET, BOB), INITIAL_BALANCE + ALICE_AMOUNT);
+            assert!(!AdaptorSwaps::<Test>::contains_key(alice_leg));
+        });
+    }
+
+    #[test]
+    fn unclaimed_legs_are_refunded_after_the_timelock() {
+        let (mut chain_a, mut chain_b) = new_test_chains();
+        let (alice_key, bob_key) = (keypair(1), keypair(2));
+        let secret = Scalar::random(&mut OsRng);
+        let adaptor_point = adaptor::adaptor_point(&secret);
+        let alice_leg = lock(&mut chain_a, ALICE, BOB, SOURCE_ASSET, ALICE_AMOUNT, &alice_key, 60);
+        let bob_leg = lock(&mut chain_b, BOB, ALICE, TARGET_ASSET, BOB_AMOUNT, &bob_key, 30);
+        let (_, bob_pre) = pre_sign(&mut chain_b, &bob_key, bob_leg, ALICE, &adaptor_point);
+
+        chain_b.execute_with(|| {
+            assert_noop!(
+                Swap::refund_adaptor_swap(RuntimeOrigin::signed(BOB), bob_leg),
+                Error::<Test>::SwapNotExpired
+            );
+
+            // Alice never claims, so Bob's leg returns to him once it expires
+            advance_relay(30);
+            let signature = adaptor::complete(&bob_pre, &secret).unwrap();
+            assert_noop!(
+                Swap::claim_adaptor_swap(RuntimeOrigin::signed(ALICE), bob_leg, signature),
+                Error::<Test>::SwapExpired
+            );
+            assert_ok!(Swap::refund_adaptor_swap(RuntimeOrigin::signed(CHARLIE), bob_leg));
+            assert_eq!(balance(TARGET_ASSET, BOB), INITIAL_BALANCE);
+        });
+
+        chain_a.execute_with(|| {
+            assert_noop!(
+                Swap::refund_adaptor_swap(RuntimeOrigin::signed(ALICE), alice_leg),
+                Error::<Test>::SwapNotExpired
+            );
+            advance_relay(30);
+            assert_ok!(Swap::refund_adaptor_swap(RuntimeOrigin::signed(ALICE), alice_leg));
+            assert_eq!(balance(SOURCE_ASSET, ALICE), INITIAL_BALANCE);
+            assert_noop!(
+                Swap::refund_adaptor_swap(RuntimeOrigin::signed(ALICE), alice_leg),
+                Error::<Test>::SwapNotFound
+            );
+        });
+    }
+}
@@ -0,0 +1,332 @@
+//! Error correction for cross-chain messages
+//!
+//! Payloads are framed with systematic Reed-Solomon erasure coding over GF(2^8). The payload
+//! is split into `data_shards` equally sized shards (zero padded), followed by `parity_shards`
+//! parity shards. Every shard carries a CRC-32, so corrupted shards are detected and treated
+//! as erasures: the payload is recovered as long as at most `parity_shards` shards are
+//! corrupted.
+//!
+//! Frame layout, integers little endian:
+//!
+//! | Field              | Size                   |
+//! |--------------------|------------------------|
+//! | version            | 1                      |
+//! | data shards        | 1                      |
+//! | parity shards      | 1                      |
+//! | shard size         | 4                      |
+//! | payload length     | 4                      |
+//! | payload checksum   | 4                      |
+//! | shard checksums    | 4 per shard            |
+//! | header checksum    | 4                      |
+//! | shards             | shard size per shard   |
+//!
+//! The header is not itself erasure coded; a frame with a corrupted header is rejected.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+/// Current frame format version
+pub const VERSION: u8 = 1;
+
+/// Size of the header fields preceding the shard checksums
+const FIXED_HEADER_LEN: usize = 15;
+
+/// Size of a checksum
+const CHECKSUM_LEN: usize = 4;
+
+/// Number of data and parity shards a payload is split into
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct ShardConfig {
+    /// Shards holding the payload, at least one
+    pub data_shards: u8,
+    /// Shards holding parity; this many corrupted shards can be recovered
+    pub parity_shards: u8,
+}
+
+impl ShardConfig {
+    /// Total number of shards
+    pub fn total_shards(&self) -> usize {
+        self.data_shards as usize + self.parity_shards as usize
+    }
+
+    fn is_valid(&self) -> bool {
+        self.data_shards > 0 && self.total_shards() <= u8::MAX as usize
+    }
+}
+
+/// Errors encoding or decoding a frame
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum CodecError {
+    /// Shard counts are zero or exceed the field size
+    InvalidShardConfig,
+    /// Payload length does not fit the header
+    PayloadTooLarge,
+    /// Frame is shorter or longer than its header describes
+    Truncated,
+    /// Frame was produced by an unknown version of the codec
+    UnsupportedVersion,
+    /// Header checksum does not match
+    CorruptedHeader,
+    /// More shards are corrupted than there are parity shards
+    TooManyCorruptedShards,
+    /// Recovered payload does not match its checksum
+    ChecksumMismatch,
+}
+
+/// Frame `payload` with Reed-Solomon parity
+pub fn apply_error_correction(payload: &[u8], config: ShardConfig) -> Result<Vec<u8>, CodecError> {
+    if !config.is_valid() {
+        return Err(CodecError::InvalidShardConfig);
+    }
+    let payload_len = u32::try_from(payload.len()).map_err(|_| CodecError::PayloadTooLarge)?;
+
+    let data_shards = config.data_shards as usize;
+    let total_shards = config.total_shards();
+    let shard_size = payload.len().div_ceil(data_shards).max(1);
+
+    let mut shards = vec![0u8; total_shards * shard_size];
+    shards[..payload.len()].copy_from_slice(payload);
+
+    let matrix = encoding_matrix(data_shards, total_shards);
+    let (data, parity) = shards.split_at_mut(data_shards * shard_size);
+    for (row, parity_shard) in parity.chunks_mut(shard_size).enumerate() {
+        let coefficients = &matrix[(data_shards + row) * data_shards..][..data_shards];
+        for (coefficient, data_shard) in coefficients.iter().zip(data.chunks(shard_size)) {
+            add_scaled(parity_shard, data_shard, *coefficient);
+        }
+    }
+
+    let mut frame = Vec::with_capacity(header_len(total_shards) + shards.len());
+    frame.push(VERSION);
+    frame.push(config.data_shards);
+    frame.push(config.parity_shards);
+    frame.extend_from_slice(&(shard_size as u32).to_le_bytes());
+    frame.extend_from_slice(&payload_len.to_le_bytes());
+    frame.extend_from_slice(&checksum(payload).to_le_bytes());
+    for shard in shards.chunks(shard_size) {
+        frame.extend_from_slice(&checksum(shard).to_le_bytes());
+    }
+    let header_checksum = checksum(&frame);
+    frame.extend_from_slice(&header_checksum.to_le_bytes());
+    frame.extend_from_slice(&shards);
+    Ok(frame)
+}
+
+/// Length of the frame `apply_error_correction` produces for a payload of `payload_len` bytes
+///
+/// Frame length grows with the payload length, so this bounds the frame of any shorter payload.
+pub fn max_frame_len(payload_len: usize, config: ShardConfig) -> usize {
+    let shard_size = payload_len.div_ceil((config.data_shards as usize).max(1)).max(1);
+    header_len(config.total_shards()).saturating_add(config.total_shards().saturating_mul(shard_size))
+}
+
+/// Verify a frame and recover its payload, correcting up to `parity_shards` corrupted shards
+pub fn verify_and_correct(frame: &[u8]) -> Result<Vec<u8>, CodecError> {
+    if frame.len() < FIXED_HEADER_LEN {
+        return Err(CodecError::Truncated);
+    }
+    if frame[0] != VERSION {
+        return Err(CodecError::UnsupportedVersion);
+    }
+    let config = ShardConfig { data_shards: frame[1], parity_shards: frame[2] };
+    if !config.is_valid() {
+        return Err(CodecError::InvalidShardConfig);
+    }
+
+    let data_shards = config.data_shards as usize;
+    let total_shards = config.total_shards();
+    let header_len = header_len(total_shards);
+    if frame.len() < header_len {
+        return Err(CodecError::Truncated);
+    }
+    let (header, shards) = frame.split_at(header_len);
+    let (header, header_checksum) = header.split_at(header_len - CHECKSUM_LEN);
+    if checksum(header) != read_u32(header_checksum) {
+        return Err(CodecError::CorruptedHeader);
+    }
+
+    let shard_size = read_u32(&header[3..]) as usize;
+    let payload_len = read_u32(&header[7..]) as usize;
+    let payload_checksum = read_u32(&header[11..]);
+    if shard_size == 0 ||
+        Some(shards.len()) != total_shards.checked_mul(shard_size) ||
+        payload_len > data_shards * shard_size
+    {
+        return Err(CodecError::Truncated);
+    }
+
+    let intact: Vec<usize> = shards
+        .chunks(shard_size)
+        .zip(header[FIXED_HEADER_LEN..].chunks(CHECKSUM_LEN))
+        .enumerate()
+        .filter(|(_, (shard, expected))| checksum(shard) == read_u32(expected))
+        .map(|(index, _)| index)
+        .take(data_shards)
+        .collect();
+    if intact.len() < data_shards {
+        return Err(CodecError::TooManyCorruptedShards);
+    }
+
+    let mut payload = if intact.iter().enumerate().all(|(position, index)| position == *index) {
+        shards[..data_shards * shard_size].to_vec()
+    } else {
+        // Invert the encoding rows of the intact shards to map them back to the data shards
+        let matrix = encoding_matrix(data_shards, total_shards);
+        let mut rows = Vec::with_capacity(data_shards * data_shards);
+        for index in &intact {
+            rows.extend_from_slice(&matrix[index * data_shards..][..data_shards]);
+        }
+        let decoding = invert(rows, data_shards).ok_or(CodecError::TooManyCorruptedShards)?;
+
+        let mut data = vec![0u8; data_shards * shard_size];
+        for (row, data_shard) in data.chunks_mut(shard_size).enumerate() {
+            for (position, index) in intact.iter().enumerate() {
+                let shard = &shards[index * shard_size..][..shard_size];
+                add_scaled(data_shard, shard, decoding[row * data_shards + position]);
+            }
+        }
+        data
+    };
+
+    payload.truncate(payload_len);
+    if checksum(&payload) != payload_checksum {
+        return Err(CodecError::ChecksumMismatch);
+    }
+    Ok(payload)
+}
+
+fn header_len(total_shards: usize) -> usize {
+    FIXED_HEADER_LEN + (total_shards + 1) * CHECKSUM_LEN
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(&bytes[..4]);
+    u32::from_le_bytes(buf)
+}
+
+/// CRC-32 (IEEE 802.3)
+fn checksum(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Logarithm and exponent tables of GF(2^8) with the polynomial x^8 + x^4 + x^3 + x^2 + 1
+const GF_TABLES: ([u8; 256], [u8; 512]) = gf_tables();
+
+const fn gf_tables() -> ([u8; 256], [u8; 512]) {
+    let mut log = [0u8; 256];
+    let mut exp = [0u8; 512];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        exp[i + 255] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= 0x11d;
+        }
+        i += 1;
+    }
+    (log, exp)
+}
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    let (log, exp) = &GF_TABLES;
+    exp[log[a as usize] as usize + log[b as usize] as usize]
+}
+
+fn gf_inv(a: u8) -> u8 {
+    let (log, exp) = &GF_TABLES;
+    exp[255 - log[a as usize] as usize]
+}
+
+fn gf_pow(a: u8, n: usize) -> u8 {
+    if n == 0 {
+        return 1;
+    }
+    if a == 0 {
+        return 0;
+    }
+    let (log, exp) = &GF_TABLES;
+    exp[(log[a as usize] as usize * n) % 255]
+}
+
+/// `target += coefficient * source`, element-wise
+fn add_scaled(target: &mut [u8], source: &[u8], coefficient: u8) {
+    if coefficient == 0 {
+        return;
+    }
+    for (t, s) in target.iter_mut().zip(source) {
+        *t ^= gf_mul(*s, coefficient);
+    }
+}
+
+/// Systematic `total x data` encoding matrix, row major
+///
+/// Derived from a Vandermonde matrix so that any `data` rows are invertible, with its top
+/// rows turned into the identity so data shards are stored as is.
+fn encoding_matrix(data: usize, total: usize) -> Vec<u8> {
+    let vandermonde: Vec<u8> = (0..total)
+        .flat_map(|row| (0..data).map(move |col| gf_pow(row as u8, col)))
+        .collect();
+    let top = invert(vandermonde[..data * data].to_vec(), data)
+        .expect("Vandermonde matrices with distinct points are invertible; qed");
+
+    let mut matrix = vec![0u8; total * data];
+    for row in 0..total {
+        for col in 0..data {
+            matrix[row * data + col] = (0..data)
+                .fold(0, |acc, k| acc ^ gf_mul(vandermonde[row * data + k], top[k * data + col]));
+        }
+    }
+    matrix
+}
+
+/// Invert a square `size x size` matrix by Gauss-Jordan elimination
+fn invert(mut matrix: Vec<u8>, size: usize) -> Option<Vec<u8>> {
+    let mut inverse = vec![0u8; size * size];
+    for i in 0..size {
+        inverse[i * size + i] = 1;
+    }
+
+    for col in 0..size {
+        let pivot = (col..size).find(|row| matrix[row * size + col] != 0)?;
+        if pivot != col {
+            for k in 0..size {
+                matrix.swap(pivot * size + k, col * size + k);
+                inverse.swap(pivot * size + k, col * size + k);
+            }
+        }
+
+        let scale = gf_inv(matrix[col * size + col]);
+        for k in 0..size {
+            matrix[col * size + k] = gf_mul(matrix[col * size + k], scale);
+            inverse[col * size + k] = gf_mul(inverse[col * size + k], scale);
+        }
+
+        for row in 0..size {
+            let factor = matrix[row * size + col];
+            if row == col || factor == 0 {
+                continue;
+            }
+            for k in 0..size {
+                matrix[row * size + k] ^= gf_mul(factor, matrix[col * size + k]);
+                inverse[row * size + k] ^= gf_mul(factor, inverse[col * size + k]);
+            }
+        }
+    }
+    Some(inverse)
+}
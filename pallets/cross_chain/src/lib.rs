@@ -2,16 +2,54 @@
 //!
 //! This module handles communication between different parachains in the Matrix-Magiq
 //! ecosystem (NRSH, ELXR, IMRT) using XCMP (Cross-Chain Message Passing).
+//!
+//! Outgoing messages are delivered through `Config::XcmSender` as an XCM program that buys
+//! execution on the target parachain and `Transact`s a `receive_xcm_message` call of this
+//! pallet there. The target chain maps the sibling parachain origin to its parachain ID with
+//! `Config::ReceiveOrigin` before processing the message. All ecosystem runtimes are expected
+//! to install this pallet at the same index.
+//!
+//! Messages are only exchanged with parachains that have a channel configured by
+//! `ChannelOrigin`. A channel lists the message types accepted in both directions, the maximum
+//! message size, a rate limit and the asset used to pay for execution on the remote chain.
+//!
+//! When `Config::ErrorCorrection` is set, message payloads travel as Reed-Solomon frames (see
+//! [`error_correction`]) and are recovered before processing. It must be set alike on all
+//! ecosystem runtimes.
+//!
+//! `Custom` messages are dispatched to the `Config::CustomHandlers` registered for their code.
+//! Codes from [`custom_codes::PROTOCOL_RESERVED_START`] upward are rejected; codes assigned to
+//! ecosystem pallets are listed in [`custom_codes::RESERVED`].
+//!
+//! Liquidity operations submitted with `submit_liquidity_operation` escrow the assets they spend
+//! and are tracked until the target chain reports an [`OperationResult`]. A successful result
+//! releases the escrow to the target chain's sovereign account, and a failed result refunds it
+//! at once. If no result arrives within `ResultTimeout` of the operation's
+//! expiration, the escrow is refunded by `on_idle` or by anyone calling `refund_operation`.
+//! Both chains track an operation by its key, the hash of its owner and caller-chosen ID (see
+//! [`Pallet::operation_key`]), so that no one can claim the ID of another account's operation.
+//!
+//! The escrowed assets travel with the operation as derivatives reserve-backed by the source
+//! chain: the XCM program deposits them into the source's sovereign account on the target
+//! chain, from which `Config::LiquidityHandler` takes them. An operation lists the assets
+//! delivered with it and may spend no more than those, so other assets held by the sovereign
+//! account are never spent on its behalf. Assets the operation yields are paid
+//! out on the target chain. When an operation fails or is rejected, the target chain burns the
+//! derivatives delivered with it.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use frame_support::{
-    dispatch::DispatchResult,
-    pallet_prelude::*,
-    traits::{Get, Currency},
-};
-use frame_system::pallet_prelude::*;
-use sp_runtime::{traits::Hash, RuntimeDebug};
+pub use pallet::*;
+
+pub mod error_correction;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+use frame_support::{dispatch::DispatchResult, weights::Weight};
+use sp_runtime::{DispatchError, RuntimeDebug};
 use sp_std::prelude::*;
 use codec::{Decode, Encode, MaxEncodedLen};
 use scale_info::TypeInfo;
@@ -25,6 +63,10 @@ pub enum XcmMessageType {
     LiquidityOperation,
     /// Price update
     PriceUpdate,
+    /// Atomic swap coordination
+    AtomicSwap,
+    /// Result of a liquidity operation
+    OperationResult,
     /// Custom message
     Custom(u8),
 }
@@ -89,57 +131,1374 @@ pub enum LiquidityOperation<AccountId, AssetId, Balance, BlockNumber, Hash> {
     },
 }
 
-/// Send a cross-chain message to another parachain
-pub fn send_xcm_message<T: frame_system::Config>(
-    target_parachain_id: u32,
-    message_type: XcmMessageType,
-    message_data: Vec<u8>,
-) -> DispatchResult {
-    // Implementation would use XCMP to send messages
-    // This is a placeholder for the actual implementation
-    Ok(())
-}
-
-/// Receive and process a cross-chain message
-pub fn process_xcm_message<T: frame_system::Config>(
-    source_parachain_id: u32,
-    message_type: XcmMessageType,
-    message_data: Vec<u8>,
-) -> DispatchResult {
-    // Implementation would process received XCMP messages
-    // This is a placeholder for the actual implementation
-    Ok(())
-}
-
-/// Send a cross-chain liquidity operation
-pub fn send_liquidity_operation<T: frame_system::Config>(
-    target_parachain_id: u32,
-    operation: LiquidityOperation<T::AccountId, T::AssetId, T::Balance, T::BlockNumber, T::Hash>,
-) -> DispatchResult {
-    // Implementation would encode the operation and send it via XCMP
-    // This is a placeholder for the actual implementation
-    Ok(())
-}
-
-/// Error correction for cross-chain messages
-pub mod error_correction {
-    use super::*;
-    
-    /// Apply error correction to outgoing messages
-    pub fn apply_error_correction<T: frame_system::Config>(
-        message_data: Vec<u8>,
-    ) -> Vec<u8> {
-        // Implementation would apply Reed-Solomon coding
-        // This is a placeholder for the actual implementation
-        message_data
+impl<AccountId, AssetId, Balance, BlockNumber: Copy, Hash: Copy>
+    LiquidityOperation<AccountId, AssetId, Balance, BlockNumber, Hash>
+{
+    /// Account that submitted the operation
+    pub fn owner(&self) -> &AccountId {
+        match self {
+            Self::AddLiquidity { provider, .. } |
+            Self::RemoveLiquidity { provider, .. } => provider,
+            Self::ExecuteSwap { initiator, .. } => initiator,
+        }
     }
-    
-    /// Verify and correct errors in incoming messages
-    pub fn verify_and_correct<T: frame_system::Config>(
+
+    /// Operation ID
+    pub fn operation_id(&self) -> Hash {
+        match self {
+            Self::AddLiquidity { operation_id, .. } |
+            Self::RemoveLiquidity { operation_id, .. } |
+            Self::ExecuteSwap { operation_id, .. } => *operation_id,
+        }
+    }
+
+    /// Expiration block
+    pub fn expiration(&self) -> BlockNumber {
+        match self {
+            Self::AddLiquidity { expiration, .. } |
+            Self::RemoveLiquidity { expiration, .. } |
+            Self::ExecuteSwap { expiration, .. } => *expiration,
+        }
+    }
+}
+
+/// Liquidity operation tagged with the sender's nonce for the target parachain
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct NoncedOperation<AccountId, AssetId, Balance, BlockNumber, Hash> {
+    /// Per-target nonce assigned by the sending parachain
+    pub nonce: u64,
+    /// The operation
+    pub operation: LiquidityOperation<AccountId, AssetId, Balance, BlockNumber, Hash>,
+    /// Assets delivered with the operation, the most it may spend of the sender's sovereign account
+    pub assets: Vec<(AssetId, Balance)>,
+}
+
+/// Outcome of a liquidity operation executed on the target parachain
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum OperationOutcome<AssetId, Balance> {
+    /// Liquidity was added, minting `shares`
+    LiquidityAdded {
+        /// Shares minted
+        shares: Balance,
+    },
+    /// Liquidity was removed, returning `assets`
+    LiquidityRemoved {
+        /// Assets withdrawn
+        assets: Vec<(AssetId, Balance)>,
+    },
+    /// Swap was executed
+    SwapExecuted {
+        /// Amount received
+        amount_received: Balance,
+    },
+    /// Operation failed and had no effect
+    Failed {
+        /// Reason for the failure
+        error: DispatchError,
+    },
+}
+
+/// Result message sent back to the source parachain of a liquidity operation
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct OperationResult<AssetId, Balance, Hash> {
+    /// Operation key
+    pub operation_key: Hash,
+    /// Outcome of the operation
+    pub outcome: OperationOutcome<AssetId, Balance>,
+}
+
+/// Why the escrow of an outbound liquidity operation was refunded
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum RefundReason {
+    /// The target chain reported the operation as failed
+    Failed,
+    /// No result arrived before the operation timed out
+    TimedOut,
+}
+
+/// Direction of a message over a channel
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum Direction {
+    /// Sent by this chain
+    Outbound,
+    /// Received from another chain
+    Inbound,
+}
+
+/// Messages exchanged over a channel in the current rate limit period
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct ChannelUsage<BlockNumber> {
+    /// Block the current period started at
+    pub period_start: BlockNumber,
+    /// Messages counted in the current period
+    pub messages: u32,
+}
+
+/// Executes liquidity operations received from other parachains
+///
+/// Assets spent by an operation are taken from `payer`, the sovereign account of the source
+/// parachain holding the assets delivered with the operation. Assets it yields are paid out on
+/// this chain to the provider or initiator of the operation.
+pub trait LiquidityHandler<AccountId, AssetId, Balance, Hash> {
+    /// Add `assets` of `payer` to `pool_id`, returning the shares minted to `provider`
+    fn add_liquidity(
+        provider: &AccountId,
+        payer: &AccountId,
+        pool_id: Hash,
+        assets: Vec<(AssetId, Balance)>,
+    ) -> Result<Balance, DispatchError>;
+
+    /// Burn `shares` of `pool_id`, returning the assets paid out to `provider`
+    fn remove_liquidity(
+        provider: &AccountId,
+        pool_id: Hash,
+        shares: Balance,
+    ) -> Result<Vec<(AssetId, Balance)>, DispatchError>;
+
+    /// Swap `amount` of `payer` along `path`, returning the amount paid out to `who`
+    fn execute_swap(
+        who: &AccountId,
+        payer: &AccountId,
+        path: Vec<AssetId>,
+        amount: Balance,
+    ) -> Result<Balance, DispatchError>;
+
+    /// Maximum weight of executing any one operation
+    fn max_weight() -> Weight;
+}
+
+/// Sends messages to other parachains on behalf of other pallets
+pub trait CrossChainMessenger {
+    /// Send `message_data` of `message_type` to `target_parachain_id`
+    fn send_message(
+        target_parachain_id: u32,
+        message_type: XcmMessageType,
         message_data: Vec<u8>,
-    ) -> Result<Vec<u8>, &'static str> {
-        // Implementation would verify and correct using Reed-Solomon
-        // This is a placeholder for the actual implementation
-        Ok(message_data)
+    ) -> DispatchResult;
+}
+
+/// Handles atomic swap coordination messages received from other parachains
+pub trait SwapMessageHandler {
+    /// Handle a swap message sent by `source_parachain_id`
+    fn handle_swap_message(source_parachain_id: u32, message_data: &[u8]) -> DispatchResult;
+}
+
+impl SwapMessageHandler for () {
+    fn handle_swap_message(_source_parachain_id: u32, _message_data: &[u8]) -> DispatchResult {
+        Err(DispatchError::Other("Atomic swaps are not supported"))
+    }
+}
+
+/// Sends liquidity operations to other parachains on behalf of other pallets
+pub trait LiquidityOperationSender<AccountId, AssetId, Balance, BlockNumber, Hash> {
+    /// Send `operation` to `target_parachain_id` without any assets
+    fn send_liquidity_operation(
+        target_parachain_id: u32,
+        operation: LiquidityOperation<AccountId, AssetId, Balance, BlockNumber, Hash>,
+    ) -> DispatchResult;
+
+    /// Send `operation` to `target_parachain_id` with the assets it spends, escrowed from its
+    /// owner, returning the operation key
+    ///
+    /// The owner is notified through [`OperationSettlementHandler`] once the operation settles.
+    fn submit_liquidity_operation(
+        target_parachain_id: u32,
+        operation: LiquidityOperation<AccountId, AssetId, Balance, BlockNumber, Hash>,
+    ) -> Result<Hash, DispatchError>;
+}
+
+/// Notified when outbound liquidity operations settle
+pub trait OperationSettlementHandler<AccountId, AssetId, Balance, Hash> {
+    /// The target chain executed the operation with `operation_key`
+    fn on_operation_completed(operation_key: Hash);
+
+    /// The escrow of the operation with `operation_key` was refunded to `owner`
+    fn on_operation_refunded(owner: &AccountId, operation_key: Hash, escrow: &[(AssetId, Balance)], reason: RefundReason);
+
+    /// Maximum weight of either notification
+    fn max_weight() -> Weight;
+}
+
+impl<AccountId, AssetId, Balance, Hash> OperationSettlementHandler<AccountId, AssetId, Balance, Hash> for () {
+    fn on_operation_completed(_operation_key: Hash) {}
+
+    fn on_operation_refunded(
+        _owner: &AccountId,
+        _operation_key: Hash,
+        _escrow: &[(AssetId, Balance)],
+        _reason: RefundReason,
+    ) {
+    }
+
+    fn max_weight() -> Weight {
+        Weight::zero()
+    }
+}
+
+/// Handles price and reserve updates received from other parachains
+pub trait PriceUpdateHandler {
+    /// Handle a price update sent by `source_parachain_id`
+    fn handle_price_update(source_parachain_id: u32, message_data: &[u8]) -> DispatchResult;
+}
+
+impl PriceUpdateHandler for () {
+    fn handle_price_update(_source_parachain_id: u32, _message_data: &[u8]) -> DispatchResult {
+        Err(DispatchError::Other("Price updates are not supported"))
+    }
+}
+
+/// Handles `Custom` messages with the codes it registers
+///
+/// Handlers are registered as a tuple in `Config::CustomHandlers`; each code may be claimed by
+/// one handler only.
+pub trait CustomMessageHandler {
+    /// Codes handled
+    fn codes() -> Vec<u8>;
+
+    /// Maximum weight of handling a message with `code`, if it is handled
+    fn max_weight(code: u8) -> Option<Weight>;
+
+    /// Handle a message with `code` sent by `source_parachain_id`, returning the weight used
+    fn handle(code: u8, source_parachain_id: u32, message_data: &[u8]) -> Result<Weight, DispatchError>;
+}
+
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+impl CustomMessageHandler for Tuple {
+    fn codes() -> Vec<u8> {
+        let mut codes = Vec::new();
+        for_tuples!( #( codes.extend(Tuple::codes()); )* );
+        codes
+    }
+
+    fn max_weight(code: u8) -> Option<Weight> {
+        for_tuples!( #(
+            if let Some(weight) = Tuple::max_weight(code) {
+                return Some(weight);
+            }
+        )* );
+        None
+    }
+
+    fn handle(code: u8, source_parachain_id: u32, message_data: &[u8]) -> Result<Weight, DispatchError> {
+        for_tuples!( #(
+            if Tuple::max_weight(code).is_some() {
+                return Tuple::handle(code, source_parachain_id, message_data);
+            }
+        )* );
+        Err(DispatchError::Other("No handler for custom message code"))
+    }
+}
+
+/// Codes of `Custom` messages
+pub mod custom_codes {
+    /// NRSH registry updates
+    pub const NRSH_REGISTRY: u8 = 0x01;
+    /// ELXR telemetry reports
+    pub const ELXR_TELEMETRY: u8 = 0x02;
+    /// IMRT coordination messages
+    pub const IMRT_COORDINATION: u8 = 0x03;
+
+    /// Codes assigned to ecosystem pallets, with the pallet owning them
+    pub const RESERVED: &[(u8, &str)] = &[
+        (NRSH_REGISTRY, "nrsh-registry"),
+        (ELXR_TELEMETRY, "elxr-telemetry"),
+        (IMRT_COORDINATION, "imrt-coordination"),
+    ];
+
+    /// First code reserved for future protocol messages; these are never dispatched
+    pub const PROTOCOL_RESERVED_START: u8 = 0xf0;
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::*;
+    use frame_support::{
+        storage::with_storage_layer,
+        traits::tokens::{
+            fungibles::{Inspect, Mutate},
+            Fortitude, Precision, Preservation,
+        },
+        PalletId,
+    };
+    use sp_runtime::traits::{
+        AccountIdConversion, AtLeast32BitUnsigned, BlockNumberProvider, MaybeConvert, One, SaturatedConversion,
+        Hash, Saturating, Zero,
+    };
+    use xcm::latest::prelude::*;
+    use xcm_executor::traits::ConvertLocation;
+    use crate::error_correction::{self, ShardConfig};
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        /// The overarching event type
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Asset identifier type
+        type AssetId: Member + Parameter + MaxEncodedLen + Copy;
+
+        /// Balance type
+        type Balance: Member + Parameter + AtLeast32BitUnsigned + Default + Copy + MaxEncodedLen;
+
+        /// Handler for atomic swap coordination messages
+        type SwapHandler: SwapMessageHandler;
+
+        /// Handler for price and reserve updates
+        type PriceHandler: PriceUpdateHandler;
+
+        /// Handlers for `Custom` messages, as a tuple
+        type CustomHandlers: CustomMessageHandler;
+
+        /// Handler executing inbound liquidity operations
+        type LiquidityHandler: LiquidityHandler<Self::AccountId, Self::AssetId, Self::Balance, Self::Hash>;
+
+        /// Handler notified when outbound liquidity operations settle
+        type SettlementHandler: OperationSettlementHandler<Self::AccountId, Self::AssetId, Self::Balance, Self::Hash>;
+
+        /// Clock shared by all parachains, used for operation expiration
+        type ExpirationProvider: BlockNumberProvider<BlockNumber = BlockNumberFor<Self>>;
+
+        /// Maximum time between receiving an operation and its expiration
+        ///
+        /// Bounds how long processed operation IDs must be remembered.
+        #[pallet::constant]
+        type MaxOperationLifetime: Get<BlockNumberFor<Self>>;
+
+        /// How far behind the highest nonce received from a parachain an operation may arrive
+        #[pallet::constant]
+        type NonceWindow: Get<u64>;
+
+        /// Maximum number of processed operations pruned per block
+        #[pallet::constant]
+        type MaxPrunedPerBlock: Get<u32>;
+
+        /// The runtime call type, used to encode `receive_xcm_message` for remote execution
+        type RuntimeCall: From<Call<Self>> + Encode;
+
+        /// Transport for outgoing XCM messages
+        type XcmSender: SendXcm;
+
+        /// Origin of incoming `receive_xcm_message` calls, yielding the sending parachain ID
+        type ReceiveOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = u32>;
+
+        /// Parachain ID of this chain
+        type SelfParaId: Get<u32>;
+
+        /// Reed-Solomon shard layout applied to message payloads, or `None` to send them as is
+        #[pallet::constant]
+        type ErrorCorrection: Get<Option<ShardConfig>>;
+
+        /// Origin allowed to configure channels to other parachains
+        type ChannelOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Maximum number of message types enabled on a channel
+        #[pallet::constant]
+        type MaxMessageTypes: Get<u32>;
+
+        /// Maximum weight of `receive_xcm_message` on the target chain
+        #[pallet::constant]
+        type RemoteCallWeight: Get<Weight>;
+
+        /// Weight of sending one message with `send_xcm_message`, including its framing
+        #[pallet::constant]
+        type SendMessageWeight: Get<Weight>;
+
+        /// Assets escrowed by outbound liquidity operations and delivered by inbound ones
+        type Assets: Mutate<Self::AccountId>
+            + Inspect<Self::AccountId, AssetId = Self::AssetId, Balance = Self::Balance>;
+
+        /// Location of an asset as seen from this chain; assets without one cannot be escrowed
+        type AssetIdToLocation: MaybeConvert<Self::AssetId, MultiLocation>;
+
+        /// Sovereign accounts of sibling parachains on this chain
+        type LocationToAccountId: ConvertLocation<Self::AccountId>;
+
+        /// Pallet ID, used to derive the escrow account
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+
+        /// Maximum number of assets escrowed by one outbound operation
+        #[pallet::constant]
+        type MaxOperationAssets: Get<u32>;
+
+        /// Time after an outbound operation's expiration to wait for its result before refunding
+        ///
+        /// Must cover the delivery time of the result message, since the target chain may
+        /// execute the operation right up to its expiration.
+        #[pallet::constant]
+        type ResultTimeout: Get<BlockNumberFor<Self>>;
+    }
+
+    /// Configuration of the channel to a trusted parachain
+    #[derive(
+        CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, TypeInfo, MaxEncodedLen,
+    )]
+    #[scale_info(skip_type_params(T))]
+    #[codec(mel_bound())]
+    pub struct ChannelConfig<T: Config> {
+        /// Message types accepted in both directions
+        pub message_types: BoundedVec<XcmMessageType, T::MaxMessageTypes>,
+        /// Maximum encoded size of a message payload, in bytes
+        pub max_message_size: u32,
+        /// Maximum number of messages per direction in each rate limit period
+        pub max_messages_per_period: u32,
+        /// Length of a rate limit period, in blocks of this chain
+        pub rate_limit_period: BlockNumberFor<T>,
+        /// Asset withdrawn from this chain's sovereign account to pay for remote execution
+        pub fee_asset: MultiAsset,
+    }
+
+    /// Liquidity operation sent to another parachain and awaiting its result
+    #[derive(
+        CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, TypeInfo, MaxEncodedLen,
+    )]
+    #[scale_info(skip_type_params(T))]
+    #[codec(mel_bound())]
+    pub struct OutboundOperation<T: Config> {
+        /// Account that submitted the operation
+        pub owner: T::AccountId,
+        /// Parachain executing the operation
+        pub target_parachain_id: u32,
+        /// Assets held by the pallet until the operation is settled
+        pub escrow: BoundedVec<(T::AssetId, T::Balance), T::MaxOperationAssets>,
+        /// Block of `ExpirationProvider` after which the escrow is refunded
+        pub refund_at: BlockNumberFor<T>,
+    }
+
+    /// Liquidity operation for this runtime
+    pub type LiquidityOperationOf<T> = LiquidityOperation<
+        <T as frame_system::Config>::AccountId,
+        <T as Config>::AssetId,
+        <T as Config>::Balance,
+        BlockNumberFor<T>,
+        <T as frame_system::Config>::Hash,
+    >;
+
+    /// Nonced liquidity operation for this runtime
+    pub type NoncedOperationOf<T> = NoncedOperation<
+        <T as frame_system::Config>::AccountId,
+        <T as Config>::AssetId,
+        <T as Config>::Balance,
+        BlockNumberFor<T>,
+        <T as frame_system::Config>::Hash,
+    >;
+
+    /// Operation result for this runtime
+    pub type OperationResultOf<T> = OperationResult<
+        <T as Config>::AssetId,
+        <T as Config>::Balance,
+        <T as frame_system::Config>::Hash,
+    >;
+
+    /// Channels to trusted parachains; messages to or from other chains are rejected
+    #[pallet::storage]
+    pub type Channels<T: Config> = StorageMap<_, Twox64Concat, u32, ChannelConfig<T>>;
+
+    /// Rate limit usage of each channel and direction
+    #[pallet::storage]
+    pub type ChannelUsages<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        u32,
+        Twox64Concat,
+        Direction,
+        ChannelUsage<BlockNumberFor<T>>,
+        ValueQuery,
+    >;
+
+    /// Next nonce for liquidity operations sent to each parachain
+    #[pallet::storage]
+    pub type OutboundNonces<T: Config> = StorageMap<_, Twox64Concat, u32, u64, ValueQuery>;
+
+    /// One past the highest nonce received from each parachain
+    #[pallet::storage]
+    pub type InboundNonces<T: Config> = StorageMap<_, Twox64Concat, u32, u64, ValueQuery>;
+
+    /// Keys of unexpired liquidity operations processed from each parachain, with their expiration
+    #[pallet::storage]
+    pub type ProcessedOperations<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, u32, Blake2_128Concat, T::Hash, BlockNumberFor<T>>;
+
+    /// Processed operations indexed by expiration, for pruning
+    #[pallet::storage]
+    pub type OperationExpiries<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, BlockNumberFor<T>, Blake2_128Concat, (u32, T::Hash), ()>;
+
+    /// Earliest expiration not yet pruned from `ProcessedOperations`
+    #[pallet::storage]
+    pub type PruneCursor<T: Config> = StorageValue<_, BlockNumberFor<T>>;
+
+    /// Liquidity operations sent to other parachains that have not been settled yet, by key
+    #[pallet::storage]
+    pub type OutboundOperations<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, OutboundOperation<T>>;
+
+    /// Outbound operations indexed by refund block, for timeout refunds
+    #[pallet::storage]
+    pub type OutboundTimeouts<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, BlockNumberFor<T>, Blake2_128Concat, T::Hash, ()>;
+
+    /// Earliest refund block not yet processed in `OutboundTimeouts`
+    #[pallet::storage]
+    pub type RefundCursor<T: Config> = StorageValue<_, BlockNumberFor<T>>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// A message was sent to another parachain
+        MessageSent {
+            target_parachain_id: u32,
+            message_type: XcmMessageType,
+            message_hash: XcmHash,
+        },
+
+        /// A message from another parachain was processed
+        MessageProcessed {
+            source_parachain_id: u32,
+            message_type: XcmMessageType,
+        },
+
+        /// A liquidity operation from another parachain was executed
+        OperationExecuted {
+            source_parachain_id: u32,
+            operation_key: T::Hash,
+            outcome: OperationOutcome<T::AssetId, T::Balance>,
+        },
+
+        /// The result of a liquidity operation sent to another parachain arrived
+        OperationResultReceived {
+            source_parachain_id: u32,
+            operation_key: T::Hash,
+            outcome: OperationOutcome<T::AssetId, T::Balance>,
+        },
+
+        /// The channel to a parachain was configured
+        ChannelUpdated {
+            parachain_id: u32,
+        },
+
+        /// The channel to a parachain was removed
+        ChannelRemoved {
+            parachain_id: u32,
+        },
+
+        /// A liquidity operation was submitted to another parachain and its assets escrowed
+        OperationSubmitted {
+            owner: T::AccountId,
+            target_parachain_id: u32,
+            operation_id: T::Hash,
+            operation_key: T::Hash,
+            escrow: Vec<(T::AssetId, T::Balance)>,
+            refund_at: BlockNumberFor<T>,
+        },
+
+        /// The escrow of an outbound liquidity operation was returned to its owner
+        OperationRefunded {
+            owner: T::AccountId,
+            operation_key: T::Hash,
+            reason: RefundReason,
+        },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Message type is not supported
+        UnsupportedMessageType,
+        /// Custom message code is reserved for protocol use
+        ReservedMessageCode,
+        /// Message payload could not be framed or recovered
+        CorruptedMessage,
+        /// XCM message could not be delivered
+        XcmSendFailed,
+        /// Operation targets a different parachain than the one it is sent to
+        TargetMismatch,
+        /// Message could not be decoded
+        MalformedMessage,
+        /// Operation expired before it was executed
+        OperationExpired,
+        /// Operation result is below the requested minimum
+        SlippageExceeded,
+        /// Operation was already processed
+        DuplicateOperation,
+        /// Operation nonce or expiration is outside the accepted window
+        OutsideReplayWindow,
+        /// Operation was not submitted by its owner
+        NotOperationOwner,
+        /// Operation has already expired
+        ExpirationInPast,
+        /// An operation of the same owner with the same ID is already pending
+        OperationPending,
+        /// Operation escrows more assets than allowed
+        TooManyAssets,
+        /// Outbound operation not found
+        UnknownOperation,
+        /// Outbound operation has not timed out yet
+        NotTimedOut,
+        /// No channel is configured for the parachain
+        UnknownParachain,
+        /// Message type is not enabled on the channel
+        MessageTypeNotAllowed,
+        /// Message exceeds the channel's maximum size
+        MessageTooLarge,
+        /// Channel's rate limit was reached for the current period
+        RateLimited,
+        /// Channel configuration has a zero period or limit, or admits more messages per period
+        /// than `NonceWindow`
+        InvalidChannelConfig,
+        /// Asset has no location and cannot be escrowed
+        UnsupportedAsset,
+        /// Assets spent by the operation were not delivered with it
+        EscrowNotReceived,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn integrity_test() {
+            let mut codes = T::CustomHandlers::codes();
+            assert!(
+                codes.iter().all(|code| *code < custom_codes::PROTOCOL_RESERVED_START),
+                "Custom message handlers must not claim protocol reserved codes"
+            );
+            let count = codes.len();
+            codes.sort_unstable();
+            codes.dedup();
+            assert_eq!(codes.len(), count, "Custom message codes must be claimed by one handler only");
+            assert!(
+                T::RemoteCallWeight::get()
+                    .all_gte(Self::receive_message_weight(&XcmMessageType::LiquidityOperation)),
+                "RemoteCallWeight must cover executing a liquidity operation"
+            );
+        }
+
+        fn on_idle(_n: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            let used = Self::refund_timed_out_operations(remaining_weight);
+            used.saturating_add(Self::prune_processed_operations(remaining_weight.saturating_sub(used)))
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Receive a message sent by `send_xcm_message` on another parachain
+        #[pallet::call_index(0)]
+        #[pallet::weight(Pallet::<T>::receive_message_weight(message_type))]
+        #[allow(clippy::useless_conversion)]
+        pub fn receive_xcm_message(
+            origin: OriginFor<T>,
+            message_type: XcmMessageType,
+            message_data: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let source_parachain_id = T::ReceiveOrigin::ensure_origin(origin)?;
+            let message_data = match T::ErrorCorrection::get() {
+                Some(config) => {
+                    // Bound the frame before decoding it; `admit_message` only sees the payload
+                    let channel = Channels::<T>::get(source_parachain_id).ok_or(Error::<T>::UnknownParachain)?;
+                    ensure!(
+                        message_data.len() <= error_correction::max_frame_len(channel.max_message_size as usize, config),
+                        Error::<T>::MessageTooLarge
+                    );
+                    error_correction::verify_and_correct(&message_data).map_err(|_| Error::<T>::CorruptedMessage)?
+                }
+                None => message_data,
+            };
+            let handler_weight = Self::process_xcm_message(source_parachain_id, message_type, message_data)?;
+            Ok(Some(Weight::from_parts(10_000, 0).saturating_add(handler_weight)).into())
+        }
+
+        /// Submit a liquidity operation to another parachain, escrowing the assets it spends
+        ///
+        /// The escrow is refunded if the target chain reports a failure, or if no result
+        /// arrives within `ResultTimeout` of the operation's expiration.
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn submit_liquidity_operation(
+            origin: OriginFor<T>,
+            target_parachain_id: u32,
+            operation: LiquidityOperationOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(*operation.owner() == who, Error::<T>::NotOperationOwner);
+            Self::do_submit_liquidity_operation(target_parachain_id, operation).map(|_| ())
+        }
+
+        /// Refund a timed out outbound operation
+        ///
+        /// Callable by anyone, for when `on_idle` has not caught up yet.
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn refund_operation(origin: OriginFor<T>, operation_key: T::Hash) -> DispatchResult {
+            ensure_signed(origin)?;
+            let outbound = OutboundOperations::<T>::get(operation_key).ok_or(Error::<T>::UnknownOperation)?;
+            ensure!(
+                T::ExpirationProvider::current_block_number() > outbound.refund_at,
+                Error::<T>::NotTimedOut
+            );
+            Self::settle_outbound_operation(operation_key, RefundReason::TimedOut)
+        }
+
+        /// Configure the channel to `parachain_id`, trusting it for the given message types
+        ///
+        /// The rate limit period and both limits must be non-zero, and a period may not admit
+        /// more messages than `NonceWindow`, so that operations arriving out of order within a
+        /// period are never rejected as replays.
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn set_channel(origin: OriginFor<T>, parachain_id: u32, config: Box<ChannelConfig<T>>) -> DispatchResult {
+            T::ChannelOrigin::ensure_origin(origin)?;
+            ensure!(
+                !config.rate_limit_period.is_zero() &&
+                    config.max_message_size > 0 &&
+                    config.max_messages_per_period > 0 &&
+                    u64::from(config.max_messages_per_period) <= T::NonceWindow::get(),
+                Error::<T>::InvalidChannelConfig
+            );
+            Channels::<T>::insert(parachain_id, *config);
+            Self::deposit_event(Event::ChannelUpdated { parachain_id });
+            Ok(())
+        }
+
+        /// Remove the channel to `parachain_id`, rejecting all further messages to and from it
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn remove_channel(origin: OriginFor<T>, parachain_id: u32) -> DispatchResult {
+            T::ChannelOrigin::ensure_origin(origin)?;
+            ensure!(Channels::<T>::contains_key(parachain_id), Error::<T>::UnknownParachain);
+            Channels::<T>::remove(parachain_id);
+            let _ = ChannelUsages::<T>::clear_prefix(parachain_id, u32::MAX, None);
+            Self::deposit_event(Event::ChannelRemoved { parachain_id });
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Send a cross-chain message to another parachain
+        pub fn send_xcm_message(
+            target_parachain_id: u32,
+            message_type: XcmMessageType,
+            message_data: Vec<u8>,
+        ) -> DispatchResult {
+            Self::send_message_with_assets(target_parachain_id, message_type, message_data, MultiAssets::new())
+        }
+
+        /// Send a message along with `assets`, deposited into this chain's sovereign account on
+        /// the target chain before the message is processed
+        ///
+        /// `assets` are located as seen from the target chain and must be reserve-backed by this
+        /// chain.
+        fn send_message_with_assets(
+            target_parachain_id: u32,
+            message_type: XcmMessageType,
+            message_data: Vec<u8>,
+            assets: MultiAssets,
+        ) -> DispatchResult {
+            let channel = Self::admit_message(target_parachain_id, Direction::Outbound, &message_type, &message_data)?;
+            let message_data = match T::ErrorCorrection::get() {
+                Some(config) => error_correction::apply_error_correction(&message_data, config)
+                    .map_err(|_| Error::<T>::CorruptedMessage)?,
+                None => message_data,
+            };
+            let call: <T as Config>::RuntimeCall = Call::<T>::receive_xcm_message {
+                message_type: message_type.clone(),
+                message_data,
+            }
+            .into();
+            let fees = channel.fee_asset;
+            let beneficiary = Self::sibling_location(T::SelfParaId::get());
+            let mut instructions = vec![
+                WithdrawAsset(fees.clone().into()),
+                BuyExecution { fees, weight_limit: Unlimited },
+            ];
+            if !assets.inner().is_empty() {
+                instructions.push(ReserveAssetDeposited(assets.clone()));
+                instructions.push(DepositAsset { assets: Definite(assets), beneficiary });
+            }
+            instructions.extend([
+                Transact {
+                    origin_kind: OriginKind::Native,
+                    require_weight_at_most: T::RemoteCallWeight::get(),
+                    call: call.encode().into(),
+                },
+                RefundSurplus,
+                DepositAsset { assets: Wild(AllCounted(1)), beneficiary },
+            ]);
+            let message = Xcm(instructions);
+
+            let (message_hash, _) = send_xcm::<T::XcmSender>(
+                Self::sibling_location(target_parachain_id),
+                message,
+            )
+            .map_err(|_| Error::<T>::XcmSendFailed)?;
+
+            Self::deposit_event(Event::MessageSent { target_parachain_id, message_type, message_hash });
+            Ok(())
+        }
+
+        /// Maximum weight of `receive_xcm_message` for `message_type`
+        pub fn receive_message_weight(message_type: &XcmMessageType) -> Weight {
+            let handler_weight = match message_type {
+                XcmMessageType::LiquidityOperation => Self::liquidity_operation_weight(),
+                XcmMessageType::OperationResult => Self::operation_result_weight(),
+                XcmMessageType::Custom(code) => T::CustomHandlers::max_weight(*code).unwrap_or_default(),
+                _ => Weight::zero(),
+            };
+            Weight::from_parts(10_000, 0).saturating_add(handler_weight)
+        }
+
+        /// Weight of executing an inbound liquidity operation and sending back its result
+        fn liquidity_operation_weight() -> Weight {
+            // Replay protection reads the processed operation, nonce and prune cursor and
+            // records the operation in both maps. Each delivered asset is checked, and burnt if
+            // the operation fails.
+            let assets = T::MaxOperationAssets::get() as u64;
+            T::DbWeight::get()
+                .reads_writes(3 + 3 * assets, 4 + 2 * assets)
+                .saturating_add(T::LiquidityHandler::max_weight())
+                .saturating_add(T::SendMessageWeight::get())
+        }
+
+        /// Weight of settling an outbound operation with its result
+        fn operation_result_weight() -> Weight {
+            // The operation is read and removed from both maps, and each escrowed asset is
+            // released or refunded
+            let assets = T::MaxOperationAssets::get() as u64;
+            T::DbWeight::get()
+                .reads_writes(1 + 2 * assets, 2 + 2 * assets)
+                .saturating_add(T::SettlementHandler::max_weight())
+        }
+
+        /// Receive and process a cross-chain message
+        ///
+        /// Returns the weight used by the message handler, on top of the base weight.
+        pub fn process_xcm_message(
+            source_parachain_id: u32,
+            message_type: XcmMessageType,
+            message_data: Vec<u8>,
+        ) -> Result<Weight, DispatchError> {
+            Self::admit_message(source_parachain_id, Direction::Inbound, &message_type, &message_data)?;
+            let mut handler_weight = Weight::zero();
+            match message_type {
+                XcmMessageType::AtomicSwap => {
+                    T::SwapHandler::handle_swap_message(source_parachain_id, &message_data)?;
+                },
+                XcmMessageType::LiquidityOperation => {
+                    let NoncedOperation { nonce, operation, assets } =
+                        NoncedOperationOf::<T>::decode(&mut &message_data[..])
+                            .map_err(|_| Error::<T>::MalformedMessage)?;
+                    Self::execute_liquidity_operation(source_parachain_id, nonce, operation, assets)?;
+                    handler_weight = Self::liquidity_operation_weight();
+                },
+                XcmMessageType::PriceUpdate => {
+                    T::PriceHandler::handle_price_update(source_parachain_id, &message_data)?;
+                },
+                XcmMessageType::OperationResult => {
+                    let result = OperationResultOf::<T>::decode(&mut &message_data[..])
+                        .map_err(|_| Error::<T>::MalformedMessage)?;
+                    Self::handle_operation_result(source_parachain_id, &result)?;
+                    Self::deposit_event(Event::OperationResultReceived {
+                        source_parachain_id,
+                        operation_key: result.operation_key,
+                        outcome: result.outcome,
+                    });
+                },
+                XcmMessageType::Custom(code) => {
+                    let max_weight = T::CustomHandlers::max_weight(code).ok_or(Error::<T>::UnsupportedMessageType)?;
+                    let used = T::CustomHandlers::handle(code, source_parachain_id, &message_data)?;
+                    handler_weight = used.min(max_weight);
+                },
+                XcmMessageType::AssetTransfer => {
+                    // Assets move with the XCM program carrying a message, never as its payload
+                    return Err(Error::<T>::UnsupportedMessageType.into());
+                },
+            }
+
+            Self::deposit_event(Event::MessageProcessed { source_parachain_id, message_type });
+            Ok(handler_weight)
+        }
+
+        /// Check a message against the channel to `parachain_id` and count it towards its rate limit
+        fn admit_message(
+            parachain_id: u32,
+            direction: Direction,
+            message_type: &XcmMessageType,
+            message_data: &[u8],
+        ) -> Result<ChannelConfig<T>, DispatchError> {
+            if let XcmMessageType::Custom(code) = message_type {
+                ensure!(*code < custom_codes::PROTOCOL_RESERVED_START, Error::<T>::ReservedMessageCode);
+            }
+            let channel = Channels::<T>::get(parachain_id).ok_or(Error::<T>::UnknownParachain)?;
+            ensure!(channel.message_types.contains(message_type), Error::<T>::MessageTypeNotAllowed);
+            ensure!(
+                message_data.len() <= channel.max_message_size as usize,
+                Error::<T>::MessageTooLarge
+            );
+
+            let now = frame_system::Pallet::<T>::block_number();
+            ChannelUsages::<T>::try_mutate(parachain_id, direction, |usage| {
+                if now >= usage.period_start.saturating_add(channel.rate_limit_period) {
+                    *usage = ChannelUsage { period_start: now, messages: 0 };
+                }
+                ensure!(usage.messages < channel.max_messages_per_period, Error::<T>::RateLimited);
+                usage.messages += 1;
+                Ok::<_, DispatchError>(())
+            })?;
+
+            Ok(channel)
+        }
+
+        /// Escrow the assets `operation` spends from its owner and send it with them to
+        /// `target_parachain_id`, returning the operation key
+        pub fn do_submit_liquidity_operation(
+            target_parachain_id: u32,
+            operation: LiquidityOperationOf<T>,
+        ) -> Result<T::Hash, DispatchError> {
+            let who = operation.owner().clone();
+            let operation_id = operation.operation_id();
+            let operation_key = Self::operation_key(&operation);
+            let expiration = operation.expiration();
+            let now = T::ExpirationProvider::current_block_number();
+            ensure!(expiration >= now, Error::<T>::ExpirationInPast);
+            ensure!(
+                expiration <= now.saturating_add(T::MaxOperationLifetime::get()),
+                Error::<T>::OutsideReplayWindow
+            );
+            ensure!(
+                !OutboundOperations::<T>::contains_key(operation_key),
+                Error::<T>::OperationPending
+            );
+
+            let escrow: BoundedVec<_, T::MaxOperationAssets> = Self::operation_escrow(&operation)
+                .try_into()
+                .map_err(|_| Error::<T>::TooManyAssets)?;
+            let delivered = Self::delivered_assets(target_parachain_id, &escrow)?;
+            for (asset_id, amount) in escrow.iter() {
+                T::Assets::transfer(*asset_id, &who, &Self::account_id(), *amount, Preservation::Expendable)?;
+            }
+
+            Self::send_nonced_operation(target_parachain_id, operation, escrow.to_vec(), delivered)?;
+
+            let refund_at = expiration.saturating_add(T::ResultTimeout::get());
+            OutboundOperations::<T>::insert(
+                operation_key,
+                OutboundOperation { owner: who.clone(), target_parachain_id, escrow: escrow.clone(), refund_at },
+            );
+            OutboundTimeouts::<T>::insert(refund_at, operation_key, ());
+            RefundCursor::<T>::mutate(|cursor| {
+                if cursor.is_none_or(|cursor| refund_at < cursor) {
+                    *cursor = Some(refund_at);
+                }
+            });
+
+            Self::deposit_event(Event::OperationSubmitted {
+                owner: who,
+                target_parachain_id,
+                operation_id,
+                operation_key,
+                escrow: escrow.into_inner(),
+                refund_at,
+            });
+            Ok(operation_key)
+        }
+
+        /// Send a cross-chain liquidity operation without any assets
+        ///
+        /// Operations spending assets must be submitted with `submit_liquidity_operation`, which
+        /// delivers them.
+        pub fn send_liquidity_operation(
+            target_parachain_id: u32,
+            operation: LiquidityOperationOf<T>,
+        ) -> DispatchResult {
+            ensure!(Self::operation_escrow(&operation).is_empty(), Error::<T>::EscrowNotReceived);
+            Self::send_nonced_operation(target_parachain_id, operation, Vec::new(), MultiAssets::new())
+        }
+
+        /// Send a liquidity operation with the next nonce for the target, delivering `escrow` as
+        /// `delivered`, located as seen from the target
+        fn send_nonced_operation(
+            target_parachain_id: u32,
+            operation: LiquidityOperationOf<T>,
+            escrow: Vec<(T::AssetId, T::Balance)>,
+            delivered: MultiAssets,
+        ) -> DispatchResult {
+            let operation_target = match &operation {
+                LiquidityOperation::AddLiquidity { target_parachain_id, .. } |
+                LiquidityOperation::RemoveLiquidity { target_parachain_id, .. } => Some(*target_parachain_id),
+                LiquidityOperation::ExecuteSwap { .. } => None,
+            };
+            ensure!(
+                operation_target.is_none_or(|id| id == target_parachain_id),
+                Error::<T>::TargetMismatch
+            );
+
+            let nonce = OutboundNonces::<T>::mutate(target_parachain_id, |nonce| {
+                let current = *nonce;
+                *nonce = nonce.wrapping_add(1);
+                current
+            });
+            Self::send_message_with_assets(
+                target_parachain_id,
+                XcmMessageType::LiquidityOperation,
+                NoncedOperationOf::<T> { nonce, operation, assets: escrow }.encode(),
+                delivered,
+            )
+        }
+
+        /// Reject duplicate and out-of-window operations, and record the operation as processed
+        ///
+        /// Operations are remembered until they expire, after which the expiration check
+        /// rejects them on its own, so a retried message can never execute twice.
+        fn ensure_not_replayed(
+            source_parachain_id: u32,
+            nonce: u64,
+            operation: &LiquidityOperationOf<T>,
+        ) -> DispatchResult {
+            let operation_key = Self::operation_key(operation);
+            let expiration = operation.expiration();
+            let now = T::ExpirationProvider::current_block_number();
+
+            ensure!(
+                !ProcessedOperations::<T>::contains_key(source_parachain_id, operation_key),
+                Error::<T>::DuplicateOperation
+            );
+            ensure!(
+                expiration <= now.saturating_add(T::MaxOperationLifetime::get()),
+                Error::<T>::OutsideReplayWindow
+            );
+            let next_nonce = InboundNonces::<T>::get(source_parachain_id);
+            ensure!(
+                nonce.saturating_add(T::NonceWindow::get()) >= next_nonce,
+                Error::<T>::OutsideReplayWindow
+            );
+
+            InboundNonces::<T>::insert(source_parachain_id, next_nonce.max(nonce.saturating_add(1)));
+            // Expired operations fail on their own and need not be remembered
+            if expiration >= now {
+                ProcessedOperations::<T>::insert(source_parachain_id, operation_key, expiration);
+                OperationExpiries::<T>::insert(expiration, (source_parachain_id, operation_key), ());
+                PruneCursor::<T>::mutate(|cursor| {
+                    if cursor.is_none_or(|cursor| expiration < cursor) {
+                        *cursor = Some(expiration);
+                    }
+                });
+            }
+            Ok(())
+        }
+
+        /// Forget processed operations that have expired, within `remaining_weight`
+        fn prune_processed_operations(remaining_weight: Weight) -> Weight {
+            let db_weight = T::DbWeight::get();
+            let max_steps = T::MaxPrunedPerBlock::get();
+            // Each step removes one operation from both maps or skips an empty expiration bucket,
+            // and reading a bucket peeks at one operation more than it removes
+            let max_weight = db_weight
+                .reads_writes(1, 1)
+                .saturating_add(db_weight.reads_writes(2, 2).saturating_mul(max_steps as u64));
+            if remaining_weight.any_lt(max_weight) {
+                return Weight::zero();
+            }
+
+            let Some(mut cursor) = PruneCursor::<T>::get() else {
+                return db_weight.reads(1);
+            };
+            let now = T::ExpirationProvider::current_block_number();
+            let mut steps = max_steps;
+            while cursor < now && steps > 0 {
+                let expired: Vec<_> = OperationExpiries::<T>::iter_key_prefix(cursor)
+                    .take(steps as usize + 1)
+                    .collect();
+                let drained = expired.len() <= steps as usize;
+                if expired.is_empty() {
+                    steps -= 1;
+                }
+
+                for (source_parachain_id, operation_key) in expired.into_iter().take(steps as usize) {
+                    ProcessedOperations::<T>::remove(source_parachain_id, operation_key);
+                    OperationExpiries::<T>::remove(cursor, (source_parachain_id, operation_key));
+                    steps -= 1;
+                }
+
+                if !drained {
+                    break;
+                }
+                cursor = cursor.saturating_add(One::one());
+            }
+            PruneCursor::<T>::put(cursor);
+
+            max_weight
+        }
+
+        /// Escrow account holding the assets of outbound operations
+        pub fn account_id() -> T::AccountId {
+            T::PalletId::get().into_account_truncating()
+        }
+
+        /// Key tracking an operation on both chains, scoping its caller-chosen ID to its owner
+        pub fn operation_key(operation: &LiquidityOperationOf<T>) -> T::Hash {
+            T::Hashing::hash_of(&(operation.owner(), operation.operation_id()))
+        }
+
+        /// Escrowed assets as delivered to `target_parachain_id`
+        fn delivered_assets(
+            target_parachain_id: u32,
+            escrow: &[(T::AssetId, T::Balance)],
+        ) -> Result<MultiAssets, DispatchError> {
+            let context = X1(Parachain(T::SelfParaId::get()));
+            let target = Self::sibling_location(target_parachain_id);
+            escrow
+                .iter()
+                .map(|(asset_id, amount)| {
+                    let location =
+                        T::AssetIdToLocation::maybe_convert(*asset_id).ok_or(Error::<T>::UnsupportedAsset)?;
+                    MultiAsset::from((location, (*amount).saturated_into::<u128>()))
+                        .reanchored(&target, context)
+                        .map_err(|_| Error::<T>::UnsupportedAsset.into())
+                })
+                .collect::<Result<Vec<_>, DispatchError>>()
+                .map(Into::into)
+        }
+
+        /// Assets an operation spends, escrowed on the source chain and delivered to the target
+        fn operation_escrow(operation: &LiquidityOperationOf<T>) -> Vec<(T::AssetId, T::Balance)> {
+            match operation {
+                LiquidityOperation::AddLiquidity { assets, .. } => assets.clone(),
+                LiquidityOperation::RemoveLiquidity { .. } => Vec::new(),
+                LiquidityOperation::ExecuteSwap { source_asset, amount, .. } => vec![(*source_asset, *amount)],
+            }
+        }
+
+        /// Total amount of `asset_id` in `assets`
+        fn total_of(assets: &[(T::AssetId, T::Balance)], asset_id: T::AssetId) -> T::Balance {
+            assets
+                .iter()
+                .filter(|(id, _)| *id == asset_id)
+                .fold(Zero::zero(), |total: T::Balance, (_, amount)| total.saturating_add(*amount))
+        }
+
+        /// Settle an outbound operation with the result reported by its target chain
+        ///
+        /// A success releases the escrow to the target chain's sovereign account, where it backs
+        /// the derivatives delivered with the operation. A failure refunds the escrow. Results
+        /// for operations that are no longer pending, such as ones already refunded after timing
+        /// out, are only reported.
+        fn handle_operation_result(source_parachain_id: u32, result: &OperationResultOf<T>) -> DispatchResult {
+            let Some(outbound) = OutboundOperations::<T>::get(result.operation_key) else {
+                return Ok(());
+            };
+            ensure!(outbound.target_parachain_id == source_parachain_id, Error::<T>::TargetMismatch);
+
+            if let OperationOutcome::Failed { .. } = result.outcome {
+                Self::settle_outbound_operation(result.operation_key, RefundReason::Failed)
+            } else {
+                let reserve = T::LocationToAccountId::convert_location(&Self::sibling_location(source_parachain_id))
+                    .ok_or(Error::<T>::UnknownParachain)?;
+                for (asset_id, amount) in outbound.escrow.iter() {
+                    T::Assets::transfer(*asset_id, &Self::account_id(), &reserve, *amount, Preservation::Expendable)?;
+                }
+                OutboundOperations::<T>::remove(result.operation_key);
+                OutboundTimeouts::<T>::remove(outbound.refund_at, result.operation_key);
+                T::SettlementHandler::on_operation_completed(result.operation_key);
+                Ok(())
+            }
+        }
+
+        /// Stop tracking an outbound operation and refund its escrow to the owner
+        fn settle_outbound_operation(operation_key: T::Hash, reason: RefundReason) -> DispatchResult {
+            let outbound = OutboundOperations::<T>::take(operation_key).ok_or(Error::<T>::UnknownOperation)?;
+            OutboundTimeouts::<T>::remove(outbound.refund_at, operation_key);
+
+            for (asset_id, amount) in outbound.escrow.iter() {
+                T::Assets::transfer(*asset_id, &Self::account_id(), &outbound.owner, *amount, Preservation::Expendable)?;
+            }
+            T::SettlementHandler::on_operation_refunded(&outbound.owner, operation_key, &outbound.escrow, reason);
+
+            Self::deposit_event(Event::OperationRefunded { owner: outbound.owner, operation_key, reason });
+            Ok(())
+        }
+
+        /// Refund outbound operations that timed out without a result, within `remaining_weight`
+        fn refund_timed_out_operations(remaining_weight: Weight) -> Weight {
+            let db_weight = T::DbWeight::get();
+            let max_steps = T::MaxPrunedPerBlock::get();
+            // Each step refunds one operation, moving up to `MaxOperationAssets` assets, or skips
+            // an empty timeout bucket, and reading a bucket peeks at one operation more than it
+            // refunds
+            let refund_weight = db_weight
+                .reads_writes(
+                    2 + 2 * T::MaxOperationAssets::get() as u64,
+                    2 + 2 * T::MaxOperationAssets::get() as u64,
+                )
+                .saturating_add(T::SettlementHandler::max_weight());
+            let max_weight = db_weight
+                .reads_writes(1, 1)
+                .saturating_add(refund_weight.saturating_mul(max_steps as u64));
+            if remaining_weight.any_lt(max_weight) {
+                return Weight::zero();
+            }
+
+            let Some(mut cursor) = RefundCursor::<T>::get() else {
+                return db_weight.reads(1);
+            };
+            let now = T::ExpirationProvider::current_block_number();
+            let mut steps = max_steps;
+            while cursor < now && steps > 0 {
+                let timed_out: Vec<_> = OutboundTimeouts::<T>::iter_key_prefix(cursor)
+                    .take(steps as usize + 1)
+                    .collect();
+                let drained = timed_out.len() <= steps as usize;
+                if timed_out.is_empty() {
+                    steps -= 1;
+                }
+
+                for operation_key in timed_out.into_iter().take(steps as usize) {
+                    // A refund that cannot be paid out stays pending for `refund_operation`
+                    if with_storage_layer(|| Self::settle_outbound_operation(operation_key, RefundReason::TimedOut))
+                        .is_err()
+                    {
+                        OutboundTimeouts::<T>::remove(cursor, operation_key);
+                    }
+                    steps -= 1;
+                }
+
+                if !drained {
+                    break;
+                }
+                cursor = cursor.saturating_add(One::one());
+            }
+            RefundCursor::<T>::put(cursor);
+
+            max_weight
+        }
+
+        /// Execute an inbound liquidity operation and report the outcome to its source
+        ///
+        /// Rejected and failed operations are rolled back and reported as
+        /// [`OperationOutcome::Failed`], and the `delivered` assets are burnt since the source
+        /// chain refunds their escrow. Only a failure to send the result, or a delivery listing
+        /// more assets than any operation escrows, fails the message itself.
+        fn execute_liquidity_operation(
+            source_parachain_id: u32,
+            nonce: u64,
+            operation: LiquidityOperationOf<T>,
+            delivered: Vec<(T::AssetId, T::Balance)>,
+        ) -> DispatchResult {
+            ensure!(
+                delivered.len() <= T::MaxOperationAssets::get() as usize,
+                Error::<T>::TooManyAssets
+            );
+            let operation_key = Self::operation_key(&operation);
+            let payer = T::LocationToAccountId::convert_location(&Self::sibling_location(source_parachain_id))
+                .ok_or(Error::<T>::UnknownParachain)?;
+            let outcome = Self::ensure_not_replayed(source_parachain_id, nonce, &operation)
+                .and_then(|()| with_storage_layer(|| Self::apply_liquidity_operation(&payer, operation, &delivered)))
+                .unwrap_or_else(|error| {
+                    for (asset_id, amount) in delivered {
+                        let _ = T::Assets::burn_from(asset_id, &payer, amount, Precision::BestEffort, Fortitude::Polite);
+                    }
+                    OperationOutcome::Failed { error }
+                });
+
+            Self::send_xcm_message(
+                source_parachain_id,
+                XcmMessageType::OperationResult,
+                OperationResultOf::<T> { operation_key, outcome: outcome.clone() }.encode(),
+            )?;
+
+            Self::deposit_event(Event::OperationExecuted { source_parachain_id, operation_key, outcome });
+            Ok(())
+        }
+
+        /// Apply a liquidity operation paid for by `payer` with the `delivered` assets, enforcing
+        /// its expiration, delivery and minimum amounts
+        fn apply_liquidity_operation(
+            payer: &T::AccountId,
+            operation: LiquidityOperationOf<T>,
+            delivered: &[(T::AssetId, T::Balance)],
+        ) -> Result<OperationOutcome<T::AssetId, T::Balance>, DispatchError> {
+            ensure!(
+                T::ExpirationProvider::current_block_number() <= operation.expiration(),
+                Error::<T>::OperationExpired
+            );
+            let escrow = Self::operation_escrow(&operation);
+            for (asset_id, _) in escrow.iter() {
+                let amount = Self::total_of(&escrow, *asset_id);
+                ensure!(
+                    Self::total_of(delivered, *asset_id) >= amount &&
+                        T::Assets::reducible_balance(*asset_id, payer, Preservation::Expendable, Fortitude::Polite) >=
+                            amount,
+                    Error::<T>::EscrowNotReceived
+                );
+            }
+
+            match operation {
+                LiquidityOperation::AddLiquidity { provider, pool_id, assets, min_shares, .. } => {
+                    let shares = T::LiquidityHandler::add_liquidity(&provider, payer, pool_id, assets)?;
+                    ensure!(shares >= min_shares, Error::<T>::SlippageExceeded);
+                    Ok(OperationOutcome::LiquidityAdded { shares })
+                },
+                LiquidityOperation::RemoveLiquidity { provider, pool_id, shares, min_assets, .. } => {
+                    let assets = T::LiquidityHandler::remove_liquidity(&provider, pool_id, shares)?;
+                    for (asset_id, min_amount) in min_assets {
+                        let amount = assets
+                            .iter()
+                            .find(|(id, _)| *id == asset_id)
+                            .map_or_else(Zero::zero, |(_, amount)| *amount);
+                        ensure!(amount >= min_amount, Error::<T>::SlippageExceeded);
+                    }
+                    Ok(OperationOutcome::LiquidityRemoved { assets })
+                },
+                LiquidityOperation::ExecuteSwap {
+                    initiator,
+                    source_asset,
+                    target_asset,
+                    amount,
+                    min_receive,
+                    mut path,
+                    ..
+                } => {
+                    if path.is_empty() {
+                        path = vec![source_asset, target_asset];
+                    }
+                    ensure!(
+                        path.first() == Some(&source_asset) && path.last() == Some(&target_asset),
+                        Error::<T>::MalformedMessage
+                    );
+
+                    let amount_received = T::LiquidityHandler::execute_swap(&initiator, payer, path, amount)?;
+                    ensure!(amount_received >= min_receive, Error::<T>::SlippageExceeded);
+                    Ok(OperationOutcome::SwapExecuted { amount_received })
+                },
+            }
+        }
+
+        /// Location of a sibling parachain, as seen from this chain
+        fn sibling_location(parachain_id: u32) -> MultiLocation {
+            MultiLocation::new(1, X1(Parachain(parachain_id)))
+        }
+    }
+
+    impl<T: Config> CrossChainMessenger for Pallet<T> {
+        fn send_message(
+            target_parachain_id: u32,
+            message_type: XcmMessageType,
+            message_data: Vec<u8>,
+        ) -> DispatchResult {
+            Self::send_xcm_message(target_parachain_id, message_type, message_data)
+        }
+    }
+
+    impl<T: Config>
+        LiquidityOperationSender<T::AccountId, T::AssetId, T::Balance, BlockNumberFor<T>, T::Hash> for Pallet<T>
+    {
+        fn send_liquidity_operation(target_parachain_id: u32, operation: LiquidityOperationOf<T>) -> DispatchResult {
+            Self::send_liquidity_operation(target_parachain_id, operation)
+        }
+
+        fn submit_liquidity_operation(
+            target_parachain_id: u32,
+            operation: LiquidityOperationOf<T>,
+        ) -> Result<T::Hash, DispatchError> {
+            Self::do_submit_liquidity_operation(target_parachain_id, operation)
+        }
     }
 }
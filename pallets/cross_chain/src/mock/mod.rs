@@ -0,0 +1,175 @@
+//! Simulated network of a relay chain and the three ecosystem parachains
+//!
+//! Every parachain runs the [`parachain`] runtime with channels open to both siblings. XCM
+//! fees are paid in the relay chain token, held on each parachain by the sovereign accounts
+//! of its siblings. All chains read the relay block number from [`RelayBlock`].
+
+pub mod parachain;
+pub mod relay_chain;
+
+use crate::{ChannelConfig, XcmMessageType};
+use frame_support::{assert_ok, parameter_types};
+use sp_runtime::{AccountId32, BuildStorage};
+use xcm::latest::prelude::*;
+use xcm_executor::traits::ConvertLocation;
+use xcm_simulator::{decl_test_network, decl_test_parachain, decl_test_relay_chain, TestExt};
+
+pub const ALICE: AccountId32 = AccountId32::new([1; 32]);
+pub const BOB: AccountId32 = AccountId32::new([2; 32]);
+/// Account holding the assets of every pool of the mock liquidity handler
+pub const POOL: AccountId32 = AccountId32::new([9; 32]);
+
+pub const NRSH: u32 = 2000;
+pub const ELXR: u32 = 2001;
+pub const IMRT: u32 = 2002;
+
+/// Asset used by liquidity operations
+pub const ASSET: u32 = 1;
+
+pub const INITIAL_BALANCE: u128 = 1_000_000_000;
+
+/// Relay chain tokens a message buys execution with on its target chain
+pub const XCM_FEE: u128 = 1_000_000;
+
+parameter_types! {
+    pub static RelayBlock: u64 = 1;
+}
+
+decl_test_parachain! {
+    pub struct Nrsh {
+        Runtime = parachain::Runtime,
+        XcmpMessageHandler = parachain::MsgQueue,
+        DmpMessageHandler = parachain::MsgQueue,
+        new_ext = para_ext(NRSH),
+    }
+}
+
+decl_test_parachain! {
+    pub struct Elxr {
+        Runtime = parachain::Runtime,
+        XcmpMessageHandler = parachain::MsgQueue,
+        DmpMessageHandler = parachain::MsgQueue,
+        new_ext = para_ext(ELXR),
+    }
+}
+
+decl_test_parachain! {
+    pub struct Imrt {
+        Runtime = parachain::Runtime,
+        XcmpMessageHandler = parachain::MsgQueue,
+        DmpMessageHandler = parachain::MsgQueue,
+        new_ext = para_ext(IMRT),
+    }
+}
+
+decl_test_relay_chain! {
+    pub struct Relay {
+        Runtime = relay_chain::Runtime,
+        RuntimeCall = relay_chain::RuntimeCall,
+        RuntimeEvent = relay_chain::RuntimeEvent,
+        XcmConfig = relay_chain::XcmConfig,
+        MessageQueue = relay_chain::MessageQueue,
+        System = relay_chain::System,
+        new_ext = relay_ext(),
+    }
+}
+
+decl_test_network! {
+    pub struct MockNet {
+        relay_chain = Relay,
+        parachains = vec![
+            (2000, Nrsh),
+            (2001, Elxr),
+            (2002, Imrt),
+        ],
+    }
+}
+
+/// Reset every chain of the network and the shared test parameters
+pub fn reset_network() {
+    RelayBlock::set(1);
+    parachain::RemoteCallWeight::set(parachain::DEFAULT_REMOTE_CALL_WEIGHT);
+    parachain::ErrorCorrection::set(None);
+    parachain::FailOperations::set(false);
+    parachain::MaxPrunedPerBlock::set(4);
+    parachain::TelemetryCodes::set(vec![crate::custom_codes::ELXR_TELEMETRY]);
+    parachain::ExtraCodes::set(vec![]);
+    parachain::HandledCustomMessages::set(vec![]);
+    parachain::SettledOperations::set(vec![]);
+    MockNet::reset();
+}
+
+/// Account of `para_id`'s sovereign account on its siblings
+pub fn sibling_account(para_id: u32) -> AccountId32 {
+    parachain::LocationToAccountId::convert_location(&MultiLocation::new(1, X1(Parachain(para_id)))).unwrap()
+}
+
+/// Channel accepting every message type, paying fees in the relay chain token
+pub fn open_channel() -> ChannelConfig<parachain::Runtime> {
+    ChannelConfig {
+        message_types: vec![
+            XcmMessageType::AssetTransfer,
+            XcmMessageType::LiquidityOperation,
+            XcmMessageType::PriceUpdate,
+            XcmMessageType::AtomicSwap,
+            XcmMessageType::OperationResult,
+        ]
+        .try_into()
+        .unwrap(),
+        max_message_size: 4_096,
+        max_messages_per_period: 16,
+        rate_limit_period: 10,
+        fee_asset: (Parent, XCM_FEE).into(),
+    }
+}
+
+pub fn para_ext(para_id: u32) -> sp_io::TestExternalities {
+    use parachain::{CrossChain, MsgQueue, Runtime, RuntimeOrigin, System};
+
+    let siblings: Vec<u32> = [NRSH, ELXR, IMRT].into_iter().filter(|id| *id != para_id).collect();
+    let mut storage = frame_system::GenesisConfig::<Runtime>::default().build_storage().unwrap();
+    pallet_balances::GenesisConfig::<Runtime> {
+        balances: [ALICE, BOB]
+            .into_iter()
+            .chain(siblings.iter().map(|id| sibling_account(*id)))
+            .map(|who| (who, INITIAL_BALANCE))
+            .collect(),
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+    pallet_assets::GenesisConfig::<Runtime> {
+        assets: vec![(ASSET, ALICE, true, 1)],
+        metadata: vec![],
+        accounts: [ALICE, BOB, POOL].into_iter().map(|who| (ASSET, who, INITIAL_BALANCE)).collect(),
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        MsgQueue::set_para_id(para_id.into());
+        for sibling in siblings {
+            assert_ok!(CrossChain::set_channel(RuntimeOrigin::root(), sibling, Box::new(open_channel())));
+        }
+    });
+    ext
+}
+
+pub fn relay_ext() -> sp_io::TestExternalities {
+    use relay_chain::{Runtime, System};
+
+    let mut storage = frame_system::GenesisConfig::<Runtime>::default().build_storage().unwrap();
+    pallet_balances::GenesisConfig::<Runtime> { balances: vec![(ALICE, INITIAL_BALANCE)] }
+        .assimilate_storage(&mut storage)
+        .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
+
+/// Events of the chain currently executing
+pub fn para_events() -> Vec<parachain::RuntimeEvent> {
+    parachain::System::events().into_iter().map(|record| record.event).collect()
+}
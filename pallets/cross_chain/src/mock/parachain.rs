@@ -0,0 +1,523 @@
+//! Parachain runtime of the test network, shared by every simulated parachain
+
+use crate as pallet_cross_chain;
+use crate::{
+    custom_codes, error_correction::ShardConfig, CustomMessageHandler, LiquidityHandler, OperationSettlementHandler,
+    RefundReason,
+};
+
+use codec::{Decode, Encode};
+use core::marker::PhantomData;
+use frame_support::{
+    construct_runtime, derive_impl, parameter_types,
+    traits::{
+        fungibles::Mutate, tokens::Preservation, AsEnsureOriginWithArg, ConstU128, ConstU32, ConstU64, ContainsPair,
+        EnsureOrigin, Everything, Nothing,
+    },
+    weights::{constants::RocksDbWeight, Weight},
+    PalletId,
+};
+use frame_system::EnsureRoot;
+use polkadot_core_primitives::BlockNumber as RelayBlockNumber;
+use polkadot_parachain_primitives::primitives::{
+    DmpMessageHandler, Id as ParaId, Sibling, XcmpMessageFormat, XcmpMessageHandler,
+};
+use sp_runtime::{
+    traits::{BlockNumberProvider, Get, IdentityLookup, MaybeConvert},
+    AccountId32, DispatchError,
+};
+use xcm::{latest::prelude::*, VersionedXcm};
+use xcm_builder::{
+    AccountId32Aliases, AllowTopLevelPaidExecutionFrom, CurrencyAdapter, EnsureXcmOrigin, FixedRateOfFungible,
+    FixedWeightBounds, FungiblesAdapter, IsConcrete, NoChecking, ParentIsPreset, SiblingParachainConvertsVia,
+    SignedToAccountId32, TakeWeightCredit,
+};
+use xcm_executor::{
+    traits::{ConvertOrigin, Error as MatchError, MatchesFungibles},
+    XcmExecutor,
+};
+
+pub type AccountId = AccountId32;
+pub type Balance = u128;
+pub type AssetId = u32;
+
+type Block = frame_system::mocking::MockBlock<Runtime>;
+
+construct_runtime!(
+    pub enum Runtime {
+        System: frame_system,
+        Balances: pallet_balances,
+        Assets: pallet_assets,
+        MsgQueue: mock_msg_queue,
+        PolkadotXcm: pallet_xcm,
+        CrossChain: pallet_cross_chain,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Runtime {
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<AccountId>;
+    type Block = Block;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type DbWeight = RocksDbWeight;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig as pallet_balances::DefaultConfig)]
+impl pallet_balances::Config for Runtime {
+    type Balance = Balance;
+    type ExistentialDeposit = ConstU128<1>;
+    type AccountStore = System;
+}
+
+impl pallet_assets::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type Balance = Balance;
+    type AssetId = AssetId;
+    type AssetIdParameter = AssetId;
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
+    type ForceOrigin = EnsureRoot<AccountId>;
+    type AssetDeposit = ConstU128<1>;
+    type AssetAccountDeposit = ConstU128<1>;
+    type MetadataDepositBase = ConstU128<1>;
+    type MetadataDepositPerByte = ConstU128<1>;
+    type ApprovalDeposit = ConstU128<1>;
+    type StringLimit = ConstU32<50>;
+    type Freezer = ();
+    type WeightInfo = ();
+    type CallbackHandle = ();
+    type Extra = ();
+    type RemoveItemsLimit = ConstU32<5>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = ();
+}
+
+parameter_types! {
+    pub const RelayLocation: MultiLocation = MultiLocation::parent();
+    pub const RelayNetwork: NetworkId = NetworkId::Kusama;
+    pub UniversalLocation: InteriorMultiLocation = Parachain(MsgQueue::parachain_id().into()).into();
+    pub const UnitWeightCost: Weight = Weight::from_parts(1_000, 1);
+    pub RelayTokenPerSecondPerByte: (xcm::latest::AssetId, u128, u128) = (Concrete(Parent.into()), 1_000, 1);
+    pub const MaxInstructions: u32 = 100;
+    pub const MaxAssetsIntoHolding: u32 = 64;
+}
+
+pub type LocationToAccountId = (
+    ParentIsPreset<AccountId>,
+    SiblingParachainConvertsVia<Sibling, AccountId>,
+    AccountId32Aliases<RelayNetwork, AccountId>,
+);
+
+/// Dispatches `Transact`s from sibling parachains with `OriginKind::Native` as their XCM origin
+///
+/// Stands in for the sibling parachain origin of `cumulus-pallet-xcm`.
+pub struct SiblingParachainAsNative;
+impl ConvertOrigin<RuntimeOrigin> for SiblingParachainAsNative {
+    fn convert_origin(origin: impl Into<MultiLocation>, kind: OriginKind) -> Result<RuntimeOrigin, MultiLocation> {
+        let origin = origin.into();
+        match (kind, origin) {
+            (OriginKind::Native, MultiLocation { parents: 1, interior: X1(Parachain(_)) }) =>
+                Ok(pallet_xcm::Origin::Xcm(origin).into()),
+            _ => Err(origin),
+        }
+    }
+}
+
+/// Ensures a call comes from a sibling parachain, yielding its ID
+pub struct EnsureSiblingParachain;
+impl EnsureOrigin<RuntimeOrigin> for EnsureSiblingParachain {
+    type Success = u32;
+
+    fn try_origin(origin: RuntimeOrigin) -> Result<u32, RuntimeOrigin> {
+        match pallet_xcm::EnsureXcm::<Everything>::try_origin(origin.clone())? {
+            MultiLocation { parents: 1, interior: X1(Parachain(id)) } => Ok(id),
+            _ => Err(origin),
+        }
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn try_successful_origin() -> Result<RuntimeOrigin, ()> {
+        Ok(pallet_xcm::Origin::Xcm(MultiLocation::new(1, X1(Parachain(1)))).into())
+    }
+}
+
+/// Index of `Assets` in the runtime
+pub const ASSETS_PALLET_INDEX: u8 = 2;
+
+/// Locates the assets of `Assets` by their ID, on this chain and its siblings
+///
+/// Asset IDs are shared by all parachains of the network, so the derivative of a sibling's
+/// asset has the same ID as the asset itself.
+pub struct AssetsLocation;
+impl MaybeConvert<AssetId, MultiLocation> for AssetsLocation {
+    fn maybe_convert(asset_id: AssetId) -> Option<MultiLocation> {
+        Some(MultiLocation::new(0, X2(PalletInstance(ASSETS_PALLET_INDEX), GeneralIndex(asset_id.into()))))
+    }
+}
+
+impl MatchesFungibles<AssetId, Balance> for AssetsLocation {
+    fn matches_fungibles(asset: &MultiAsset) -> Result<(AssetId, Balance), MatchError> {
+        let (Concrete(location), Fungible(amount)) = (&asset.id, &asset.fun) else {
+            return Err(MatchError::AssetNotHandled);
+        };
+        let index = match location {
+            MultiLocation { parents: 0, interior: X2(PalletInstance(ASSETS_PALLET_INDEX), GeneralIndex(index)) } |
+            MultiLocation {
+                parents: 1,
+                interior: X3(Parachain(_), PalletInstance(ASSETS_PALLET_INDEX), GeneralIndex(index)),
+            } => *index,
+            _ => return Err(MatchError::AssetNotHandled),
+        };
+        let asset_id = index.try_into().map_err(|_| MatchError::AssetIdConversionFailed)?;
+        Ok((asset_id, *amount))
+    }
+}
+
+/// Trusts sibling parachains as reserves of their own `Assets`
+pub struct SiblingAssets;
+impl ContainsPair<MultiAsset, MultiLocation> for SiblingAssets {
+    fn contains(asset: &MultiAsset, origin: &MultiLocation) -> bool {
+        match (&asset.id, origin) {
+            (
+                Concrete(MultiLocation {
+                    parents: 1,
+                    interior: X3(Parachain(reserve), PalletInstance(ASSETS_PALLET_INDEX), GeneralIndex(_)),
+                }),
+                MultiLocation { parents: 1, interior: X1(Parachain(sender)) },
+            ) => reserve == sender,
+            _ => false,
+        }
+    }
+}
+
+parameter_types! {
+    pub CheckingAccount: AccountId = PolkadotXcm::check_account();
+}
+
+pub type AssetTransactors = (
+    CurrencyAdapter<Balances, IsConcrete<RelayLocation>, LocationToAccountId, AccountId, ()>,
+    FungiblesAdapter<Assets, AssetsLocation, LocationToAccountId, AccountId, NoChecking, CheckingAccount>,
+);
+
+pub type XcmRouter = super::ParachainXcmRouter<MsgQueue>;
+
+pub struct XcmConfig;
+impl xcm_executor::Config for XcmConfig {
+    type RuntimeCall = RuntimeCall;
+    type XcmSender = XcmRouter;
+    type AssetTransactor = AssetTransactors;
+    type OriginConverter = SiblingParachainAsNative;
+    type IsReserve = SiblingAssets;
+    type IsTeleporter = ();
+    type UniversalLocation = UniversalLocation;
+    type Barrier = (TakeWeightCredit, AllowTopLevelPaidExecutionFrom<Everything>);
+    type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
+    type Trader = FixedRateOfFungible<RelayTokenPerSecondPerByte, ()>;
+    type ResponseHandler = ();
+    type AssetTrap = ();
+    type AssetLocker = ();
+    type AssetExchanger = ();
+    type AssetClaims = ();
+    type SubscriptionService = ();
+    type PalletInstancesInfo = ();
+    type FeeManager = ();
+    type MaxAssetsIntoHolding = MaxAssetsIntoHolding;
+    type MessageExporter = ();
+    type UniversalAliases = Nothing;
+    type CallDispatcher = RuntimeCall;
+    type SafeCallFilter = Everything;
+    type Aliasers = Nothing;
+    type TransactionalProcessor = xcm_builder::FrameTransactionalProcessor;
+}
+
+impl pallet_xcm::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type SendXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, SignedToAccountId32<RuntimeOrigin, AccountId, RelayNetwork>>;
+    type XcmRouter = XcmRouter;
+    type ExecuteXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, SignedToAccountId32<RuntimeOrigin, AccountId, RelayNetwork>>;
+    type XcmExecuteFilter = Nothing;
+    type XcmExecutor = XcmExecutor<XcmConfig>;
+    type XcmTeleportFilter = Nothing;
+    type XcmReserveTransferFilter = Nothing;
+    type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
+    type UniversalLocation = UniversalLocation;
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    const VERSION_DISCOVERY_QUEUE_SIZE: u32 = 100;
+    type AdvertisedXcmVersion = pallet_xcm::CurrentXcmVersion;
+    type Currency = Balances;
+    type CurrencyMatcher = ();
+    type TrustedLockers = ();
+    type SovereignAccountOf = LocationToAccountId;
+    type MaxLockers = ConstU32<8>;
+    type MaxRemoteLockConsumers = ConstU32<0>;
+    type RemoteLockConsumerIdentifier = ();
+    type WeightInfo = pallet_xcm::TestWeightInfo;
+    type AdminOrigin = EnsureRoot<AccountId>;
+}
+
+/// Weight `RemoteCallWeight` is reset to for every test
+pub const DEFAULT_REMOTE_CALL_WEIGHT: Weight = Weight::from_parts(5_000_000_000, 64 * 1024);
+
+parameter_types! {
+    pub const CrossChainPalletId: PalletId = PalletId(*b"mm/xchan");
+    pub static RemoteCallWeight: Weight = DEFAULT_REMOTE_CALL_WEIGHT;
+    pub const SendMessageWeight: Weight = Weight::from_parts(50_000_000, 1_024);
+    pub static ErrorCorrection: Option<ShardConfig> = None;
+    /// Operations the mock liquidity handler rejects
+    pub static FailOperations: bool = false;
+    pub static MaxPrunedPerBlock: u32 = 4;
+    /// Codes claimed by the telemetry handler
+    pub static TelemetryCodes: Vec<u8> = vec![custom_codes::ELXR_TELEMETRY];
+    /// Codes claimed by a second handler, to register conflicting codes
+    pub static ExtraCodes: Vec<u8> = vec![];
+    /// Custom messages handled, with their code and source parachain
+    pub static HandledCustomMessages: Vec<(u8, u32, Vec<u8>)> = vec![];
+    /// Outbound operations settled, with the reason their escrow was refunded, if it was
+    pub static SettledOperations: Vec<(sp_core::H256, Option<RefundReason>)> = vec![];
+}
+
+/// Maximum weight of handling a custom message
+pub const CUSTOM_HANDLER_WEIGHT: Weight = Weight::from_parts(100_000, 0);
+
+/// Relay chain clock, shared by every parachain of the network
+pub struct RelayClock;
+impl BlockNumberProvider for RelayClock {
+    type BlockNumber = u64;
+
+    fn current_block_number() -> u64 {
+        super::RelayBlock::get()
+    }
+}
+
+/// Parachain ID of the chain currently executing
+pub struct SelfParaId;
+impl Get<u32> for SelfParaId {
+    fn get() -> u32 {
+        MsgQueue::parachain_id().into()
+    }
+}
+
+/// Liquidity handler keeping every pool's assets in [`super::POOL`], minting one share per
+/// asset unit and swapping one for one
+pub struct MockLiquidity;
+impl LiquidityHandler<AccountId, AssetId, Balance, sp_core::H256> for MockLiquidity {
+    fn add_liquidity(
+        _provider: &AccountId,
+        payer: &AccountId,
+        _pool_id: sp_core::H256,
+        assets: Vec<(AssetId, Balance)>,
+    ) -> Result<Balance, DispatchError> {
+        Self::ensure_enabled()?;
+        for (asset_id, amount) in assets.iter() {
+            Self::transfer(*asset_id, payer, &super::POOL, *amount)?;
+        }
+        Ok(assets.iter().map(|(_, amount)| amount).sum())
+    }
+
+    fn remove_liquidity(
+        provider: &AccountId,
+        _pool_id: sp_core::H256,
+        shares: Balance,
+    ) -> Result<Vec<(AssetId, Balance)>, DispatchError> {
+        Self::ensure_enabled()?;
+        Self::transfer(super::ASSET, &super::POOL, provider, shares)?;
+        Ok(vec![(super::ASSET, shares)])
+    }
+
+    fn execute_swap(
+        who: &AccountId,
+        payer: &AccountId,
+        path: Vec<AssetId>,
+        amount: Balance,
+    ) -> Result<Balance, DispatchError> {
+        Self::ensure_enabled()?;
+        let (Some(asset_in), Some(asset_out)) = (path.first(), path.last()) else {
+            return Err(DispatchError::Other("Empty swap path"));
+        };
+        Self::transfer(*asset_in, payer, &super::POOL, amount)?;
+        Self::transfer(*asset_out, &super::POOL, who, amount)?;
+        Ok(amount)
+    }
+
+    fn max_weight() -> Weight {
+        Weight::from_parts(200_000_000, 4_096)
+    }
+}
+
+impl MockLiquidity {
+    fn transfer(asset_id: AssetId, from: &AccountId, to: &AccountId, amount: Balance) -> Result<(), DispatchError> {
+        <Assets as Mutate<AccountId>>::transfer(asset_id, from, to, amount, Preservation::Expendable).map(|_| ())
+    }
+
+    fn ensure_enabled() -> Result<(), DispatchError> {
+        if FailOperations::get() {
+            Err(DispatchError::Other("Liquidity operations disabled"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Custom message handler claiming `Codes`
+///
+/// Messages are the SCALE encoded reference time the handler reports using.
+pub struct MockCustomHandler<Codes>(PhantomData<Codes>);
+impl<Codes: Get<Vec<u8>>> CustomMessageHandler for MockCustomHandler<Codes> {
+    fn codes() -> Vec<u8> {
+        Codes::get()
+    }
+
+    fn max_weight(code: u8) -> Option<Weight> {
+        Codes::get().contains(&code).then_some(CUSTOM_HANDLER_WEIGHT)
+    }
+
+    fn handle(code: u8, source_parachain_id: u32, message_data: &[u8]) -> Result<Weight, DispatchError> {
+        let ref_time = u64::decode(&mut &message_data[..]).map_err(|_| DispatchError::Other("Malformed custom message"))?;
+        HandledCustomMessages::mutate(|messages| messages.push((code, source_parachain_id, message_data.to_vec())));
+        Ok(Weight::from_parts(ref_time, 0))
+    }
+}
+
+/// Records settled operations in [`SettledOperations`]
+pub struct MockSettlement;
+impl OperationSettlementHandler<AccountId, AssetId, Balance, sp_core::H256> for MockSettlement {
+    fn on_operation_completed(operation_key: sp_core::H256) {
+        SettledOperations::mutate(|settled| settled.push((operation_key, None)));
+    }
+
+    fn on_operation_refunded(
+        _owner: &AccountId,
+        operation_key: sp_core::H256,
+        _escrow: &[(AssetId, Balance)],
+        reason: RefundReason,
+    ) {
+        SettledOperations::mutate(|settled| settled.push((operation_key, Some(reason))));
+    }
+
+    fn max_weight() -> Weight {
+        Weight::from_parts(20_000, 0)
+    }
+}
+
+impl pallet_cross_chain::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type AssetId = AssetId;
+    type Balance = Balance;
+    type SwapHandler = ();
+    type PriceHandler = ();
+    type CustomHandlers = (MockCustomHandler<TelemetryCodes>, MockCustomHandler<ExtraCodes>);
+    type LiquidityHandler = MockLiquidity;
+    type SettlementHandler = MockSettlement;
+    type ExpirationProvider = RelayClock;
+    type MaxOperationLifetime = ConstU64<100>;
+    type NonceWindow = ConstU64<16>;
+    type MaxPrunedPerBlock = MaxPrunedPerBlock;
+    type RuntimeCall = RuntimeCall;
+    type XcmSender = XcmRouter;
+    type ReceiveOrigin = EnsureSiblingParachain;
+    type SelfParaId = SelfParaId;
+    type ErrorCorrection = ErrorCorrection;
+    type ChannelOrigin = EnsureRoot<AccountId>;
+    type MaxMessageTypes = ConstU32<16>;
+    type RemoteCallWeight = RemoteCallWeight;
+    type SendMessageWeight = SendMessageWeight;
+    type Assets = Assets;
+    type AssetIdToLocation = AssetsLocation;
+    type LocationToAccountId = LocationToAccountId;
+    type PalletId = CrossChainPalletId;
+    type MaxOperationAssets = ConstU32<4>;
+    type ResultTimeout = ConstU64<10>;
+}
+
+#[frame_support::pallet]
+pub mod mock_msg_queue {
+    use super::*;
+    use frame_support::pallet_prelude::*;
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+        type XcmExecutor: ExecuteXcm<Self::RuntimeCall>;
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {}
+
+    #[pallet::pallet]
+    #[pallet::without_storage_info]
+    pub struct Pallet<T>(_);
+
+    #[pallet::storage]
+    #[pallet::getter(fn parachain_id)]
+    pub(super) type ParachainId<T: Config> = StorageValue<_, ParaId, ValueQuery>;
+
+    impl<T: Config> Get<ParaId> for Pallet<T> {
+        fn get() -> ParaId {
+            Self::parachain_id()
+        }
+    }
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// An XCMP message was executed
+        Executed { sender: ParaId, outcome: Outcome },
+        /// An XCMP message used an unsupported XCM version
+        BadVersion { sender: ParaId },
+    }
+
+    impl<T: Config> Pallet<T> {
+        pub fn set_para_id(para_id: ParaId) {
+            ParachainId::<T>::put(para_id);
+        }
+    }
+
+    impl<T: Config> XcmpMessageHandler for Pallet<T> {
+        fn handle_xcmp_messages<'a, I: Iterator<Item = (ParaId, RelayBlockNumber, &'a [u8])>>(
+            iter: I,
+            max_weight: Weight,
+        ) -> Weight {
+            for (sender, _sent_at, data) in iter {
+                let mut data = data;
+                XcmpMessageFormat::decode(&mut data).expect("simulator encodes concatenated versioned XCM");
+                while !data.is_empty() {
+                    let versioned = VersionedXcm::<T::RuntimeCall>::decode(&mut data)
+                        .expect("simulator encodes versioned XCM");
+                    let message_hash = versioned.using_encoded(sp_io::hashing::blake2_256);
+                    let event = match Xcm::<T::RuntimeCall>::try_from(versioned) {
+                        Ok(message) => {
+                            let origin = (Parent, Parachain(sender.into()));
+                            let outcome = T::XcmExecutor::execute_xcm(origin, message, message_hash, max_weight);
+                            Event::Executed { sender, outcome }
+                        },
+                        Err(()) => Event::BadVersion { sender },
+                    };
+                    Self::deposit_event(event);
+                }
+            }
+            max_weight
+        }
+    }
+
+    impl<T: Config> DmpMessageHandler for Pallet<T> {
+        fn handle_dmp_messages(iter: impl Iterator<Item = (RelayBlockNumber, Vec<u8>)>, limit: Weight) -> Weight {
+            for (_sent_at, data) in iter {
+                let message_hash = sp_io::hashing::blake2_256(&data);
+                if let Ok(message) = VersionedXcm::<T::RuntimeCall>::decode(&mut &data[..])
+                    .map_err(|_| ())
+                    .and_then(Xcm::<T::RuntimeCall>::try_from)
+                {
+                    T::XcmExecutor::execute_xcm(Parent, message, message_hash, limit);
+                }
+            }
+            limit
+        }
+    }
+}
+
+impl mock_msg_queue::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type XcmExecutor = XcmExecutor<XcmConfig>;
+}
@@ -0,0 +1,1012 @@
+use crate::{mock::*, *};
+use codec::Encode;
+use frame_support::{
+    assert_noop, assert_ok,
+    traits::{
+        fungibles::{Inspect, Mutate},
+        Hooks,
+    },
+    weights::Weight,
+};
+use mock::parachain::{mock_msg_queue, Assets, CrossChain, Runtime, RuntimeEvent, RuntimeOrigin, System};
+use sp_core::H256;
+use sp_runtime::AccountId32;
+use xcm::latest::{Junction::Parachain, Junctions::X1, MultiLocation, Outcome};
+use xcm_simulator::TestExt;
+
+const AMOUNT: u128 = 1_000;
+
+fn balance(who: &AccountId32) -> u128 {
+    <Assets as Inspect<AccountId32>>::balance(ASSET, who)
+}
+
+fn add_liquidity(target_parachain_id: u32, operation_id: u8) -> LiquidityOperationOf<Runtime> {
+    add_liquidity_of(ALICE, target_parachain_id, operation_id)
+}
+
+fn add_liquidity_of(provider: AccountId32, target_parachain_id: u32, operation_id: u8) -> LiquidityOperationOf<Runtime> {
+    LiquidityOperation::AddLiquidity {
+        provider,
+        pool_id: H256::repeat_byte(1),
+        assets: vec![(ASSET, AMOUNT)],
+        target_parachain_id,
+        min_shares: AMOUNT,
+        operation_id: H256::repeat_byte(operation_id),
+        expiration: RelayBlock::get() + 50,
+    }
+}
+
+fn executed_operations() -> Vec<(u32, OperationOutcome<u32, u128>)> {
+    para_events()
+        .into_iter()
+        .filter_map(|event| match event {
+            RuntimeEvent::CrossChain(Event::OperationExecuted { source_parachain_id, outcome, .. }) =>
+                Some((source_parachain_id, outcome)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn received_results() -> Vec<(u32, OperationOutcome<u32, u128>)> {
+    para_events()
+        .into_iter()
+        .filter_map(|event| match event {
+            RuntimeEvent::CrossChain(Event::OperationResultReceived { source_parachain_id, outcome, .. }) =>
+                Some((source_parachain_id, outcome)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Origin of a message sent by the sibling `para_id`
+fn sibling_origin(para_id: u32) -> RuntimeOrigin {
+    pallet_xcm::Origin::Xcm(MultiLocation::new(1, X1(Parachain(para_id)))).into()
+}
+
+#[test]
+fn liquidity_operation_round_trips_between_parachains() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        assert_ok!(CrossChain::submit_liquidity_operation(
+            RuntimeOrigin::signed(ALICE),
+            ELXR,
+            add_liquidity(ELXR, 1),
+        ));
+        assert_eq!(balance(&CrossChain::account_id()), AMOUNT);
+    });
+
+    Elxr::execute_with(|| {
+        assert_eq!(executed_operations(), vec![(NRSH, OperationOutcome::LiquidityAdded { shares: AMOUNT })]);
+        assert_eq!(balance(&POOL), INITIAL_BALANCE + AMOUNT);
+        assert_eq!(balance(&sibling_account(NRSH)), 0);
+    });
+
+    Nrsh::execute_with(|| {
+        assert_eq!(received_results(), vec![(ELXR, OperationOutcome::LiquidityAdded { shares: AMOUNT })]);
+        assert!(OutboundOperations::<Runtime>::iter().next().is_none());
+        // The escrow now backs the derivatives held on the target chain
+        assert_eq!(balance(&CrossChain::account_id()), 0);
+        assert_eq!(balance(&sibling_account(ELXR)), AMOUNT);
+        assert_eq!(balance(&ALICE), INITIAL_BALANCE - AMOUNT);
+    });
+}
+
+#[test]
+fn failed_operation_refunds_the_escrow() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        parachain::FailOperations::set(true);
+        assert_ok!(CrossChain::submit_liquidity_operation(
+            RuntimeOrigin::signed(ALICE),
+            ELXR,
+            add_liquidity(ELXR, 1),
+        ));
+        assert_eq!(balance(&ALICE), INITIAL_BALANCE - AMOUNT);
+    });
+
+    Elxr::execute_with(|| {
+        assert!(matches!(executed_operations()[..], [(NRSH, OperationOutcome::Failed { .. })]));
+        // The derivatives delivered with the operation are burnt
+        assert_eq!(balance(&POOL), INITIAL_BALANCE);
+        assert_eq!(balance(&sibling_account(NRSH)), 0);
+        assert_eq!(Assets::total_issuance(ASSET), 3 * INITIAL_BALANCE);
+    });
+
+    Nrsh::execute_with(|| {
+        assert!(System::events().iter().any(|record| matches!(
+            record.event,
+            RuntimeEvent::CrossChain(Event::OperationRefunded { reason: RefundReason::Failed, .. })
+        )));
+        assert_eq!(balance(&ALICE), INITIAL_BALANCE);
+    });
+}
+
+#[test]
+fn operations_submitted_by_other_pallets_are_escrowed_and_settled() {
+    reset_network();
+    let submit = |operation| {
+        <CrossChain as LiquidityOperationSender<_, _, _, _, _>>::submit_liquidity_operation(ELXR, operation)
+    };
+
+    let completed = Nrsh::execute_with(|| {
+        let operation = add_liquidity_of(BOB, ELXR, 1);
+        let operation_key = submit(operation.clone()).unwrap();
+        assert_eq!(operation_key, CrossChain::operation_key(&operation));
+        assert_eq!(balance(&BOB), INITIAL_BALANCE - AMOUNT);
+        operation_key
+    });
+    Elxr::execute_with(|| {
+        parachain::FailOperations::set(true);
+    });
+    let failed = Nrsh::execute_with(|| submit(add_liquidity_of(BOB, ELXR, 2)).unwrap());
+
+    Nrsh::execute_with(|| {
+        assert_eq!(
+            parachain::SettledOperations::get(),
+            vec![(completed, None), (failed, Some(RefundReason::Failed))]
+        );
+        assert_eq!(balance(&BOB), INITIAL_BALANCE - AMOUNT);
+    });
+}
+
+#[test]
+fn swap_output_is_paid_to_the_initiator_on_the_target_chain() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        assert_ok!(CrossChain::submit_liquidity_operation(
+            RuntimeOrigin::signed(BOB),
+            ELXR,
+            LiquidityOperation::ExecuteSwap {
+                initiator: BOB,
+                source_asset: ASSET,
+                target_asset: ASSET,
+                amount: AMOUNT,
+                min_receive: AMOUNT,
+                path: Vec::new(),
+                operation_id: H256::repeat_byte(1),
+                expiration: RelayBlock::get() + 50,
+            },
+        ));
+        assert_eq!(balance(&BOB), INITIAL_BALANCE - AMOUNT);
+    });
+
+    Elxr::execute_with(|| {
+        assert_eq!(executed_operations(), vec![(NRSH, OperationOutcome::SwapExecuted { amount_received: AMOUNT })]);
+        assert_eq!(balance(&BOB), INITIAL_BALANCE + AMOUNT);
+        assert_eq!(balance(&POOL), INITIAL_BALANCE);
+    });
+
+    Nrsh::execute_with(|| {
+        assert_eq!(balance(&CrossChain::account_id()), 0);
+        assert_eq!(balance(&sibling_account(ELXR)), AMOUNT);
+    });
+}
+
+#[test]
+fn operation_without_its_assets_is_rejected() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        assert_noop!(
+            CrossChain::send_liquidity_operation(ELXR, add_liquidity(ELXR, 1)),
+            Error::<Runtime>::EscrowNotReceived
+        );
+    });
+
+    Elxr::execute_with(|| {
+        // Assets already held by the sovereign account are not spent on the operation's behalf
+        assert_ok!(<Assets as Mutate<_>>::mint_into(ASSET, &sibling_account(NRSH), 2 * AMOUNT));
+        for (nonce, assets) in [vec![], vec![(ASSET, AMOUNT / 2), (ASSET, AMOUNT / 2 - 1)]].into_iter().enumerate() {
+            let operation = add_liquidity(ELXR, nonce as u8 + 1);
+            let message = NoncedOperationOf::<Runtime> { nonce: nonce as u64, operation, assets };
+            assert_ok!(CrossChain::receive_xcm_message(
+                sibling_origin(NRSH),
+                XcmMessageType::LiquidityOperation,
+                message.encode(),
+            ));
+            assert_eq!(
+                executed_operations().last(),
+                Some(&(NRSH, OperationOutcome::Failed { error: Error::<Runtime>::EscrowNotReceived.into() }))
+            );
+            assert_eq!(balance(&POOL), INITIAL_BALANCE);
+        }
+        // Only the assets listed as delivered are burnt
+        assert_eq!(balance(&sibling_account(NRSH)), AMOUNT + 1);
+    });
+}
+
+fn remove_liquidity(operation_id: u8, min_amount: u128) -> LiquidityOperationOf<Runtime> {
+    LiquidityOperation::RemoveLiquidity {
+        provider: ALICE,
+        pool_id: H256::repeat_byte(1),
+        shares: AMOUNT,
+        target_parachain_id: ELXR,
+        min_assets: vec![(ASSET, min_amount)],
+        operation_id: H256::repeat_byte(operation_id),
+        expiration: RelayBlock::get() + 50,
+    }
+}
+
+/// Submit `operation` from NRSH to ELXR, signed by its owner, returning its key
+fn submit_to_elxr(operation: LiquidityOperationOf<Runtime>) -> H256 {
+    let owner = operation.owner().clone();
+    assert_ok!(CrossChain::submit_liquidity_operation(RuntimeOrigin::signed(owner), ELXR, operation.clone()));
+    CrossChain::operation_key(&operation)
+}
+
+/// Check on ELXR that the operations from NRSH failed with `error` without moving any assets
+fn assert_failed_without_effect(count: usize, error: Error<Runtime>) {
+    let failed = (NRSH, OperationOutcome::Failed { error: error.into() });
+    assert_eq!(executed_operations(), vec![failed; count]);
+    assert_eq!((balance(&ALICE), balance(&BOB), balance(&POOL)), (INITIAL_BALANCE, INITIAL_BALANCE, INITIAL_BALANCE));
+    // The derivatives delivered with the operations are burnt
+    assert_eq!(balance(&sibling_account(NRSH)), 0);
+    assert_eq!(Assets::total_issuance(ASSET), 3 * INITIAL_BALANCE);
+}
+
+#[test]
+fn expired_operations_are_refunded_without_effect() {
+    reset_network();
+
+    let operation_key = Nrsh::execute_with(|| {
+        let operation_key = submit_to_elxr(add_liquidity(ELXR, 1));
+        // The operation arrives after it expired
+        RelayBlock::set(RelayBlock::get() + 51);
+        operation_key
+    });
+
+    Elxr::execute_with(|| assert_failed_without_effect(1, Error::<Runtime>::OperationExpired));
+
+    Nrsh::execute_with(|| {
+        assert_eq!(parachain::SettledOperations::get(), vec![(operation_key, Some(RefundReason::Failed))]);
+        assert!(OutboundOperations::<Runtime>::iter().next().is_none());
+        assert_eq!(balance(&ALICE), INITIAL_BALANCE);
+        assert_eq!(balance(&CrossChain::account_id()), 0);
+    });
+}
+
+#[test]
+fn operations_below_their_minimum_are_refunded_without_effect() {
+    reset_network();
+
+    let operation_keys = Nrsh::execute_with(|| {
+        let mut add = add_liquidity(ELXR, 1);
+        if let LiquidityOperation::AddLiquidity { min_shares, .. } = &mut add {
+            *min_shares = AMOUNT + 1;
+        }
+        let swap = LiquidityOperation::ExecuteSwap {
+            initiator: BOB,
+            source_asset: ASSET,
+            target_asset: ASSET,
+            amount: AMOUNT,
+            min_receive: AMOUNT + 1,
+            path: Vec::new(),
+            operation_id: H256::repeat_byte(2),
+            expiration: RelayBlock::get() + 50,
+        };
+        [add, swap, remove_liquidity(3, AMOUNT + 1)].map(submit_to_elxr)
+    });
+
+    Elxr::execute_with(|| assert_failed_without_effect(3, Error::<Runtime>::SlippageExceeded));
+
+    Nrsh::execute_with(|| {
+        assert_eq!(
+            parachain::SettledOperations::get(),
+            operation_keys.map(|key| (key, Some(RefundReason::Failed))).to_vec()
+        );
+        assert!(OutboundOperations::<Runtime>::iter().next().is_none());
+        assert_eq!((balance(&ALICE), balance(&BOB)), (INITIAL_BALANCE, INITIAL_BALANCE));
+        assert_eq!(balance(&CrossChain::account_id()), 0);
+    });
+}
+
+#[test]
+fn remove_liquidity_round_trips_between_parachains() {
+    reset_network();
+
+    let operation_key = Nrsh::execute_with(|| {
+        let operation_key = submit_to_elxr(remove_liquidity(1, AMOUNT));
+        // Removing liquidity spends no assets of the source chain
+        assert_eq!(balance(&CrossChain::account_id()), 0);
+        operation_key
+    });
+
+    let removed = OperationOutcome::LiquidityRemoved { assets: vec![(ASSET, AMOUNT)] };
+    Elxr::execute_with(|| {
+        assert_eq!(executed_operations(), vec![(NRSH, removed.clone())]);
+        assert_eq!(balance(&ALICE), INITIAL_BALANCE + AMOUNT);
+        assert_eq!(balance(&POOL), INITIAL_BALANCE - AMOUNT);
+    });
+
+    Nrsh::execute_with(|| {
+        assert_eq!(received_results(), vec![(ELXR, removed)]);
+        assert_eq!(parachain::SettledOperations::get(), vec![(operation_key, None)]);
+        assert!(OutboundOperations::<Runtime>::iter().next().is_none());
+        assert_eq!(balance(&ALICE), INITIAL_BALANCE);
+        assert_eq!(balance(&sibling_account(ELXR)), 0);
+    });
+}
+
+#[test]
+fn operations_flow_between_all_three_parachains() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        assert_ok!(CrossChain::submit_liquidity_operation(
+            RuntimeOrigin::signed(ALICE),
+            ELXR,
+            add_liquidity(ELXR, 1),
+        ));
+        assert_ok!(CrossChain::submit_liquidity_operation(
+            RuntimeOrigin::signed(ALICE),
+            IMRT,
+            add_liquidity(IMRT, 2),
+        ));
+    });
+    Elxr::execute_with(|| {
+        assert_ok!(CrossChain::submit_liquidity_operation(
+            RuntimeOrigin::signed(ALICE),
+            IMRT,
+            add_liquidity(IMRT, 3),
+        ));
+    });
+
+    Imrt::execute_with(|| {
+        let mut sources: Vec<_> = executed_operations().into_iter().map(|(source, _)| source).collect();
+        sources.sort_unstable();
+        assert_eq!(sources, vec![NRSH, ELXR]);
+    });
+    Elxr::execute_with(|| {
+        assert_eq!(executed_operations().len(), 1);
+        assert_eq!(received_results(), vec![(IMRT, OperationOutcome::LiquidityAdded { shares: AMOUNT })]);
+    });
+    Nrsh::execute_with(|| {
+        let mut sources: Vec<_> = received_results().into_iter().map(|(source, _)| source).collect();
+        sources.sort_unstable();
+        assert_eq!(sources, vec![ELXR, IMRT]);
+    });
+}
+
+#[test]
+fn operation_ids_are_scoped_to_their_owner() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        assert_ok!(CrossChain::submit_liquidity_operation(
+            RuntimeOrigin::signed(BOB),
+            ELXR,
+            add_liquidity_of(BOB, ELXR, 1),
+        ));
+        // Bob claiming the ID first does not block Alice's operation
+        assert_ok!(CrossChain::submit_liquidity_operation(
+            RuntimeOrigin::signed(ALICE),
+            ELXR,
+            add_liquidity(ELXR, 1),
+        ));
+        assert_noop!(
+            CrossChain::submit_liquidity_operation(RuntimeOrigin::signed(ALICE), ELXR, add_liquidity(ELXR, 1)),
+            Error::<Runtime>::OperationPending
+        );
+    });
+
+    Elxr::execute_with(|| {
+        assert_eq!(
+            executed_operations(),
+            vec![
+                (NRSH, OperationOutcome::LiquidityAdded { shares: AMOUNT }),
+                (NRSH, OperationOutcome::LiquidityAdded { shares: AMOUNT }),
+            ]
+        );
+        assert_eq!(balance(&POOL), INITIAL_BALANCE + 2 * AMOUNT);
+    });
+}
+
+/// Deliver `operation` from NRSH with `nonce`, as the XCM program carrying it would with its assets
+fn deliver(nonce: u64, operation: LiquidityOperationOf<Runtime>) {
+    let assets = vec![(ASSET, AMOUNT)];
+    assert_ok!(<Assets as Mutate<_>>::mint_into(ASSET, &sibling_account(NRSH), AMOUNT));
+    assert_ok!(CrossChain::receive_xcm_message(
+        sibling_origin(NRSH),
+        XcmMessageType::LiquidityOperation,
+        NoncedOperationOf::<Runtime> { nonce, operation, assets }.encode(),
+    ));
+}
+
+fn last_outcome() -> Option<OperationOutcome<u32, u128>> {
+    executed_operations().pop().map(|(_, outcome)| outcome)
+}
+
+fn failed(error: Error<Runtime>) -> Option<OperationOutcome<u32, u128>> {
+    Some(OperationOutcome::Failed { error: error.into() })
+}
+
+#[test]
+fn redelivered_operations_are_rejected() {
+    reset_network();
+
+    Elxr::execute_with(|| {
+        deliver(0, add_liquidity(ELXR, 1));
+        assert_eq!(last_outcome(), Some(OperationOutcome::LiquidityAdded { shares: AMOUNT }));
+        deliver(0, add_liquidity(ELXR, 1));
+        assert_eq!(last_outcome(), failed(Error::<Runtime>::DuplicateOperation));
+
+        // A new nonce does not make the same operation new
+        deliver(1, add_liquidity(ELXR, 1));
+        assert_eq!(last_outcome(), failed(Error::<Runtime>::DuplicateOperation));
+
+        // Liquidity was added once, and the redelivered assets are burnt
+        assert_eq!(balance(&POOL), INITIAL_BALANCE + AMOUNT);
+        assert_eq!(balance(&sibling_account(NRSH)), 0);
+    });
+}
+
+#[test]
+fn operations_outside_the_replay_window_are_rejected() {
+    reset_network();
+
+    Elxr::execute_with(|| {
+        deliver(20, add_liquidity(ELXR, 1));
+        assert_eq!(last_outcome(), Some(OperationOutcome::LiquidityAdded { shares: AMOUNT }));
+        assert_eq!(InboundNonces::<Runtime>::get(NRSH), 21);
+
+        // Nonces may trail the highest one received by up to `NonceWindow`
+        deliver(4, add_liquidity(ELXR, 2));
+        assert_eq!(last_outcome(), failed(Error::<Runtime>::OutsideReplayWindow));
+        deliver(5, add_liquidity(ELXR, 3));
+        assert_eq!(last_outcome(), Some(OperationOutcome::LiquidityAdded { shares: AMOUNT }));
+
+        // Expirations beyond `MaxOperationLifetime` could outlive the record of the operation
+        let mut operation = add_liquidity(ELXR, 4);
+        if let LiquidityOperation::AddLiquidity { expiration, .. } = &mut operation {
+            *expiration = RelayBlock::get() + 101;
+        }
+        deliver(21, operation);
+        assert_eq!(last_outcome(), failed(Error::<Runtime>::OutsideReplayWindow));
+
+        assert_eq!(balance(&POOL), INITIAL_BALANCE + 2 * AMOUNT);
+        assert!(!ProcessedOperations::<Runtime>::contains_key(NRSH, CrossChain::operation_key(&add_liquidity(ELXR, 2))));
+    });
+}
+
+#[test]
+fn pruned_operations_are_still_rejected() {
+    reset_network();
+
+    Elxr::execute_with(|| {
+        let operation = add_liquidity(ELXR, 1);
+        deliver(0, operation.clone());
+        assert_eq!(last_outcome(), Some(OperationOutcome::LiquidityAdded { shares: AMOUNT }));
+
+        RelayBlock::set(RelayBlock::get() + 51);
+        CrossChain::on_idle(1, Weight::MAX);
+        assert!(!ProcessedOperations::<Runtime>::contains_key(NRSH, CrossChain::operation_key(&operation)));
+
+        // Once forgotten, the operation has expired
+        deliver(1, operation);
+        assert_eq!(last_outcome(), failed(Error::<Runtime>::OperationExpired));
+        assert_eq!(balance(&POOL), INITIAL_BALANCE + AMOUNT);
+        assert_eq!(balance(&sibling_account(NRSH)), 0);
+    });
+}
+
+#[test]
+fn pruning_makes_progress_one_step_per_block() {
+    reset_network();
+    parachain::MaxPrunedPerBlock::set(1);
+
+    Nrsh::execute_with(|| {
+        for operation_id in 1..=2 {
+            assert_ok!(CrossChain::submit_liquidity_operation(
+                RuntimeOrigin::signed(ALICE),
+                ELXR,
+                add_liquidity(ELXR, operation_id),
+            ));
+        }
+    });
+
+    Elxr::execute_with(|| {
+        assert_eq!(ProcessedOperations::<Runtime>::iter().count(), 2);
+        RelayBlock::set(RelayBlock::get() + 51);
+        CrossChain::on_idle(1, Weight::MAX);
+        assert_eq!(ProcessedOperations::<Runtime>::iter().count(), 1);
+        CrossChain::on_idle(2, Weight::MAX);
+        assert_eq!(ProcessedOperations::<Runtime>::iter().count(), 0);
+        assert_eq!(OperationExpiries::<Runtime>::iter().count(), 0);
+    });
+}
+
+#[test]
+fn timed_out_operations_are_refunded_one_step_per_block() {
+    reset_network();
+    parachain::MaxPrunedPerBlock::set(1);
+    // Operations are not executed on the target chain, so no result ever arrives
+    parachain::RemoteCallWeight::set(Weight::from_parts(1, 0));
+
+    Nrsh::execute_with(|| {
+        for operation_id in 1..=2 {
+            assert_ok!(CrossChain::submit_liquidity_operation(
+                RuntimeOrigin::signed(ALICE),
+                ELXR,
+                add_liquidity(ELXR, operation_id),
+            ));
+        }
+        assert_eq!(balance(&ALICE), INITIAL_BALANCE - 2 * AMOUNT);
+
+        RelayBlock::set(RelayBlock::get() + 61);
+        CrossChain::on_idle(1, Weight::MAX);
+        assert_eq!(OutboundOperations::<Runtime>::iter().count(), 1);
+        assert_eq!(balance(&ALICE), INITIAL_BALANCE - AMOUNT);
+        CrossChain::on_idle(2, Weight::MAX);
+        assert_eq!(OutboundOperations::<Runtime>::iter().count(), 0);
+        assert_eq!(balance(&ALICE), INITIAL_BALANCE);
+    });
+}
+
+#[test]
+fn liquidity_operation_weight_covers_handler_and_result() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        let expected = Weight::from_parts(10_000, 0)
+            .saturating_add(<Runtime as frame_system::Config>::DbWeight::get().reads_writes(3 + 3 * 4, 4 + 2 * 4))
+            .saturating_add(<parachain::MockLiquidity as LiquidityHandler<_, _, _, H256>>::max_weight())
+            .saturating_add(parachain::SendMessageWeight::get());
+        assert_eq!(CrossChain::receive_message_weight(&XcmMessageType::LiquidityOperation), expected);
+        assert!(expected.all_gt(CrossChain::receive_message_weight(&XcmMessageType::PriceUpdate)));
+        assert_eq!(
+            CrossChain::receive_message_weight(&XcmMessageType::OperationResult),
+            Weight::from_parts(10_000, 0)
+                .saturating_add(<Runtime as frame_system::Config>::DbWeight::get().reads_writes(1 + 2 * 4, 2 + 2 * 4))
+                .saturating_add(<parachain::MockSettlement as OperationSettlementHandler<_, _, _, H256>>::max_weight())
+        );
+    });
+}
+
+#[test]
+fn operation_exceeding_remote_call_weight_is_not_executed() {
+    reset_network();
+    let required = Nrsh::execute_with(|| CrossChain::receive_message_weight(&XcmMessageType::LiquidityOperation));
+    parachain::RemoteCallWeight::set(required.saturating_sub(Weight::from_parts(1, 0)));
+
+    Nrsh::execute_with(|| {
+        assert_ok!(CrossChain::submit_liquidity_operation(
+            RuntimeOrigin::signed(ALICE),
+            ELXR,
+            add_liquidity(ELXR, 1),
+        ));
+    });
+
+    Elxr::execute_with(|| {
+        assert!(executed_operations().is_empty());
+        assert!(para_events().iter().any(|event| matches!(
+            event,
+            RuntimeEvent::MsgQueue(mock_msg_queue::Event::Executed { outcome: Outcome::Incomplete(..), .. })
+        )));
+    });
+}
+
+#[test]
+#[should_panic(expected = "RemoteCallWeight must cover executing a liquidity operation")]
+fn integrity_test_rejects_insufficient_remote_call_weight() {
+    reset_network();
+    parachain::RemoteCallWeight::set(Weight::from_parts(10_000, 0));
+    Nrsh::execute_with(<CrossChain as Hooks<u64>>::integrity_test);
+}
+
+#[test]
+fn set_channel_rejects_invalid_configs() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        let invalid: [fn(&mut ChannelConfig<Runtime>); 4] = [
+            |config| config.rate_limit_period = 0,
+            |config| config.max_message_size = 0,
+            |config| config.max_messages_per_period = 0,
+            |config| config.max_messages_per_period = 17,
+        ];
+        for invalidate in invalid {
+            let mut config = open_channel();
+            invalidate(&mut config);
+            assert_noop!(
+                CrossChain::set_channel(RuntimeOrigin::root(), ELXR, Box::new(config)),
+                Error::<Runtime>::InvalidChannelConfig
+            );
+        }
+
+        let mut config = open_channel();
+        config.max_messages_per_period = 16;
+        assert_ok!(CrossChain::set_channel(RuntimeOrigin::root(), ELXR, Box::new(config)));
+    });
+}
+
+#[test]
+fn oversized_frames_are_rejected_before_decoding() {
+    reset_network();
+    let config = crate::error_correction::ShardConfig { data_shards: 4, parity_shards: 2 };
+    parachain::ErrorCorrection::set(Some(config));
+    let max_frame_len = crate::error_correction::max_frame_len(4_096, config);
+
+    Nrsh::execute_with(|| {
+        let frame = crate::error_correction::apply_error_correction(&[0; 4_097], config).unwrap();
+        assert!(frame.len() > max_frame_len);
+        assert_noop!(
+            CrossChain::receive_xcm_message(sibling_origin(ELXR), XcmMessageType::PriceUpdate, frame),
+            Error::<Runtime>::MessageTooLarge
+        );
+        assert_noop!(
+            CrossChain::receive_xcm_message(sibling_origin(ELXR), XcmMessageType::PriceUpdate, vec![0; max_frame_len + 1]),
+            Error::<Runtime>::MessageTooLarge
+        );
+        // A frame within the bound is decoded
+        assert_noop!(
+            CrossChain::receive_xcm_message(sibling_origin(ELXR), XcmMessageType::PriceUpdate, vec![0; max_frame_len]),
+            Error::<Runtime>::CorruptedMessage
+        );
+    });
+}
+
+/// Open the channel from ELXR to `Custom` messages with `codes`, on top of the default types
+fn allow_custom_codes(codes: &[u8]) {
+    let mut config = open_channel();
+    for code in codes {
+        config.message_types.try_push(XcmMessageType::Custom(*code)).unwrap();
+    }
+    assert_ok!(CrossChain::set_channel(RuntimeOrigin::root(), ELXR, Box::new(config)));
+}
+
+#[test]
+fn messages_are_admitted_by_channel() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        assert_noop!(
+            CrossChain::send_xcm_message(3000, XcmMessageType::PriceUpdate, vec![]),
+            Error::<Runtime>::UnknownParachain
+        );
+        assert_noop!(
+            CrossChain::receive_xcm_message(sibling_origin(3000), XcmMessageType::PriceUpdate, vec![]),
+            Error::<Runtime>::UnknownParachain
+        );
+
+        let telemetry = XcmMessageType::Custom(custom_codes::ELXR_TELEMETRY);
+        assert_noop!(
+            CrossChain::send_xcm_message(ELXR, telemetry.clone(), 0u64.encode()),
+            Error::<Runtime>::MessageTypeNotAllowed
+        );
+        assert_noop!(
+            CrossChain::receive_xcm_message(sibling_origin(ELXR), telemetry, 0u64.encode()),
+            Error::<Runtime>::MessageTypeNotAllowed
+        );
+
+        assert_noop!(
+            CrossChain::send_xcm_message(ELXR, XcmMessageType::PriceUpdate, vec![0; 4_097]),
+            Error::<Runtime>::MessageTooLarge
+        );
+        assert_noop!(
+            CrossChain::receive_xcm_message(sibling_origin(ELXR), XcmMessageType::PriceUpdate, vec![0; 4_097]),
+            Error::<Runtime>::MessageTooLarge
+        );
+    });
+}
+
+#[test]
+fn channels_are_rate_limited_per_direction_and_period() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        let mut config = open_channel();
+        config.max_messages_per_period = 2;
+        config.message_types.try_push(XcmMessageType::Custom(custom_codes::ELXR_TELEMETRY)).unwrap();
+        assert_ok!(CrossChain::set_channel(RuntimeOrigin::root(), ELXR, Box::new(config)));
+        let send = || CrossChain::send_xcm_message(ELXR, XcmMessageType::PriceUpdate, vec![]);
+        let receive = || {
+            CrossChain::receive_xcm_message(
+                sibling_origin(ELXR),
+                XcmMessageType::Custom(custom_codes::ELXR_TELEMETRY),
+                0u64.encode(),
+            )
+        };
+
+        assert_ok!(send());
+        assert_ok!(send());
+        assert_noop!(send(), Error::<Runtime>::RateLimited);
+        // The inbound direction has a limit of its own
+        assert_ok!(receive());
+        assert_ok!(receive());
+        assert_noop!(receive(), Error::<Runtime>::RateLimited);
+
+        // The first period started at block 0
+        System::set_block_number(9);
+        assert_noop!(send(), Error::<Runtime>::RateLimited);
+        assert_noop!(receive(), Error::<Runtime>::RateLimited);
+
+        // Both limits reset once the period of 10 blocks is over
+        System::set_block_number(10);
+        assert_ok!(send());
+        assert_ok!(receive());
+        assert_eq!(
+            ChannelUsages::<Runtime>::get(ELXR, Direction::Outbound),
+            ChannelUsage { period_start: 10, messages: 1 }
+        );
+        assert_eq!(
+            ChannelUsages::<Runtime>::get(ELXR, Direction::Inbound),
+            ChannelUsage { period_start: 10, messages: 1 }
+        );
+    });
+}
+
+#[test]
+fn custom_messages_are_dispatched_to_their_handler() {
+    reset_network();
+    parachain::ExtraCodes::set(vec![0x42]);
+
+    Nrsh::execute_with(|| {
+        allow_custom_codes(&[custom_codes::ELXR_TELEMETRY, 0x42]);
+        for code in [custom_codes::ELXR_TELEMETRY, 0x42] {
+            assert_ok!(CrossChain::receive_xcm_message(
+                sibling_origin(ELXR),
+                XcmMessageType::Custom(code),
+                5_000u64.encode(),
+            ));
+            System::assert_last_event(RuntimeEvent::CrossChain(Event::MessageProcessed {
+                source_parachain_id: ELXR,
+                message_type: XcmMessageType::Custom(code),
+            }));
+        }
+        assert_eq!(
+            parachain::HandledCustomMessages::get(),
+            vec![(custom_codes::ELXR_TELEMETRY, ELXR, 5_000u64.encode()), (0x42, ELXR, 5_000u64.encode())]
+        );
+    });
+}
+
+#[test]
+fn custom_handler_weight_is_charged_up_to_its_maximum() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        allow_custom_codes(&[custom_codes::ELXR_TELEMETRY]);
+        let message_type = XcmMessageType::Custom(custom_codes::ELXR_TELEMETRY);
+        let base = Weight::from_parts(10_000, 0);
+        assert_eq!(
+            CrossChain::receive_message_weight(&message_type),
+            base.saturating_add(parachain::CUSTOM_HANDLER_WEIGHT)
+        );
+
+        let post_info =
+            CrossChain::receive_xcm_message(sibling_origin(ELXR), message_type.clone(), 5_000u64.encode()).unwrap();
+        assert_eq!(post_info.actual_weight, Some(base.saturating_add(Weight::from_parts(5_000, 0))));
+
+        // A handler reporting more than its maximum is charged the maximum
+        let post_info = CrossChain::receive_xcm_message(sibling_origin(ELXR), message_type, u64::MAX.encode()).unwrap();
+        assert_eq!(post_info.actual_weight, Some(base.saturating_add(parachain::CUSTOM_HANDLER_WEIGHT)));
+    });
+}
+
+#[test]
+fn unregistered_custom_codes_are_rejected() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        // Codes of ecosystem pallets this runtime does not register, and an unassigned code
+        let unregistered = [custom_codes::NRSH_REGISTRY, custom_codes::IMRT_COORDINATION, 0x42];
+        allow_custom_codes(&unregistered);
+        for code in unregistered {
+            assert_eq!(
+                CrossChain::receive_message_weight(&XcmMessageType::Custom(code)),
+                Weight::from_parts(10_000, 0)
+            );
+            assert_noop!(
+                CrossChain::receive_xcm_message(sibling_origin(ELXR), XcmMessageType::Custom(code), 0u64.encode()),
+                Error::<Runtime>::UnsupportedMessageType
+            );
+        }
+        assert!(parachain::HandledCustomMessages::get().is_empty());
+    });
+}
+
+#[test]
+fn protocol_reserved_codes_are_never_dispatched() {
+    reset_network();
+    // Not even to a handler claiming them
+    let reserved = [custom_codes::PROTOCOL_RESERVED_START, u8::MAX];
+    parachain::ExtraCodes::set(reserved.to_vec());
+
+    Nrsh::execute_with(|| {
+        allow_custom_codes(&reserved);
+        for code in reserved {
+            assert_noop!(
+                CrossChain::receive_xcm_message(sibling_origin(ELXR), XcmMessageType::Custom(code), 0u64.encode()),
+                Error::<Runtime>::ReservedMessageCode
+            );
+        }
+        assert!(parachain::HandledCustomMessages::get().is_empty());
+    });
+}
+
+#[test]
+fn unhandled_message_types_are_rejected() {
+    reset_network();
+
+    Nrsh::execute_with(|| {
+        assert_noop!(
+            CrossChain::receive_xcm_message(sibling_origin(ELXR), XcmMessageType::AssetTransfer, vec![]),
+            Error::<Runtime>::UnsupportedMessageType
+        );
+    });
+}
+
+#[test]
+fn integrity_test_accepts_distinct_custom_codes() {
+    reset_network();
+    parachain::ExtraCodes::set(vec![custom_codes::NRSH_REGISTRY, 0x42]);
+    Nrsh::execute_with(<CrossChain as Hooks<u64>>::integrity_test);
+}
+
+#[test]
+#[should_panic(expected = "Custom message codes must be claimed by one handler only")]
+fn integrity_test_rejects_duplicate_custom_codes() {
+    reset_network();
+    parachain::ExtraCodes::set(vec![0x42, custom_codes::ELXR_TELEMETRY]);
+    Nrsh::execute_with(<CrossChain as Hooks<u64>>::integrity_test);
+}
+
+#[test]
+#[should_panic(expected = "Custom message handlers must not claim protocol reserved codes")]
+fn integrity_test_rejects_protocol_reserved_custom_codes() {
+    reset_network();
+    parachain::ExtraCodes::set(vec![custom_codes::PROTOCOL_RESERVED_START]);
+    Nrsh::execute_with(<CrossChain as Hooks<u64>>::integrity_test);
+}
+
+mod error_correction {
+    use crate::error_correction::{apply_error_correction, max_frame_len, verify_and_correct, CodecError, ShardConfig};
+    use proptest::prelude::*;
+
+    const CONFIGS: [(u8, u8); 6] = [(1, 0), (1, 1), (2, 1), (4, 2), (10, 4), (200, 55)];
+
+    fn config(data_shards: u8, parity_shards: u8) -> ShardConfig {
+        ShardConfig { data_shards, parity_shards }
+    }
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 + 7) as u8).collect()
+    }
+
+    fn header_len(config: ShardConfig) -> usize {
+        15 + (config.total_shards() + 1) * 4
+    }
+
+    /// Flip bytes of the given shards of a frame
+    fn corrupt(frame: &mut [u8], config: ShardConfig, payload_len: usize, shards: &[usize]) {
+        let shard_size = payload_len.div_ceil(config.data_shards as usize).max(1);
+        for shard in shards {
+            frame[header_len(config) + shard * shard_size] ^= 0xff;
+        }
+    }
+
+    #[test]
+    fn round_trips_across_shard_configs() {
+        for (data_shards, parity_shards) in CONFIGS {
+            for len in [0, 1, 37, 1_000] {
+                let payload = payload(len);
+                let frame = apply_error_correction(&payload, config(data_shards, parity_shards)).unwrap();
+                assert_eq!(verify_and_correct(&frame), Ok(payload), "config ({data_shards}, {parity_shards})");
+            }
+        }
+    }
+
+    #[test]
+    fn max_frame_len_bounds_every_shorter_payload() {
+        for (data_shards, parity_shards) in CONFIGS {
+            let config = config(data_shards, parity_shards);
+            for len in [0, 1, 37, 1_000] {
+                let bound = max_frame_len(len, config);
+                assert_eq!(apply_error_correction(&payload(len), config).unwrap().len(), bound);
+                for shorter in [0, len / 2, len.saturating_sub(1)] {
+                    assert!(apply_error_correction(&payload(shorter), config).unwrap().len() <= bound);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn recovers_exactly_parity_shards_corrupted_shards() {
+        let config = config(4, 2);
+        let payload = payload(100);
+        let frame = apply_error_correction(&payload, config).unwrap();
+        for first in 0..config.total_shards() {
+            for second in first + 1..config.total_shards() {
+                let mut corrupted = frame.clone();
+                corrupt(&mut corrupted, config, payload.len(), &[first, second]);
+                assert_eq!(verify_and_correct(&corrupted), Ok(payload.clone()), "shards {first} and {second}");
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_more_corrupted_shards_than_parity_shards() {
+        for (data_shards, parity_shards) in CONFIGS {
+            let config = config(data_shards, parity_shards);
+            let payload = payload(1_000);
+            let mut frame = apply_error_correction(&payload, config).unwrap();
+            let shards: Vec<usize> = (0..=parity_shards as usize).collect();
+            corrupt(&mut frame, config, payload.len(), &shards);
+            assert_eq!(verify_and_correct(&frame), Err(CodecError::TooManyCorruptedShards));
+        }
+    }
+
+    #[test]
+    fn rejects_corrupted_headers() {
+        let config = config(4, 2);
+        let frame = apply_error_correction(&payload(100), config).unwrap();
+
+        let mut corrupted = frame.clone();
+        corrupted[0] ^= 0xff;
+        assert_eq!(verify_and_correct(&corrupted), Err(CodecError::UnsupportedVersion));
+
+        // Shard counts change the header length, so are rejected one way or another
+        for index in 1..3 {
+            let mut corrupted = frame.clone();
+            corrupted[index] ^= 0x01;
+            assert!(verify_and_correct(&corrupted).is_err());
+        }
+
+        for index in 3..header_len(config) {
+            let mut corrupted = frame.clone();
+            corrupted[index] ^= 0x01;
+            assert_eq!(verify_and_correct(&corrupted), Err(CodecError::CorruptedHeader), "byte {index}");
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_and_extended_frames() {
+        let frame = apply_error_correction(&payload(100), config(4, 2)).unwrap();
+        for len in 0..frame.len() {
+            assert!(verify_and_correct(&frame[..len]).is_err(), "length {len}");
+        }
+        assert_eq!(verify_and_correct(&frame[..frame.len() - 1]), Err(CodecError::Truncated));
+
+        let mut extended = frame.clone();
+        extended.push(0);
+        assert_eq!(verify_and_correct(&extended), Err(CodecError::Truncated));
+    }
+
+    #[test]
+    fn rejects_invalid_shard_configs() {
+        assert_eq!(apply_error_correction(b"payload", config(0, 1)), Err(CodecError::InvalidShardConfig));
+        assert_eq!(apply_error_correction(b"payload", config(200, 56)), Err(CodecError::InvalidShardConfig));
+    }
+
+    proptest! {
+        #[test]
+        fn corrects_up_to_parity_shards_corrupted_shards(
+            payload in proptest::collection::vec(any::<u8>(), 0..512),
+            data_shards in 1u8..16,
+            parity_shards in 0u8..8,
+            corrupted in proptest::collection::btree_set(0usize..24, 0..8),
+            flip in 1u8..,
+        ) {
+            let config = config(data_shards, parity_shards);
+            let mut frame = apply_error_correction(&payload, config).unwrap();
+            let shard_size = payload.len().div_ceil(data_shards as usize).max(1);
+            let corrupted: Vec<usize> = corrupted
+                .into_iter()
+                .filter(|shard| *shard < config.total_shards())
+                .take(parity_shards as usize)
+                .collect();
+            for shard in &corrupted {
+                frame[header_len(config) + shard * shard_size + shard_size / 2] ^= flip;
+            }
+            prop_assert_eq!(verify_and_correct(&frame), Ok(payload));
+        }
+
+        #[test]
+        fn never_panics_on_arbitrary_frames(frame in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let _ = verify_and_correct(&frame);
+        }
+    }
+}
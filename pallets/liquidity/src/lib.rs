@@ -2,16 +2,61 @@
 //!
 //! This pallet implements a shared liquidity pool across all chains in the Matrix-Magiq
 //! ecosystem (NRSH, ELXR, IMRT) with quantum-resistant operations.
+//!
+//! Each chain holds its own pools, whose assets are held by an account derived from the pool ID
+//! (see [`Pallet::pool_account`]). Governance links a local pool to its counterpart on other
+//! chains with `link_pool`; `report_reserves` publishes a pool's reserves to the linked chains
+//! as `PriceUpdate` messages. When the prices of linked pools drift apart by more than the
+//! configured threshold, anyone may call `rebalance`, which withdraws part of the
+//! protocol-owned liquidity held by the pallet account and adds it to the remote pool through
+//! an `AddLiquidity` operation carrying the withdrawn assets. If the operation fails or times
+//! out, the refunded assets are added back to the local pool. Use `plan_rebalance` or
+//! [`rebalance::simulate`] to dry-run.
+//!
+//! Besides per-chain pools, LPs can hold shares of one pool spanning all chains; see
+//! [`unified`].
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
 pub use pallet::*;
 
+pub mod rebalance;
+pub mod unified;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
 #[frame_support::pallet]
 pub mod pallet {
-    use frame_support::pallet_prelude::*;
+    use crate::{
+        rebalance::{self, PoolReserves},
+        unified::{UnifiedPoolMessage, UnifiedPoolRole},
+    };
+    use frame_support::{
+        pallet_prelude::*,
+        storage::with_storage_layer,
+        traits::tokens::{
+            fungibles::{Inspect, Mutate},
+            Preservation,
+        },
+        PalletId,
+    };
     use frame_system::pallet_prelude::*;
-    use sp_std::vec::Vec;
+    use pallet_cross_chain::{
+        custom_codes, CrossChainMessenger, CustomMessageHandler, LiquidityHandler, LiquidityOperation,
+        LiquidityOperationSender, OperationSettlementHandler, PriceUpdateHandler, RefundReason, XcmMessageType,
+    };
+    use sp_runtime::{
+        helpers_128bit::multiply_by_rational_with_rounding,
+        traits::{
+            AccountIdConversion, AtLeast32BitUnsigned, BlockNumberProvider, CheckedAdd, Hash, SaturatedConversion,
+            Saturating, Zero,
+        },
+        Rounding,
+    };
+    use sp_std::{vec, vec::Vec};
     
     #[pallet::pallet]
     pub struct Pallet<T>(_);
@@ -26,6 +71,10 @@ pub mod pallet {
         
         /// Asset identifier type
         type AssetId: Member + Parameter + MaxEncodedLen + Copy;
+
+        /// Assets held by pools
+        type Assets: Mutate<Self::AccountId>
+            + Inspect<Self::AccountId, AssetId = Self::AssetId, Balance = BalanceOf<Self>>;
         
         /// Max number of assets in a liquidity pool
         #[pallet::constant]
@@ -34,6 +83,38 @@ pub mod pallet {
         /// Maximum swap path length
         #[pallet::constant]
         type MaxSwapPathLength: Get<u32>;
+
+        /// Messenger used to publish reserve reports
+        type Messenger: CrossChainMessenger;
+
+        /// Sender of rebalancing operations to other parachains
+        type OperationSender: LiquidityOperationSender<
+            Self::AccountId,
+            Self::AssetId,
+            BalanceOf<Self>,
+            BlockNumberFor<Self>,
+            Self::Hash,
+        >;
+
+        /// Clock shared by all parachains, used for operation expiration
+        type ExpirationProvider: BlockNumberProvider<BlockNumber = BlockNumberFor<Self>>;
+
+        /// Time a rebalancing operation may take to execute on the remote chain
+        #[pallet::constant]
+        type RebalanceLifetime: Get<BlockNumberFor<Self>>;
+
+        /// Origin managing linked pools and rebalancing limits
+        type RebalanceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Pallet ID, used to derive the account holding protocol-owned liquidity
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+
+        /// Role of this chain in the unified pool
+        type UnifiedRole: Get<UnifiedPoolRole>;
+
+        /// Origin managing the unified pool
+        type UnifiedPoolOrigin: EnsureOrigin<Self::RuntimeOrigin>;
     }
 
     #[pallet::storage]
@@ -53,6 +134,115 @@ pub mod pallet {
         ValueQuery,
     >;
     
+    /// Liquidity shares held by each provider in each pool
+    #[pallet::storage]
+    pub type LiquidityShares<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::Hash,
+        Blake2_128Concat,
+        T::AccountId,
+        BalanceOf<T>,
+        ValueQuery,
+    >;
+    
+    /// Counterparts of local pools on other parachains
+    #[pallet::storage]
+    pub type LinkedPools<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::Hash,
+        Twox64Concat,
+        u32,
+        T::Hash,
+    >;
+    
+    /// Latest reserves reported by other parachains for their pools
+    #[pallet::storage]
+    pub type RemoteReserves<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        u32,
+        Blake2_128Concat,
+        T::Hash,
+        RemoteReserve<T>,
+    >;
+    
+    /// Limits within which rebalancing may move liquidity; rebalancing is disabled when unset
+    #[pallet::storage]
+    pub type RebalanceLimitsConfig<T: Config> = StorageValue<_, RebalanceLimits<T>>;
+    
+    /// Block each pool was last rebalanced towards each parachain
+    #[pallet::storage]
+    pub type LastRebalanced<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::Hash,
+        Twox64Concat,
+        u32,
+        BlockNumberFor<T>,
+    >;
+    
+    /// Nonce making rebalancing operation IDs unique
+    #[pallet::storage]
+    pub type NextRebalanceNonce<T: Config> = StorageValue<_, u64, ValueQuery>;
+    
+    /// Pool and parachain of each rebalancing operation awaiting its result, by operation key
+    #[pallet::storage]
+    pub type PendingRebalances<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, (T::Hash, u32)>;
+    
+    /// Assets making up the unified pool
+    #[pallet::storage]
+    pub type UnifiedPoolAssets<T: Config> = StorageValue<_, BoundedVec<T::AssetId, T::MaxAssetsPerPool>, ValueQuery>;
+    
+    /// Whether unified pool deposits and withdrawals are paused
+    #[pallet::storage]
+    pub type UnifiedPaused<T: Config> = StorageValue<_, bool, ValueQuery>;
+    
+    /// Hub: unified pool shares held by each account
+    #[pallet::storage]
+    pub type UnifiedShares<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+    
+    /// Hub: total unified pool shares
+    #[pallet::storage]
+    pub type TotalUnifiedShares<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+    
+    /// Hub: reserves of each asset across all spokes
+    #[pallet::storage]
+    pub type GlobalReserves<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, BalanceOf<T>, ValueQuery>;
+    
+    /// Hub: reserves of each asset held by each spoke
+    #[pallet::storage]
+    pub type SpokeReserves<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        u32,
+        Blake2_128Concat,
+        T::AssetId,
+        BalanceOf<T>,
+        ValueQuery,
+    >;
+    
+    /// Hub: messages sent to each spoke
+    #[pallet::storage]
+    pub type HubMessagesSent<T: Config> = StorageMap<_, Twox64Concat, u32, u64, ValueQuery>;
+    
+    /// Spoke: unified pool reserves held by this chain
+    #[pallet::storage]
+    pub type UnifiedReserves<T: Config> = StorageMap<_, Blake2_128Concat, T::AssetId, BalanceOf<T>, ValueQuery>;
+    
+    /// Spoke: requests awaiting an answer from the hub
+    #[pallet::storage]
+    pub type PendingUnifiedRequests<T: Config> = StorageMap<_, Blake2_128Concat, T::Hash, PendingUnifiedRequest<T>>;
+    
+    /// Spoke: messages applied from the hub
+    #[pallet::storage]
+    pub type HubMessagesApplied<T: Config> = StorageValue<_, u64, ValueQuery>;
+    
+    /// Spoke: nonce making unified request IDs unique
+    #[pallet::storage]
+    pub type NextUnifiedRequestNonce<T: Config> = StorageValue<_, u64, ValueQuery>;
+    
     #[pallet::storage]
     pub type CrossChainSwaps<T: Config> = StorageMap<
         _,
@@ -62,7 +252,11 @@ pub mod pallet {
     >;
     
     /// Liquidity pool representation
-    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    #[derive(
+        CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, TypeInfo, MaxEncodedLen,
+    )]
+    #[scale_info(skip_type_params(T))]
+    #[codec(mel_bound())]
     pub struct LiquidityPool<T: Config> {
         /// Pool ID
         pub id: T::Hash,
@@ -79,11 +273,15 @@ pub mod pallet {
         /// Pool state
         pub state: PoolState,
         /// Creation timestamp
-        pub created_at: T::BlockNumber,
+        pub created_at: BlockNumberFor<T>,
     }
     
     /// Asset in a liquidity pool
-    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    #[derive(
+        CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, TypeInfo, MaxEncodedLen,
+    )]
+    #[scale_info(skip_type_params(T))]
+    #[codec(mel_bound())]
     pub struct PoolAsset<T: Config> {
         /// Asset ID
         pub asset_id: T::AssetId,
@@ -118,7 +316,11 @@ pub mod pallet {
     }
     
     /// Cross-chain swap operation
-    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+    #[derive(
+        CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, TypeInfo, MaxEncodedLen,
+    )]
+    #[scale_info(skip_type_params(T))]
+    #[codec(mel_bound())]
     pub struct CrossChainSwap<T: Config> {
         /// Swap ID
         pub id: T::Hash,
@@ -141,9 +343,9 @@ pub mod pallet {
         /// Swap status
         pub status: SwapStatus,
         /// Created at block
-        pub created_at: T::BlockNumber,
+        pub created_at: BlockNumberFor<T>,
         /// Expires at block
-        pub expires_at: T::BlockNumber,
+        pub expires_at: BlockNumberFor<T>,
         /// Swap result (if completed)
         pub result: Option<SwapResult<T>>,
     }
@@ -164,23 +366,117 @@ pub mod pallet {
     }
     
     /// Swap result
-    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    #[derive(
+        CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, TypeInfo, MaxEncodedLen,
+    )]
+    #[scale_info(skip_type_params(T))]
+    #[codec(mel_bound())]
     pub struct SwapResult<T: Config> {
         /// Amount received
         pub amount_received: BalanceOf<T>,
         /// Fees paid
         pub fees_paid: BalanceOf<T>,
         /// Completion block
-        pub completed_at: T::BlockNumber,
+        pub completed_at: BlockNumberFor<T>,
+    }
+    
+    /// Reserves of a pool on another parachain
+    #[derive(
+        CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, TypeInfo, MaxEncodedLen,
+    )]
+    #[scale_info(skip_type_params(T))]
+    #[codec(mel_bound())]
+    pub struct RemoteReserve<T: Config> {
+        /// Asset reserves
+        pub reserves: BoundedVec<(T::AssetId, BalanceOf<T>), T::MaxAssetsPerPool>,
+        /// Block the report was received at
+        pub reported_at: BlockNumberFor<T>,
+    }
+    
+    /// Reserve report sent to linked parachains in `PriceUpdate` messages
+    #[derive(
+        CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, TypeInfo,
+    )]
+    #[scale_info(skip_type_params(T))]
+    pub struct ReserveReport<T: Config> {
+        /// Pool ID on the sending chain
+        pub pool_id: T::Hash,
+        /// Asset reserves
+        pub reserves: Vec<(T::AssetId, BalanceOf<T>)>,
+    }
+    
+    /// Governance limits on rebalancing
+    #[derive(
+        CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, TypeInfo, MaxEncodedLen,
+    )]
+    #[scale_info(skip_type_params(T))]
+    #[codec(mel_bound())]
+    pub struct RebalanceLimits<T: Config> {
+        /// Minimum price deviation between linked pools, in basis points, before rebalancing
+        pub min_deviation_basis_points: u32,
+        /// Maximum share of the protocol-owned liquidity moved per rebalance, in basis points
+        pub max_move_basis_points: u32,
+        /// Minimum blocks between rebalances of a pool towards the same parachain
+        pub cooldown: BlockNumberFor<T>,
+        /// Maximum age of the remote reserve report, in blocks
+        pub max_report_age: BlockNumberFor<T>,
+    }
+    
+    /// Liquidity a rebalance would move
+    #[derive(
+        CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, TypeInfo,
+    )]
+    #[scale_info(skip_type_params(T))]
+    pub struct RebalancePlan<T: Config> {
+        /// Pool receiving the liquidity
+        pub remote_pool_id: T::Hash,
+        /// Protocol-owned shares withdrawn from the local pool
+        pub shares: BalanceOf<T>,
+        /// Assets moved
+        pub assets: Vec<(T::AssetId, BalanceOf<T>)>,
+    }
+    
+    /// Unified pool request submitted on a spoke
+    #[derive(
+        CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, Encode, Decode, TypeInfo, MaxEncodedLen,
+    )]
+    #[scale_info(skip_type_params(T))]
+    #[codec(mel_bound())]
+    pub enum PendingUnifiedRequest<T: Config> {
+        /// Deposit of `assets`
+        Deposit {
+            /// Depositing account
+            provider: T::AccountId,
+            /// Assets deposited
+            assets: BoundedVec<(T::AssetId, BalanceOf<T>), T::MaxAssetsPerPool>,
+        },
+        /// Withdrawal of `shares`
+        Withdraw {
+            /// Withdrawing account
+            provider: T::AccountId,
+            /// Shares to burn
+            shares: BalanceOf<T>,
+        },
     }
     
+    /// Unified pool message for this runtime
+    pub type UnifiedPoolMessageOf<T> = UnifiedPoolMessage<
+        <T as frame_system::Config>::AccountId,
+        <T as Config>::AssetId,
+        BalanceOf<T>,
+        <T as frame_system::Config>::Hash,
+    >;
+    
+    /// Amount of each of several assets
+    pub type AssetAmountsOf<T> = Vec<(<T as Config>::AssetId, BalanceOf<T>)>;
+    
     /// Alias for balance type
     pub type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
     
     /// Currency trait
     pub trait Currency<AccountId> {
         /// Balance type
-        type Balance: Member + Parameter + AtLeast32BitUnsigned + Default + Copy;
+        type Balance: Member + Parameter + AtLeast32BitUnsigned + Default + Copy + MaxEncodedLen;
         
         /// Get free balance
         fn free_balance(who: &AccountId) -> Self::Balance;
@@ -252,6 +548,125 @@ pub mod pallet {
             initiator: T::AccountId,
             amount_received: BalanceOf<T>,
         },
+        
+        /// A local pool was linked to a pool on another parachain
+        PoolLinked {
+            pool_id: T::Hash,
+            parachain_id: u32,
+            remote_pool_id: T::Hash,
+        },
+        
+        /// A local pool was unlinked from another parachain
+        PoolUnlinked {
+            pool_id: T::Hash,
+            parachain_id: u32,
+        },
+        
+        /// Rebalancing limits were changed
+        RebalanceLimitsSet {
+            limits: Option<RebalanceLimits<T>>,
+        },
+        
+        /// A pool's reserves were reported to its linked parachains
+        ReservesReported {
+            pool_id: T::Hash,
+        },
+        
+        /// Another parachain reported the reserves of one of its pools
+        RemoteReservesUpdated {
+            parachain_id: u32,
+            pool_id: T::Hash,
+        },
+        
+        /// Protocol-owned liquidity was moved to a pool on another parachain
+        Rebalanced {
+            pool_id: T::Hash,
+            parachain_id: u32,
+            remote_pool_id: T::Hash,
+            shares: BalanceOf<T>,
+            assets: Vec<(T::AssetId, BalanceOf<T>)>,
+        },
+        
+        /// The refunded assets of a failed or timed out rebalance were added back to the pool
+        RebalanceReturned {
+            pool_id: T::Hash,
+            parachain_id: u32,
+            shares: BalanceOf<T>,
+        },
+        
+        /// The refunded assets of a rebalance could not be added back to the pool; they stay
+        /// with the pallet account
+        RebalanceReturnFailed {
+            pool_id: T::Hash,
+            parachain_id: u32,
+            error: DispatchError,
+        },
+        
+        /// The assets of the unified pool were set
+        UnifiedPoolAssetsSet {
+            assets: Vec<T::AssetId>,
+        },
+        
+        /// The unified pool was paused or resumed
+        UnifiedPoolPausedSet {
+            paused: bool,
+        },
+        
+        /// A unified pool request was forwarded to the hub
+        UnifiedRequestSubmitted {
+            request_id: T::Hash,
+            provider: T::AccountId,
+        },
+        
+        /// The hub credited a deposit made on this spoke
+        UnifiedDepositCompleted {
+            request_id: T::Hash,
+            provider: T::AccountId,
+            shares: BalanceOf<T>,
+        },
+        
+        /// The hub approved a withdrawal, releasing reserves held by this spoke
+        UnifiedWithdrawalCompleted {
+            request_id: T::Hash,
+            provider: T::AccountId,
+            shares: BalanceOf<T>,
+            assets: Vec<(T::AssetId, BalanceOf<T>)>,
+        },
+        
+        /// The hub rejected a unified pool request
+        UnifiedRequestRejected {
+            request_id: T::Hash,
+            provider: T::AccountId,
+            error: DispatchError,
+        },
+        
+        /// Hub: shares were minted for a deposit on a spoke
+        UnifiedDepositCredited {
+            spoke_parachain_id: u32,
+            provider: T::AccountId,
+            shares: BalanceOf<T>,
+        },
+        
+        /// Hub: shares were burnt for a withdrawal from a spoke
+        UnifiedWithdrawalApproved {
+            spoke_parachain_id: u32,
+            provider: T::AccountId,
+            shares: BalanceOf<T>,
+            assets: Vec<(T::AssetId, BalanceOf<T>)>,
+        },
+        
+        /// Hub: a spoke's reported reserves match the hub's accounting
+        UnifiedReservesVerified {
+            spoke_parachain_id: u32,
+        },
+        
+        /// Hub: a spoke's reported reserves differ from the hub's accounting; the pool is paused
+        UnifiedReserveMismatch {
+            spoke_parachain_id: u32,
+            asset_id: T::AssetId,
+            accounted: BalanceOf<T>,
+            reported: BalanceOf<T>,
+        },
     }
     
     #[pallet::error]
@@ -297,7 +712,928 @@ pub mod pallet {
         
         /// Swap already completed
         SwapAlreadyCompleted,
+        
+        /// Provider does not hold enough shares
+        InsufficientShares,
+        
+        /// Arithmetic overflow
+        Overflow,
+        
+        /// Pool is not linked to the parachain
+        PoolNotLinked,
+        
+        /// No rebalancing limits are configured
+        RebalancingDisabled,
+        
+        /// Only two-asset constant product pools can be rebalanced
+        UnsupportedPool,
+        
+        /// Remote reserves are missing or too old
+        StaleReserves,
+        
+        /// Price deviation is below the rebalancing threshold
+        DeviationBelowThreshold,
+        
+        /// Pool was rebalanced towards the parachain too recently
+        RebalanceCooldown,
+        
+        /// No protocol-owned liquidity to move
+        NothingToRebalance,
+        
+        /// Reserve report could not be decoded
+        MalformedReport,
+        
+        /// Rebalancing limits are out of range
+        InvalidRebalanceLimits,
+        
+        /// This chain is not a spoke of the unified pool
+        NotUnifiedSpoke,
+        
+        /// Unified pool is paused
+        UnifiedPoolPaused,
+        
+        /// Unified pool assets cannot change while shares are outstanding
+        UnifiedPoolInUse,
+        
+        /// Unified pool message is not expected from the sender
+        UnexpectedUnifiedMessage,
+        
+        /// Unified pool message could not be decoded
+        MalformedUnifiedMessage,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        #[cfg(feature = "try-runtime")]
+        fn try_state(_n: BlockNumberFor<T>) -> Result<(), DispatchError> {
+            Self::check_unified_invariants().map_err(Into::into)
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Link a local pool to its counterpart on another parachain
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn link_pool(
+            origin: OriginFor<T>,
+            pool_id: T::Hash,
+            parachain_id: u32,
+            remote_pool_id: T::Hash,
+        ) -> DispatchResult {
+            T::RebalanceOrigin::ensure_origin(origin)?;
+            ensure!(LiquidityPools::<T>::contains_key(pool_id), Error::<T>::PoolNotFound);
+
+            LinkedPools::<T>::insert(pool_id, parachain_id, remote_pool_id);
+            Self::deposit_event(Event::PoolLinked { pool_id, parachain_id, remote_pool_id });
+            Ok(())
+        }
+
+        /// Unlink a local pool from another parachain
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn unlink_pool(origin: OriginFor<T>, pool_id: T::Hash, parachain_id: u32) -> DispatchResult {
+            T::RebalanceOrigin::ensure_origin(origin)?;
+            ensure!(LinkedPools::<T>::contains_key(pool_id, parachain_id), Error::<T>::PoolNotLinked);
+
+            LinkedPools::<T>::remove(pool_id, parachain_id);
+            LastRebalanced::<T>::remove(pool_id, parachain_id);
+            Self::deposit_event(Event::PoolUnlinked { pool_id, parachain_id });
+            Ok(())
+        }
+
+        /// Set the rebalancing limits, or disable rebalancing with `None`
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn set_rebalance_limits(origin: OriginFor<T>, limits: Option<RebalanceLimits<T>>) -> DispatchResult {
+            T::RebalanceOrigin::ensure_origin(origin)?;
+            if let Some(limits) = &limits {
+                ensure!(limits.max_move_basis_points <= 10_000, Error::<T>::InvalidRebalanceLimits);
+            }
+
+            RebalanceLimitsConfig::<T>::set(limits.clone());
+            Self::deposit_event(Event::RebalanceLimitsSet { limits });
+            Ok(())
+        }
+
+        /// Publish a pool's reserves to all parachains it is linked to
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn report_reserves(origin: OriginFor<T>, pool_id: T::Hash) -> DispatchResult {
+            ensure_signed(origin)?;
+            let pool = LiquidityPools::<T>::get(pool_id).ok_or(Error::<T>::PoolNotFound)?;
+            let report = ReserveReport::<T> {
+                pool_id,
+                reserves: pool.assets.iter().map(|asset| (asset.asset_id, asset.balance)).collect(),
+            }
+            .encode();
+
+            let mut linked = false;
+            for (parachain_id, _) in LinkedPools::<T>::iter_prefix(pool_id) {
+                T::Messenger::send_message(parachain_id, XcmMessageType::PriceUpdate, report.clone())?;
+                linked = true;
+            }
+            ensure!(linked, Error::<T>::PoolNotLinked);
+
+            Self::deposit_event(Event::ReservesReported { pool_id });
+            Ok(())
+        }
+
+        /// Move protocol-owned liquidity from a pool to its counterpart on `parachain_id`
+        ///
+        /// Callable by anyone; the amount is set by [`Pallet::plan_rebalance`] within the
+        /// governance limits. The withdrawn assets are escrowed with the operation and added back
+        /// to the pool if it fails or times out.
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn rebalance(origin: OriginFor<T>, pool_id: T::Hash, parachain_id: u32) -> DispatchResult {
+            ensure_signed(origin)?;
+            let plan = Self::plan_rebalance(pool_id, parachain_id)?;
+            let account = Self::account_id();
+            let assets = Self::do_remove_liquidity(&account, pool_id, plan.shares)?;
+
+            let nonce = NextRebalanceNonce::<T>::mutate(|nonce| {
+                let current = *nonce;
+                *nonce = nonce.wrapping_add(1);
+                current
+            });
+            let operation_key = T::OperationSender::submit_liquidity_operation(
+                parachain_id,
+                LiquidityOperation::AddLiquidity {
+                    provider: account,
+                    pool_id: plan.remote_pool_id,
+                    assets: assets.clone(),
+                    target_parachain_id: parachain_id,
+                    min_shares: Zero::zero(),
+                    operation_id: T::Hashing::hash_of(&(b"rebalance", pool_id, parachain_id, nonce)),
+                    expiration: T::ExpirationProvider::current_block_number()
+                        .saturating_add(T::RebalanceLifetime::get()),
+                },
+            )?;
+
+            PendingRebalances::<T>::insert(operation_key, (pool_id, parachain_id));
+            LastRebalanced::<T>::insert(pool_id, parachain_id, frame_system::Pallet::<T>::block_number());
+            Self::deposit_event(Event::Rebalanced {
+                pool_id,
+                parachain_id,
+                remote_pool_id: plan.remote_pool_id,
+                shares: plan.shares,
+                assets,
+            });
+            Ok(())
+        }
+
+        /// Set the assets making up the unified pool
+        #[pallet::call_index(5)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn set_unified_pool_assets(
+            origin: OriginFor<T>,
+            assets: BoundedVec<T::AssetId, T::MaxAssetsPerPool>,
+        ) -> DispatchResult {
+            T::UnifiedPoolOrigin::ensure_origin(origin)?;
+            ensure!(TotalUnifiedShares::<T>::get().is_zero(), Error::<T>::UnifiedPoolInUse);
+            let mut unique = assets.to_vec();
+            unique.sort_by_key(|asset_id| asset_id.encode());
+            unique.dedup();
+            ensure!(unique.len() == assets.len(), Error::<T>::InvalidAssets);
+
+            UnifiedPoolAssets::<T>::put(&assets);
+            Self::deposit_event(Event::UnifiedPoolAssetsSet { assets: assets.into_inner() });
+            Ok(())
+        }
+
+        /// Pause or resume unified pool deposits and withdrawals
+        #[pallet::call_index(6)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn set_unified_pool_paused(origin: OriginFor<T>, paused: bool) -> DispatchResult {
+            T::UnifiedPoolOrigin::ensure_origin(origin)?;
+            UnifiedPaused::<T>::put(paused);
+            Self::deposit_event(Event::UnifiedPoolPausedSet { paused });
+            Ok(())
+        }
+
+        /// Deposit `assets` into the unified pool on this spoke
+        ///
+        /// `assets` must contain every unified pool asset exactly once and move to the unified
+        /// pool account. Shares are minted on the hub, which reports back with
+        /// `UnifiedDepositCompleted` or `UnifiedRequestRejected`, refunding the assets.
+        #[pallet::call_index(7)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn deposit_unified(origin: OriginFor<T>, assets: Vec<(T::AssetId, BalanceOf<T>)>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let hub_parachain_id = Self::unified_hub()?;
+            ensure!(!UnifiedPaused::<T>::get(), Error::<T>::UnifiedPoolPaused);
+            let basket = UnifiedPoolAssets::<T>::get();
+            ensure!(
+                !basket.is_empty() &&
+                    assets.len() == basket.len() &&
+                    basket.iter().all(|asset_id| assets.iter().any(|(id, _)| id == asset_id)),
+                Error::<T>::InvalidAssets
+            );
+            let assets: BoundedVec<_, T::MaxAssetsPerPool> =
+                assets.try_into().map_err(|_| Error::<T>::TooManyAssets)?;
+
+            let unified_account = Self::unified_account();
+            for (asset_id, amount) in assets.iter() {
+                T::Assets::transfer(*asset_id, &who, &unified_account, *amount, Preservation::Expendable)?;
+            }
+
+            let request_id = Self::next_unified_request_id(&who);
+            PendingUnifiedRequests::<T>::insert(
+                request_id,
+                PendingUnifiedRequest::Deposit { provider: who.clone(), assets: assets.clone() },
+            );
+            Self::send_unified_message(
+                hub_parachain_id,
+                UnifiedPoolMessage::Deposit { request_id, provider: who.clone(), assets: assets.into_inner() },
+            )?;
+
+            Self::deposit_event(Event::UnifiedRequestSubmitted { request_id, provider: who });
+            Ok(())
+        }
+
+        /// Redeem `shares` of the unified pool for reserves held by this spoke
+        ///
+        /// The hub burns the shares if this spoke holds enough of every asset, and reports back
+        /// with `UnifiedWithdrawalCompleted` or `UnifiedRequestRejected`.
+        #[pallet::call_index(8)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn withdraw_unified(origin: OriginFor<T>, shares: BalanceOf<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let hub_parachain_id = Self::unified_hub()?;
+            ensure!(!UnifiedPaused::<T>::get(), Error::<T>::UnifiedPoolPaused);
+            ensure!(!shares.is_zero(), Error::<T>::InsufficientShares);
+
+            let request_id = Self::next_unified_request_id(&who);
+            PendingUnifiedRequests::<T>::insert(
+                request_id,
+                PendingUnifiedRequest::Withdraw { provider: who.clone(), shares },
+            );
+            Self::send_unified_message(
+                hub_parachain_id,
+                UnifiedPoolMessage::Withdraw { request_id, provider: who.clone(), shares },
+            )?;
+
+            Self::deposit_event(Event::UnifiedRequestSubmitted { request_id, provider: who });
+            Ok(())
+        }
+
+        /// Report the balances of this spoke's unified pool account to the hub for verification
+        #[pallet::call_index(9)]
+        #[pallet::weight(Weight::from_parts(10_000, 0))]
+        pub fn report_unified_reserves(origin: OriginFor<T>) -> DispatchResult {
+            ensure_signed(origin)?;
+            let hub_parachain_id = Self::unified_hub()?;
+            let unified_account = Self::unified_account();
+            let reserves = UnifiedPoolAssets::<T>::get()
+                .into_iter()
+                .map(|asset_id| (asset_id, T::Assets::balance(asset_id, &unified_account)))
+                .collect();
+
+            Self::send_unified_message(
+                hub_parachain_id,
+                UnifiedPoolMessage::ReserveReport {
+                    reserves,
+                    applied_hub_messages: HubMessagesApplied::<T>::get(),
+                },
+            )
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Add `assets` of `payer` to a pool, returning the shares minted to `provider`
+        ///
+        /// `assets` must contain every pool asset exactly once. Shares are minted in proportion
+        /// to the smallest relative deposit; the first deposit mints shares equal to the amount
+        /// of the first asset.
+        pub fn do_add_liquidity(
+            provider: &T::AccountId,
+            payer: &T::AccountId,
+            pool_id: T::Hash,
+            assets: Vec<(T::AssetId, BalanceOf<T>)>,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            let pool_account = Self::pool_account(pool_id);
+            let shares = LiquidityPools::<T>::try_mutate(pool_id, |maybe_pool| -> Result<_, DispatchError> {
+                let pool = maybe_pool.as_mut().ok_or(Error::<T>::PoolNotFound)?;
+                ensure!(pool.state == PoolState::Active, Error::<T>::PoolNotActive);
+                ensure!(assets.len() == pool.assets.len(), Error::<T>::InvalidAssets);
+
+                let mut shares: Option<BalanceOf<T>> = None;
+                for pool_asset in pool.assets.iter_mut() {
+                    let amount = assets
+                        .iter()
+                        .find(|(asset_id, _)| *asset_id == pool_asset.asset_id)
+                        .map(|(_, amount)| *amount)
+                        .ok_or(Error::<T>::InvalidAssets)?;
+
+                    let minted = if pool.total_shares.is_zero() {
+                        shares.unwrap_or(amount)
+                    } else {
+                        Self::mul_div(amount, pool.total_shares, pool_asset.balance, Rounding::Down)?
+                    };
+                    shares = Some(shares.map_or(minted, |shares| shares.min(minted)));
+                    pool_asset.balance = pool_asset.balance.checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+                    T::Assets::transfer(pool_asset.asset_id, payer, &pool_account, amount, Preservation::Expendable)?;
+                }
+
+                let shares = shares.filter(|shares| !shares.is_zero()).ok_or(Error::<T>::InsufficientLiquidity)?;
+                pool.total_shares = pool.total_shares.checked_add(&shares).ok_or(Error::<T>::Overflow)?;
+                Ok(shares)
+            })?;
+
+            LiquidityShares::<T>::mutate(pool_id, provider, |owned| *owned = owned.saturating_add(shares));
+
+            Self::deposit_event(Event::LiquidityAdded {
+                pool_id,
+                provider: provider.clone(),
+                assets,
+                shares,
+            });
+
+            Ok(shares)
+        }
+
+        /// Burn `shares` of `provider` in a pool, returning the assets paid out to `provider`
+        pub fn do_remove_liquidity(
+            provider: &T::AccountId,
+            pool_id: T::Hash,
+            shares: BalanceOf<T>,
+        ) -> Result<AssetAmountsOf<T>, DispatchError> {
+            let owned = LiquidityShares::<T>::get(pool_id, provider);
+            ensure!(!shares.is_zero() && owned >= shares, Error::<T>::InsufficientShares);
+
+            let assets = LiquidityPools::<T>::try_mutate(pool_id, |maybe_pool| -> Result<_, DispatchError> {
+                let pool = maybe_pool.as_mut().ok_or(Error::<T>::PoolNotFound)?;
+                ensure!(pool.state != PoolState::Closed, Error::<T>::PoolNotActive);
+
+                let mut assets = Vec::with_capacity(pool.assets.len());
+                for pool_asset in pool.assets.iter_mut() {
+                    let amount = Self::mul_div(pool_asset.balance, shares, pool.total_shares, Rounding::Down)?;
+                    pool_asset.balance = pool_asset.balance.saturating_sub(amount);
+                    assets.push((pool_asset.asset_id, amount));
+                }
+
+                pool.total_shares = pool.total_shares.saturating_sub(shares);
+                Ok(assets)
+            })?;
+
+            LiquidityShares::<T>::insert(pool_id, provider, owned.saturating_sub(shares));
+            let pool_account = Self::pool_account(pool_id);
+            for (asset_id, amount) in assets.iter().filter(|(_, amount)| !amount.is_zero()) {
+                T::Assets::transfer(*asset_id, &pool_account, provider, *amount, Preservation::Expendable)?;
+            }
+
+            Self::deposit_event(Event::LiquidityRemoved {
+                pool_id,
+                provider: provider.clone(),
+                assets: assets.clone(),
+                shares,
+            });
+
+            Ok(assets)
+        }
+
+        /// Swap `amount_in` of `path[0]` held by `payer` along `path` through constant product
+        /// pools, returning the amount of the last asset paid out to `who`
+        pub fn do_swap(
+            who: &T::AccountId,
+            payer: &T::AccountId,
+            path: Vec<T::AssetId>,
+            amount_in: BalanceOf<T>,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            ensure!(path.len() >= 2, Error::<T>::InvalidAssets);
+            ensure!(path.len() as u32 <= T::MaxSwapPathLength::get(), Error::<T>::SwapPathTooLong);
+
+            // Each hop's input is paid by the previous hop's pool
+            let mut holder = payer.clone();
+            let mut amount_out = amount_in;
+            for hop in path.windows(2) {
+                let (pool_id, received) = Self::swap_in_pool(hop[0], hop[1], amount_out)?;
+                let pool_account = Self::pool_account(pool_id);
+                T::Assets::transfer(hop[0], &holder, &pool_account, amount_out, Preservation::Expendable)?;
+                holder = pool_account;
+                amount_out = received;
+            }
+            T::Assets::transfer(path[path.len() - 1], &holder, who, amount_out, Preservation::Expendable)?;
+
+            Self::deposit_event(Event::SwapExecuted {
+                who: who.clone(),
+                asset_in: path[0],
+                asset_out: path[path.len() - 1],
+                amount_in,
+                amount_out,
+            });
+
+            Ok(amount_out)
+        }
+
+        /// Swap through the first active constant product pool holding both assets, returning
+        /// the pool and the amount out
+        fn swap_in_pool(
+            asset_in: T::AssetId,
+            asset_out: T::AssetId,
+            amount_in: BalanceOf<T>,
+        ) -> Result<(T::Hash, BalanceOf<T>), DispatchError> {
+            let pool_id = AssetPools::<T>::get(asset_in)
+                .into_iter()
+                .find(|pool_id| {
+                    LiquidityPools::<T>::get(pool_id).is_some_and(|pool| {
+                        pool.state == PoolState::Active &&
+                            pool.pool_type == PoolType::ConstantProduct &&
+                            pool.assets.iter().any(|asset| asset.asset_id == asset_out)
+                    })
+                })
+                .ok_or(Error::<T>::PoolNotFound)?;
+
+            LiquidityPools::<T>::try_mutate(pool_id, |maybe_pool| {
+                let pool = maybe_pool.as_mut().ok_or(Error::<T>::PoolNotFound)?;
+                let fee_basis_points = pool.fee_basis_points;
+                let index_in = pool.assets.iter().position(|asset| asset.asset_id == asset_in)
+                    .ok_or(Error::<T>::AssetNotFound)?;
+                let index_out = pool.assets.iter().position(|asset| asset.asset_id == asset_out)
+                    .ok_or(Error::<T>::AssetNotFound)?;
+                let reserve_in = pool.assets[index_in].balance;
+                let reserve_out = pool.assets[index_out].balance;
+
+                let amount_in_after_fee = Self::mul_div(
+                    amount_in,
+                    BalanceOf::<T>::from(10_000u32.saturating_sub(fee_basis_points as u32)),
+                    BalanceOf::<T>::from(10_000u32),
+                    Rounding::Down,
+                )?;
+                let amount_out = Self::mul_div(
+                    reserve_out,
+                    amount_in_after_fee,
+                    reserve_in.checked_add(&amount_in_after_fee).ok_or(Error::<T>::Overflow)?,
+                    Rounding::Down,
+                )?;
+                ensure!(
+                    !amount_out.is_zero() && amount_out < reserve_out,
+                    Error::<T>::InsufficientLiquidity
+                );
+
+                pool.assets[index_in].balance = reserve_in.checked_add(&amount_in).ok_or(Error::<T>::Overflow)?;
+                pool.assets[index_out].balance = reserve_out - amount_out;
+                Ok((pool_id, amount_out))
+            })
+        }
+
+        /// Account holding protocol-owned liquidity
+        pub fn account_id() -> T::AccountId {
+            T::PalletId::get().into_account_truncating()
+        }
+
+        /// Spoke: account holding the unified pool reserves and the deposits awaiting the hub
+        pub fn unified_account() -> T::AccountId {
+            T::PalletId::get().into_sub_account_truncating(b"unified")
+        }
+
+        /// Account holding the assets of a pool
+        pub fn pool_account(pool_id: T::Hash) -> T::AccountId {
+            T::PalletId::get().into_sub_account_truncating(pool_id)
+        }
+
+        /// Liquidity `rebalance` would move from a pool to its counterpart on `parachain_id`
+        ///
+        /// Moves enough to bring the remote pool to the combined price of both pools, capped at
+        /// `max_move_basis_points` of the protocol-owned shares.
+        pub fn plan_rebalance(pool_id: T::Hash, parachain_id: u32) -> Result<RebalancePlan<T>, DispatchError> {
+            let limits = RebalanceLimitsConfig::<T>::get().ok_or(Error::<T>::RebalancingDisabled)?;
+            let remote_pool_id = LinkedPools::<T>::get(pool_id, parachain_id).ok_or(Error::<T>::PoolNotLinked)?;
+            let pool = LiquidityPools::<T>::get(pool_id).ok_or(Error::<T>::PoolNotFound)?;
+            ensure!(pool.state == PoolState::Active, Error::<T>::PoolNotActive);
+            ensure!(
+                pool.pool_type == PoolType::ConstantProduct && pool.assets.len() == 2,
+                Error::<T>::UnsupportedPool
+            );
+
+            let now = frame_system::Pallet::<T>::block_number();
+            ensure!(
+                LastRebalanced::<T>::get(pool_id, parachain_id)
+                    .is_none_or(|last| now >= last.saturating_add(limits.cooldown)),
+                Error::<T>::RebalanceCooldown
+            );
+            let remote = RemoteReserves::<T>::get(parachain_id, remote_pool_id)
+                .filter(|remote| now <= remote.reported_at.saturating_add(limits.max_report_age))
+                .ok_or(Error::<T>::StaleReserves)?;
+
+            let (base, quote) = (&pool.assets[0], &pool.assets[1]);
+            let remote_balance = |asset_id: T::AssetId| {
+                remote
+                    .reserves
+                    .iter()
+                    .find(|(id, _)| *id == asset_id)
+                    .map(|(_, balance)| (*balance).saturated_into::<u128>())
+                    .ok_or(Error::<T>::InvalidAssets)
+            };
+            let local_reserves = PoolReserves {
+                base: base.balance.saturated_into(),
+                quote: quote.balance.saturated_into(),
+            };
+            let remote_reserves = PoolReserves {
+                base: remote_balance(base.asset_id)?,
+                quote: remote_balance(quote.asset_id)?,
+            };
+
+            let (Some(local_price), Some(remote_price)) = (local_reserves.price(), remote_reserves.price()) else {
+                return Err(Error::<T>::InsufficientLiquidity.into());
+            };
+            ensure!(
+                rebalance::deviation_basis_points(local_price, remote_price) >= limits.min_deviation_basis_points,
+                Error::<T>::DeviationBelowThreshold
+            );
+            let wanted = rebalance::equalizing_base_amount(local_reserves, remote_reserves)
+                .ok_or(Error::<T>::NothingToRebalance)?;
+
+            let owned = LiquidityShares::<T>::get(pool_id, Self::account_id());
+            let max_shares = Self::mul_div(
+                owned,
+                BalanceOf::<T>::from(limits.max_move_basis_points),
+                BalanceOf::<T>::from(10_000u32),
+                Rounding::Down,
+            )?;
+            let wanted_shares = Self::mul_div(wanted.saturated_into(), pool.total_shares, base.balance, Rounding::Up)?;
+            let shares = wanted_shares.min(max_shares);
+            ensure!(!shares.is_zero(), Error::<T>::NothingToRebalance);
+
+            let assets = pool
+                .assets
+                .iter()
+                .map(|asset| {
+                    Self::mul_div(asset.balance, shares, pool.total_shares, Rounding::Down)
+                        .map(|amount| (asset.asset_id, amount))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(RebalancePlan { remote_pool_id, shares, assets })
+        }
+
+        /// Parachain ID of the unified pool hub, if this chain is a spoke
+        fn unified_hub() -> Result<u32, DispatchError> {
+            match T::UnifiedRole::get() {
+                UnifiedPoolRole::Spoke { hub_parachain_id } => Ok(hub_parachain_id),
+                UnifiedPoolRole::Hub => Err(Error::<T>::NotUnifiedSpoke.into()),
+            }
+        }
+
+        fn next_unified_request_id(who: &T::AccountId) -> T::Hash {
+            let nonce = NextUnifiedRequestNonce::<T>::mutate(|nonce| {
+                let current = *nonce;
+                *nonce = nonce.wrapping_add(1);
+                current
+            });
+            T::Hashing::hash_of(&(b"unified", who, nonce))
+        }
+
+        fn send_unified_message(parachain_id: u32, message: UnifiedPoolMessageOf<T>) -> DispatchResult {
+            T::Messenger::send_message(
+                parachain_id,
+                XcmMessageType::Custom(custom_codes::IMRT_COORDINATION),
+                message.encode(),
+            )
+        }
+
+        /// Maximum weight of handling a unified pool message
+        fn unified_message_weight() -> Weight {
+            let assets = T::MaxAssetsPerPool::get() as u64;
+            T::DbWeight::get().reads_writes(6 + 5 * assets, 6 + 5 * assets)
+        }
+
+        /// Handle a unified pool message according to this chain's role
+        fn handle_unified_message(source_parachain_id: u32, message: UnifiedPoolMessageOf<T>) -> DispatchResult {
+            match T::UnifiedRole::get() {
+                UnifiedPoolRole::Hub => Self::handle_spoke_message(source_parachain_id, message),
+                UnifiedPoolRole::Spoke { hub_parachain_id } => {
+                    ensure!(source_parachain_id == hub_parachain_id, Error::<T>::UnexpectedUnifiedMessage);
+                    Self::handle_hub_message(message)
+                },
+            }
+        }
+
+        /// Hub: apply a spoke request and answer it
+        ///
+        /// Failed requests are rolled back and answered with [`UnifiedPoolMessage::Rejected`] so
+        /// the spoke can settle them.
+        fn handle_spoke_message(spoke_parachain_id: u32, message: UnifiedPoolMessageOf<T>) -> DispatchResult {
+            let reply = match message {
+                UnifiedPoolMessage::Deposit { request_id, provider, assets } => {
+                    with_storage_layer(|| Self::credit_unified_deposit(spoke_parachain_id, &provider, &assets))
+                        .map(|shares| UnifiedPoolMessage::DepositCredited { request_id, shares })
+                        .unwrap_or_else(|error| UnifiedPoolMessage::Rejected { request_id, error })
+                },
+                UnifiedPoolMessage::Withdraw { request_id, provider, shares } => {
+                    with_storage_layer(|| Self::approve_unified_withdrawal(spoke_parachain_id, &provider, shares))
+                        .map(|assets| UnifiedPoolMessage::WithdrawalApproved { request_id, assets })
+                        .unwrap_or_else(|error| UnifiedPoolMessage::Rejected { request_id, error })
+                },
+                UnifiedPoolMessage::ReserveReport { reserves, applied_hub_messages } => {
+                    Self::verify_spoke_reserves(spoke_parachain_id, reserves, applied_hub_messages);
+                    return Ok(());
+                },
+                _ => return Err(Error::<T>::UnexpectedUnifiedMessage.into()),
+            };
+
+            HubMessagesSent::<T>::mutate(spoke_parachain_id, |sent| *sent = sent.saturating_add(1));
+            Self::send_unified_message(spoke_parachain_id, reply)
+        }
+
+        /// Hub: mint shares for `assets` deposited on a spoke
+        ///
+        /// Shares are minted in proportion to the smallest deposit relative to the global
+        /// reserves; the first deposit mints shares equal to the amount of the first asset.
+        fn credit_unified_deposit(
+            spoke_parachain_id: u32,
+            provider: &T::AccountId,
+            assets: &[(T::AssetId, BalanceOf<T>)],
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            ensure!(!UnifiedPaused::<T>::get(), Error::<T>::UnifiedPoolPaused);
+            let basket = UnifiedPoolAssets::<T>::get();
+            ensure!(!basket.is_empty() && assets.len() == basket.len(), Error::<T>::InvalidAssets);
+
+            let total_shares = TotalUnifiedShares::<T>::get();
+            let mut shares: Option<BalanceOf<T>> = None;
+            for asset_id in basket.iter() {
+                let amount = assets
+                    .iter()
+                    .find(|(id, _)| id == asset_id)
+                    .map(|(_, amount)| *amount)
+                    .ok_or(Error::<T>::InvalidAssets)?;
+                let reserve = GlobalReserves::<T>::get(asset_id);
+
+                let minted = if total_shares.is_zero() {
+                    shares.unwrap_or(amount)
+                } else {
+                    Self::mul_div(amount, total_shares, reserve, Rounding::Down)?
+                };
+                shares = Some(shares.map_or(minted, |shares| shares.min(minted)));
+                GlobalReserves::<T>::insert(asset_id, reserve.checked_add(&amount).ok_or(Error::<T>::Overflow)?);
+                SpokeReserves::<T>::mutate(spoke_parachain_id, asset_id, |held| *held = held.saturating_add(amount));
+            }
+
+            let shares = shares.filter(|shares| !shares.is_zero()).ok_or(Error::<T>::InsufficientLiquidity)?;
+            TotalUnifiedShares::<T>::put(total_shares.checked_add(&shares).ok_or(Error::<T>::Overflow)?);
+            UnifiedShares::<T>::mutate(provider, |owned| *owned = owned.saturating_add(shares));
+
+            Self::deposit_event(Event::UnifiedDepositCredited {
+                spoke_parachain_id,
+                provider: provider.clone(),
+                shares,
+            });
+            Ok(shares)
+        }
+
+        /// Hub: burn `shares` of `provider` for their part of the global reserves, paid out of
+        /// the reserves held by the spoke
+        fn approve_unified_withdrawal(
+            spoke_parachain_id: u32,
+            provider: &T::AccountId,
+            shares: BalanceOf<T>,
+        ) -> Result<AssetAmountsOf<T>, DispatchError> {
+            ensure!(!UnifiedPaused::<T>::get(), Error::<T>::UnifiedPoolPaused);
+            let owned = UnifiedShares::<T>::get(provider);
+            ensure!(!shares.is_zero() && owned >= shares, Error::<T>::InsufficientShares);
+
+            let total_shares = TotalUnifiedShares::<T>::get();
+            let mut assets = Vec::new();
+            for asset_id in UnifiedPoolAssets::<T>::get() {
+                let reserve = GlobalReserves::<T>::get(asset_id);
+                let amount = Self::mul_div(reserve, shares, total_shares, Rounding::Down)?;
+                let held = SpokeReserves::<T>::get(spoke_parachain_id, asset_id);
+                ensure!(held >= amount, Error::<T>::InsufficientLiquidity);
+
+                SpokeReserves::<T>::insert(spoke_parachain_id, asset_id, held - amount);
+                GlobalReserves::<T>::insert(asset_id, reserve - amount);
+                assets.push((asset_id, amount));
+            }
+
+            TotalUnifiedShares::<T>::put(total_shares - shares);
+            UnifiedShares::<T>::insert(provider, owned - shares);
+
+            Self::deposit_event(Event::UnifiedWithdrawalApproved {
+                spoke_parachain_id,
+                provider: provider.clone(),
+                shares,
+                assets: assets.clone(),
+            });
+            Ok(assets)
+        }
+
+        /// Hub: compare a spoke's reported reserves with the hub's accounting
+        ///
+        /// Reports sent while hub messages to the spoke are still in flight are ignored, so every
+        /// deposit the spoke holds has been settled. A spoke holding less than accounted for
+        /// pauses the unified pool until governance resumes it; assets sent to its account
+        /// outside the pool are not a mismatch.
+        fn verify_spoke_reserves(
+            spoke_parachain_id: u32,
+            reserves: Vec<(T::AssetId, BalanceOf<T>)>,
+            applied_hub_messages: u64,
+        ) {
+            if applied_hub_messages != HubMessagesSent::<T>::get(spoke_parachain_id) {
+                return;
+            }
+
+            for asset_id in UnifiedPoolAssets::<T>::get() {
+                let accounted = SpokeReserves::<T>::get(spoke_parachain_id, asset_id);
+                let reported = reserves
+                    .iter()
+                    .find(|(id, _)| *id == asset_id)
+                    .map_or_else(Zero::zero, |(_, amount)| *amount);
+                if reported < accounted {
+                    UnifiedPaused::<T>::put(true);
+                    Self::deposit_event(Event::UnifiedReserveMismatch {
+                        spoke_parachain_id,
+                        asset_id,
+                        accounted,
+                        reported,
+                    });
+                    return;
+                }
+            }
+
+            Self::deposit_event(Event::UnifiedReservesVerified { spoke_parachain_id });
+        }
+
+        /// Spoke: settle a request with the hub's answer
+        fn handle_hub_message(message: UnifiedPoolMessageOf<T>) -> DispatchResult {
+            let request_id = match &message {
+                UnifiedPoolMessage::DepositCredited { request_id, .. } |
+                UnifiedPoolMessage::WithdrawalApproved { request_id, .. } |
+                UnifiedPoolMessage::Rejected { request_id, .. } => *request_id,
+                _ => return Err(Error::<T>::UnexpectedUnifiedMessage.into()),
+            };
+            HubMessagesApplied::<T>::mutate(|applied| *applied = applied.saturating_add(1));
+            let Some(request) = PendingUnifiedRequests::<T>::take(request_id) else {
+                return Ok(());
+            };
+
+            match (message, request) {
+                (
+                    UnifiedPoolMessage::DepositCredited { shares, .. },
+                    PendingUnifiedRequest::Deposit { provider, assets },
+                ) => {
+                    for (asset_id, amount) in assets {
+                        UnifiedReserves::<T>::mutate(asset_id, |reserve| *reserve = reserve.saturating_add(amount));
+                    }
+                    Self::deposit_event(Event::UnifiedDepositCompleted { request_id, provider, shares });
+                },
+                (
+                    UnifiedPoolMessage::WithdrawalApproved { assets, .. },
+                    PendingUnifiedRequest::Withdraw { provider, shares },
+                ) => {
+                    let unified_account = Self::unified_account();
+                    for (asset_id, amount) in assets.iter() {
+                        T::Assets::transfer(*asset_id, &unified_account, &provider, *amount, Preservation::Expendable)?;
+                        UnifiedReserves::<T>::mutate(asset_id, |reserve| *reserve = reserve.saturating_sub(*amount));
+                    }
+                    Self::deposit_event(Event::UnifiedWithdrawalCompleted { request_id, provider, shares, assets });
+                },
+                (UnifiedPoolMessage::Rejected { error, .. }, PendingUnifiedRequest::Deposit { provider, assets }) => {
+                    let unified_account = Self::unified_account();
+                    for (asset_id, amount) in assets {
+                        T::Assets::transfer(asset_id, &unified_account, &provider, amount, Preservation::Expendable)?;
+                    }
+                    Self::deposit_event(Event::UnifiedRequestRejected { request_id, provider, error });
+                },
+                (UnifiedPoolMessage::Rejected { error, .. }, PendingUnifiedRequest::Withdraw { provider, .. }) => {
+                    Self::deposit_event(Event::UnifiedRequestRejected { request_id, provider, error });
+                },
+                _ => return Err(Error::<T>::UnexpectedUnifiedMessage.into()),
+            }
+            Ok(())
+        }
+
+        /// Check that unified shares add up, the global reserves equal the sum of the reserves
+        /// accounted to the spokes and this chain's unified pool account holds its reserves
+        pub fn check_unified_invariants() -> Result<(), &'static str> {
+            let shares = UnifiedShares::<T>::iter_values()
+                .fold(BalanceOf::<T>::zero(), |total, shares| total.saturating_add(shares));
+            ensure!(shares == TotalUnifiedShares::<T>::get(), "Unified shares do not add up to the total");
+
+            for asset_id in UnifiedPoolAssets::<T>::get() {
+                let held = SpokeReserves::<T>::iter()
+                    .filter(|(_, id, _)| *id == asset_id)
+                    .fold(BalanceOf::<T>::zero(), |total, (_, _, held)| total.saturating_add(held));
+                ensure!(
+                    held == GlobalReserves::<T>::get(asset_id),
+                    "Global reserves do not match the reserves of the spokes"
+                );
+                ensure!(
+                    T::Assets::balance(asset_id, &Self::unified_account()) >= UnifiedReserves::<T>::get(asset_id),
+                    "Unified pool account holds less than its reserves"
+                );
+            }
+            Ok(())
+        }
+
+        /// `a * b / c` without intermediate overflow
+        fn mul_div(
+            a: BalanceOf<T>,
+            b: BalanceOf<T>,
+            c: BalanceOf<T>,
+            rounding: Rounding,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            multiply_by_rational_with_rounding(a.saturated_into(), b.saturated_into(), c.saturated_into(), rounding)
+                .and_then(|result| result.try_into().ok())
+                .ok_or_else(|| Error::<T>::Overflow.into())
+        }
     }
 
-    // Implementation code will be added in separate files
+    impl<T: Config> LiquidityHandler<T::AccountId, T::AssetId, BalanceOf<T>, T::Hash> for Pallet<T> {
+        fn add_liquidity(
+            provider: &T::AccountId,
+            payer: &T::AccountId,
+            pool_id: T::Hash,
+            assets: Vec<(T::AssetId, BalanceOf<T>)>,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            Self::do_add_liquidity(provider, payer, pool_id, assets)
+        }
+
+        fn remove_liquidity(
+            provider: &T::AccountId,
+            pool_id: T::Hash,
+            shares: BalanceOf<T>,
+        ) -> Result<Vec<(T::AssetId, BalanceOf<T>)>, DispatchError> {
+            Self::do_remove_liquidity(provider, pool_id, shares)
+        }
+
+        fn execute_swap(
+            who: &T::AccountId,
+            payer: &T::AccountId,
+            path: Vec<T::AssetId>,
+            amount: BalanceOf<T>,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            Self::do_swap(who, payer, path, amount)
+        }
+
+        fn max_weight() -> Weight {
+            // Adding or removing liquidity updates and transfers every asset of the pool; a swap
+            // updates one pool and makes one transfer per hop
+            let assets = T::MaxAssetsPerPool::get() as u64;
+            let hops = T::MaxSwapPathLength::get() as u64;
+            T::DbWeight::get().reads_writes(3 + 4 * assets.max(hops), 3 + 4 * assets.max(hops))
+        }
+    }
+
+    impl<T: Config> OperationSettlementHandler<T::AccountId, T::AssetId, BalanceOf<T>, T::Hash> for Pallet<T> {
+        fn on_operation_completed(operation_key: T::Hash) {
+            PendingRebalances::<T>::remove(operation_key);
+        }
+
+        fn on_operation_refunded(
+            _owner: &T::AccountId,
+            operation_key: T::Hash,
+            escrow: &[(T::AssetId, BalanceOf<T>)],
+            _reason: RefundReason,
+        ) {
+            // Operation keys are scoped to their owner, so only rebalances refunded to the
+            // pallet account are found
+            let Some((pool_id, parachain_id)) = PendingRebalances::<T>::take(operation_key) else {
+                return;
+            };
+            let account = Self::account_id();
+            match with_storage_layer(|| Self::do_add_liquidity(&account, &account, pool_id, escrow.to_vec())) {
+                Ok(shares) => Self::deposit_event(Event::RebalanceReturned { pool_id, parachain_id, shares }),
+                Err(error) => Self::deposit_event(Event::RebalanceReturnFailed { pool_id, parachain_id, error }),
+            }
+        }
+
+        fn max_weight() -> Weight {
+            // Returning a rebalance adds liquidity, updating and transferring every pool asset
+            let assets = T::MaxAssetsPerPool::get() as u64;
+            T::DbWeight::get().reads_writes(4 + 2 * assets, 4 + 2 * assets)
+        }
+    }
+
+    impl<T: Config> CustomMessageHandler for Pallet<T> {
+        fn codes() -> Vec<u8> {
+            vec![custom_codes::IMRT_COORDINATION]
+        }
+
+        fn max_weight(code: u8) -> Option<Weight> {
+            (code == custom_codes::IMRT_COORDINATION).then(Self::unified_message_weight)
+        }
+
+        fn handle(_code: u8, source_parachain_id: u32, message_data: &[u8]) -> Result<Weight, DispatchError> {
+            let message = UnifiedPoolMessageOf::<T>::decode(&mut &message_data[..])
+                .map_err(|_| Error::<T>::MalformedUnifiedMessage)?;
+            Self::handle_unified_message(source_parachain_id, message)?;
+            Ok(Self::unified_message_weight())
+        }
+    }
+
+    impl<T: Config> PriceUpdateHandler for Pallet<T> {
+        fn handle_price_update(source_parachain_id: u32, message_data: &[u8]) -> DispatchResult {
+            let report = ReserveReport::<T>::decode(&mut &message_data[..]).map_err(|_| Error::<T>::MalformedReport)?;
+            let reserves = BoundedVec::try_from(report.reserves).map_err(|_| Error::<T>::TooManyAssets)?;
+
+            RemoteReserves::<T>::insert(
+                source_parachain_id,
+                report.pool_id,
+                RemoteReserve { reserves, reported_at: frame_system::Pallet::<T>::block_number() },
+            );
+            Self::deposit_event(Event::RemoteReservesUpdated { parachain_id: source_parachain_id, pool_id: report.pool_id });
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,284 @@
+//! Test environment for the liquidity pallet
+//!
+//! Each [`Chain`] is a separate externalities instance standing in for one parachain: NRSH and
+//! ELXR are spokes of the unified pool and IMRT is its hub. Messages sent by any chain are
+//! queued in an outbox and delivered with [`relay`]. Liquidity operations escrow their assets in
+//! [`ESCROW`] and stay pending until a test settles them with [`settle_operation`].
+
+use crate as pallet_liquidity;
+use crate::{unified::UnifiedPoolRole, *};
+
+use frame_support::{
+    construct_runtime, derive_impl, parameter_types,
+    traits::{
+        fungible,
+        fungibles::Mutate,
+        tokens::{Fortitude, Precision, Preservation},
+        AsEnsureOriginWithArg, ConstU32, ConstU64,
+    },
+    PalletId,
+};
+use pallet_cross_chain::{
+    CrossChainMessenger, CustomMessageHandler, LiquidityOperation, LiquidityOperationSender,
+    OperationSettlementHandler, PriceUpdateHandler, RefundReason, XcmMessageType,
+};
+use sp_core::H256;
+use sp_runtime::{
+    traits::{BlakeTwo256, BlockNumberProvider, Hash, IdentityLookup},
+    BuildStorage, DispatchError, DispatchResult,
+};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+construct_runtime!(
+    pub enum Test {
+        System: frame_system,
+        Balances: pallet_balances,
+        Assets: pallet_assets,
+        Liquidity: pallet_liquidity,
+    }
+);
+
+/// Wide enough for the pool accounts derived from the pallet ID to stay distinct
+pub type AccountId = u128;
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+/// Account holding the assets escrowed by pending liquidity operations
+pub const ESCROW: AccountId = 99;
+
+/// First asset of every pool
+pub const BASE: u32 = 1;
+/// Second asset of every pool
+pub const QUOTE: u32 = 2;
+
+pub const NRSH: u32 = 2000;
+pub const ELXR: u32 = 2001;
+pub const IMRT: u32 = 2002;
+
+pub const INITIAL_BALANCE: u64 = 1_000_000;
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<AccountId>;
+    type AccountData = pallet_balances::AccountData<u64>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig as pallet_balances::DefaultConfig)]
+impl pallet_balances::Config for Test {
+    type AccountStore = System;
+}
+
+impl pallet_assets::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Balance = u64;
+    type AssetId = u32;
+    type AssetIdParameter = u32;
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
+    type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+    type AssetDeposit = ConstU64<1>;
+    type AssetAccountDeposit = ConstU64<1>;
+    type MetadataDepositBase = ConstU64<1>;
+    type MetadataDepositPerByte = ConstU64<1>;
+    type ApprovalDeposit = ConstU64<1>;
+    type StringLimit = ConstU32<50>;
+    type Freezer = ();
+    type WeightInfo = ();
+    type CallbackHandle = ();
+    type Extra = ();
+    type RemoveItemsLimit = ConstU32<5>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = ();
+}
+
+/// Liquidity operation of the test runtime
+pub type Operation = LiquidityOperation<AccountId, u32, u64, u64, H256>;
+
+parameter_types! {
+    pub const LiquidityPalletId: PalletId = PalletId(*b"mm/liqui");
+    pub static SelfParaId: u32 = NRSH;
+    pub static Role: UnifiedPoolRole = UnifiedPoolRole::Spoke { hub_parachain_id: IMRT };
+    pub static RelayBlock: u64 = 1;
+    /// Messages sent by any chain: source, target, type and payload
+    static Outbox: Vec<(u32, u32, XcmMessageType, Vec<u8>)> = Vec::new();
+    /// Operations submitted with their assets and not settled yet: target, key and operation
+    pub static SubmittedOperations: Vec<(u32, H256, Operation)> = Vec::new();
+}
+
+/// Native currency, only used for its balance type
+pub struct MockCurrency;
+impl pallet_liquidity::Currency<AccountId> for MockCurrency {
+    type Balance = u64;
+
+    fn free_balance(who: &AccountId) -> u64 {
+        <Balances as fungible::Inspect<_>>::balance(who)
+    }
+
+    fn transfer(
+        source: &AccountId,
+        dest: &AccountId,
+        value: u64,
+        existence_requirement: ExistenceRequirement,
+    ) -> DispatchResult {
+        let preservation = match existence_requirement {
+            ExistenceRequirement::KeepAlive => Preservation::Preserve,
+            ExistenceRequirement::AllowDeath => Preservation::Expendable,
+        };
+        <Balances as fungible::Mutate<_>>::transfer(source, dest, value, preservation).map(|_| ())
+    }
+}
+
+/// Queues messages in the [`Outbox`] until [`relay`] delivers them
+pub struct MockMessenger;
+impl CrossChainMessenger for MockMessenger {
+    fn send_message(target_parachain_id: u32, message_type: XcmMessageType, message_data: Vec<u8>) -> DispatchResult {
+        Outbox::mutate(|outbox| outbox.push((SelfParaId::get(), target_parachain_id, message_type, message_data)));
+        Ok(())
+    }
+}
+
+/// Escrows the assets of submitted operations in [`ESCROW`] and records them in
+/// [`SubmittedOperations`]
+pub struct MockOperationSender;
+impl LiquidityOperationSender<AccountId, u32, u64, u64, H256> for MockOperationSender {
+    fn send_liquidity_operation(_target_parachain_id: u32, _operation: Operation) -> DispatchResult {
+        Err(DispatchError::Other("Operations must be submitted with their assets"))
+    }
+
+    fn submit_liquidity_operation(target_parachain_id: u32, operation: Operation) -> Result<H256, DispatchError> {
+        let operation_key = BlakeTwo256::hash_of(&(operation.owner(), operation.operation_id()));
+        for (asset_id, amount) in escrow(&operation) {
+            <Assets as Mutate<_>>::transfer(asset_id, operation.owner(), &ESCROW, amount, Preservation::Expendable)?;
+        }
+        SubmittedOperations::mutate(|submitted| submitted.push((target_parachain_id, operation_key, operation)));
+        Ok(operation_key)
+    }
+}
+
+fn escrow(operation: &Operation) -> Vec<(u32, u64)> {
+    match operation {
+        LiquidityOperation::AddLiquidity { assets, .. } => assets.clone(),
+        _ => Vec::new(),
+    }
+}
+
+/// Settle a submitted operation: a success burns its escrow as if delivered to the target chain,
+/// a failure refunds it to the owner
+pub fn settle_operation(operation_key: H256, refund: Option<RefundReason>) {
+    let operation = SubmittedOperations::mutate(|submitted| {
+        let index = submitted.iter().position(|(_, key, _)| *key == operation_key).expect("unknown operation");
+        submitted.remove(index).2
+    });
+    let escrow = escrow(&operation);
+    match refund {
+        None => {
+            for (asset_id, amount) in escrow {
+                <Assets as Mutate<_>>::burn_from(asset_id, &ESCROW, amount, Precision::Exact, Fortitude::Force).unwrap();
+            }
+            Liquidity::on_operation_completed(operation_key);
+        },
+        Some(reason) => {
+            for (asset_id, amount) in escrow.iter() {
+                <Assets as Mutate<_>>::transfer(*asset_id, &ESCROW, operation.owner(), *amount, Preservation::Expendable)
+                    .unwrap();
+            }
+            Liquidity::on_operation_refunded(operation.owner(), operation_key, &escrow, reason);
+        },
+    }
+}
+
+/// Relay chain clock shared by every chain
+pub struct MockRelayClock;
+impl BlockNumberProvider for MockRelayClock {
+    type BlockNumber = u64;
+
+    fn current_block_number() -> u64 {
+        RelayBlock::get()
+    }
+}
+
+impl Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Currency = MockCurrency;
+    type AssetId = u32;
+    type Assets = Assets;
+    type MaxAssetsPerPool = ConstU32<4>;
+    type MaxSwapPathLength = ConstU32<3>;
+    type Messenger = MockMessenger;
+    type OperationSender = MockOperationSender;
+    type ExpirationProvider = MockRelayClock;
+    type RebalanceLifetime = ConstU64<20>;
+    type RebalanceOrigin = frame_system::EnsureRoot<AccountId>;
+    type PalletId = LiquidityPalletId;
+    type UnifiedRole = Role;
+    type UnifiedPoolOrigin = frame_system::EnsureRoot<AccountId>;
+}
+
+/// One parachain of the network
+pub struct Chain {
+    pub para_id: u32,
+    role: UnifiedPoolRole,
+    ext: sp_io::TestExternalities,
+}
+
+impl Chain {
+    /// Chain where ALICE and BOB each hold `INITIAL_BALANCE` of both assets
+    pub fn new(para_id: u32) -> Self {
+        let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        pallet_assets::GenesisConfig::<Test> {
+            assets: vec![(BASE, ALICE, true, 1), (QUOTE, ALICE, true, 1)],
+            metadata: vec![],
+            accounts: [BASE, QUOTE]
+                .into_iter()
+                .flat_map(|asset| [ALICE, BOB].map(|who| (asset, who, INITIAL_BALANCE)))
+                .collect(),
+        }
+        .assimilate_storage(&mut storage)
+        .unwrap();
+
+        let role = match para_id {
+            IMRT => UnifiedPoolRole::Hub,
+            _ => UnifiedPoolRole::Spoke { hub_parachain_id: IMRT },
+        };
+        let mut ext = sp_io::TestExternalities::new(storage);
+        ext.execute_with(|| System::set_block_number(1));
+        Self { para_id, role, ext }
+    }
+
+    /// Run `f` as this chain
+    pub fn execute_with<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        SelfParaId::set(self.para_id);
+        Role::set(self.role);
+        self.ext.execute_with(f)
+    }
+}
+
+/// NRSH, ELXR and IMRT, starting at relay block 1 with an empty outbox
+pub fn new_test_chains() -> (Chain, Chain, Chain) {
+    RelayBlock::set(1);
+    Outbox::take();
+    SubmittedOperations::take();
+    (Chain::new(NRSH), Chain::new(ELXR), Chain::new(IMRT))
+}
+
+/// Deliver every queued message to its target chain
+pub fn relay(chains: &mut [&mut Chain]) {
+    while !Outbox::get().is_empty() {
+        for (source, target, message_type, data) in Outbox::take() {
+            let chain = chains
+                .iter_mut()
+                .find(|chain| chain.para_id == target)
+                .expect("message sent to an unknown chain");
+            chain
+                .execute_with(|| match message_type {
+                    XcmMessageType::PriceUpdate => Liquidity::handle_price_update(source, &data),
+                    XcmMessageType::Custom(code) => Liquidity::handle(code, source, &data).map(|_| ()),
+                    _ => panic!("unexpected message type"),
+                })
+                .expect("message rejected");
+        }
+    }
+}
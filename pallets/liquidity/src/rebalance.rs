@@ -0,0 +1,125 @@
+//! Price equalization between two-asset constant product pools on different chains
+//!
+//! Liquidity is moved from one chain to another at the source pool's price: removing it leaves
+//! the source price unchanged, adding it pulls the destination price towards the source price.
+//! The planner moves just enough to bring the destination pool to the combined price of both
+//! pools, `(quote_a + quote_b) / (base_a + base_b)`.
+//!
+//! The functions here are pure so rebalancing can be dry-run off-chain with [`simulate`].
+
+use sp_runtime::{
+    helpers_128bit::multiply_by_rational_with_rounding, traits::Zero, FixedPointNumber, FixedU128, Rounding,
+    RuntimeDebug,
+};
+use sp_std::prelude::*;
+
+/// Reserves of a two-asset pool
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct PoolReserves {
+    /// Reserve of the first pool asset
+    pub base: u128,
+    /// Reserve of the second pool asset
+    pub quote: u128,
+}
+
+impl PoolReserves {
+    /// Price of the base asset in the quote asset
+    pub fn price(&self) -> Option<FixedU128> {
+        FixedU128::checked_from_rational(self.quote, self.base)
+    }
+}
+
+/// Relative difference between two prices, in basis points of the lower one
+pub fn deviation_basis_points(a: FixedU128, b: FixedU128) -> u32 {
+    let (low, high) = if a < b { (a, b) } else { (b, a) };
+    if low.is_zero() {
+        return u32::MAX;
+    }
+    let deviation = (high - low) / low;
+    deviation.saturating_mul_int(10_000u128).try_into().unwrap_or(u32::MAX)
+}
+
+/// Base amount to move from `source` to `destination` at the source price so the destination
+/// price reaches the combined price of both pools
+pub fn equalizing_base_amount(source: PoolReserves, destination: PoolReserves) -> Option<u128> {
+    let source_price = source.price()?;
+    let target = FixedU128::checked_from_rational(
+        source.quote.checked_add(destination.quote)?,
+        source.base.checked_add(destination.base)?,
+    )?;
+
+    // Solve (quote_d + a * p_s) / (base_d + a) = target for a
+    let target_quote = target.saturating_mul_int(destination.base);
+    let (quote_gap, price_gap) = if source_price > target {
+        (target_quote.checked_sub(destination.quote)?, source_price - target)
+    } else {
+        (destination.quote.checked_sub(target_quote)?, target - source_price)
+    };
+    if price_gap.is_zero() {
+        return None;
+    }
+
+    multiply_by_rational_with_rounding(quote_gap, FixedU128::DIV, price_gap.into_inner(), Rounding::Down)
+        .filter(|amount| *amount > 0)
+}
+
+/// Move `base` from `source` to `destination` at the source price, returning the quote moved
+pub fn transfer(source: &mut PoolReserves, destination: &mut PoolReserves, base: u128) -> Option<u128> {
+    let base = base.min(source.base);
+    let quote = multiply_by_rational_with_rounding(base, source.quote, source.base, Rounding::Down)?;
+    source.base -= base;
+    source.quote -= quote;
+    destination.base = destination.base.checked_add(base)?;
+    destination.quote = destination.quote.checked_add(quote)?;
+    Some(quote)
+}
+
+/// Dry-run `rounds` of rebalancing across pools on several chains
+///
+/// In each round every chain rebalances towards every other chain whose price deviates by at
+/// least `min_deviation_basis_points`, moving at most `max_move_basis_points` of its base
+/// reserve per step. Returns the reserves after each round.
+pub fn simulate(
+    mut pools: Vec<PoolReserves>,
+    min_deviation_basis_points: u32,
+    max_move_basis_points: u32,
+    rounds: u32,
+) -> Vec<Vec<PoolReserves>> {
+    let mut history = Vec::with_capacity(rounds as usize);
+    for _ in 0..rounds {
+        for source in 0..pools.len() {
+            for destination in 0..pools.len() {
+                if source == destination {
+                    continue;
+                }
+                let (Some(source_price), Some(destination_price)) =
+                    (pools[source].price(), pools[destination].price())
+                else {
+                    continue;
+                };
+                if deviation_basis_points(source_price, destination_price) < min_deviation_basis_points {
+                    continue;
+                }
+
+                let Some(wanted) = equalizing_base_amount(pools[source], pools[destination]) else {
+                    continue;
+                };
+                let cap = multiply_by_rational_with_rounding(
+                    pools[source].base,
+                    max_move_basis_points as u128,
+                    10_000,
+                    Rounding::Down,
+                )
+                .unwrap_or_default();
+
+                let (mut from, mut to) = (pools[source], pools[destination]);
+                if transfer(&mut from, &mut to, wanted.min(cap)).is_some() {
+                    pools[source] = from;
+                    pools[destination] = to;
+                }
+            }
+        }
+        history.push(pools.clone());
+    }
+    history
+}
@@ -0,0 +1,515 @@
+use crate::{mock::*, *};
+use frame_support::{
+    assert_noop, assert_ok,
+    traits::{
+        fungibles::{Inspect, Mutate},
+        tokens::Preservation,
+    },
+};
+use pallet_cross_chain::{LiquidityOperation, RefundReason};
+use sp_core::H256;
+use sp_runtime::DispatchError;
+
+/// Pool on NRSH
+const POOL: H256 = H256::repeat_byte(1);
+/// Counterpart of `POOL` on ELXR
+const REMOTE_POOL: H256 = H256::repeat_byte(2);
+
+fn balance(asset: u32, who: AccountId) -> u64 {
+    <Assets as Inspect<AccountId>>::balance(asset, &who)
+}
+
+/// Create a constant product pool of BASE and QUOTE holding `base` and `quote` of
+/// protocol-owned liquidity paid by ALICE
+fn create_pool(pool_id: H256, base: u64, quote: u64) {
+    let asset = |asset_id| PoolAsset::<Test> {
+        asset_id,
+        balance: 0,
+        weight: None,
+        origin_parachain_id: SelfParaId::get(),
+    };
+    LiquidityPools::<Test>::insert(
+        pool_id,
+        LiquidityPool {
+            id: pool_id,
+            creator: ALICE,
+            assets: vec![asset(BASE), asset(QUOTE)].try_into().unwrap(),
+            pool_type: PoolType::ConstantProduct,
+            fee_basis_points: 30,
+            total_shares: 0,
+            state: PoolState::Active,
+            created_at: 1,
+        },
+    );
+    for asset_id in [BASE, QUOTE] {
+        AssetPools::<Test>::mutate(asset_id, |pools| pools.try_push(pool_id).unwrap());
+    }
+    assert_ok!(Liquidity::do_add_liquidity(
+        &Liquidity::account_id(),
+        &ALICE,
+        pool_id,
+        vec![(BASE, base), (QUOTE, quote)],
+    ));
+}
+
+fn reserves(pool_id: H256) -> (u64, u64) {
+    let pool = LiquidityPools::<Test>::get(pool_id).unwrap();
+    (pool.assets[0].balance, pool.assets[1].balance)
+}
+
+fn set_limits(min_deviation_basis_points: u32, max_move_basis_points: u32) {
+    assert_ok!(Liquidity::set_rebalance_limits(
+        RuntimeOrigin::root(),
+        Some(RebalanceLimits { min_deviation_basis_points, max_move_basis_points, cooldown: 10, max_report_age: 20 }),
+    ));
+}
+
+/// `POOL` at price 1 linked to `REMOTE_POOL` at price 2, whose reserves NRSH received at block 1
+fn linked_pools() -> (Chain, Chain) {
+    let (mut nrsh, mut elxr, _) = new_test_chains();
+    elxr.execute_with(|| {
+        create_pool(REMOTE_POOL, 10_000, 20_000);
+        assert_ok!(Liquidity::link_pool(RuntimeOrigin::root(), REMOTE_POOL, NRSH, POOL));
+        assert_ok!(Liquidity::report_reserves(RuntimeOrigin::signed(BOB), REMOTE_POOL));
+    });
+    nrsh.execute_with(|| {
+        create_pool(POOL, 10_000, 10_000);
+        assert_ok!(Liquidity::link_pool(RuntimeOrigin::root(), POOL, ELXR, REMOTE_POOL));
+    });
+    relay(&mut [&mut nrsh, &mut elxr]);
+    (nrsh, elxr)
+}
+
+#[test]
+fn plan_rebalance_moves_the_equalizing_amount_up_to_the_cap() {
+    let (mut nrsh, _elxr) = linked_pools();
+
+    nrsh.execute_with(|| {
+        // Bringing the remote pool to the combined price of 1.5 takes all 10_000 base
+        set_limits(100, 10_000);
+        assert_eq!(Liquidity::plan_rebalance(POOL, ELXR).unwrap().shares, 10_000);
+
+        set_limits(100, 2_500);
+        let plan = Liquidity::plan_rebalance(POOL, ELXR).unwrap();
+        assert_eq!(plan.remote_pool_id, REMOTE_POOL);
+        assert_eq!(plan.shares, 2_500);
+        assert_eq!(plan.assets, vec![(BASE, 2_500), (QUOTE, 2_500)]);
+    });
+}
+
+#[test]
+fn plan_rebalance_respects_the_limits() {
+    let (mut nrsh, _elxr) = linked_pools();
+
+    nrsh.execute_with(|| {
+        assert_noop!(Liquidity::plan_rebalance(POOL, ELXR), Error::<Test>::RebalancingDisabled);
+        assert_noop!(
+            Liquidity::set_rebalance_limits(RuntimeOrigin::signed(ALICE), None),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Liquidity::set_rebalance_limits(
+                RuntimeOrigin::root(),
+                Some(RebalanceLimits { min_deviation_basis_points: 0, max_move_basis_points: 10_001, cooldown: 0, max_report_age: 0 }),
+            ),
+            Error::<Test>::InvalidRebalanceLimits
+        );
+
+        // The prices of the pools deviate by 10_000 basis points
+        set_limits(10_001, 2_500);
+        assert_noop!(Liquidity::plan_rebalance(POOL, ELXR), Error::<Test>::DeviationBelowThreshold);
+        set_limits(10_000, 2_500);
+        assert_ok!(Liquidity::plan_rebalance(POOL, ELXR));
+        assert_noop!(Liquidity::plan_rebalance(POOL, IMRT), Error::<Test>::PoolNotLinked);
+
+        // The remote reserves go stale `max_report_age` blocks after they were received
+        System::set_block_number(21);
+        assert_ok!(Liquidity::plan_rebalance(POOL, ELXR));
+        System::set_block_number(22);
+        assert_noop!(Liquidity::plan_rebalance(POOL, ELXR), Error::<Test>::StaleReserves);
+    });
+}
+
+#[test]
+fn rebalance_escrows_the_withdrawn_liquidity_until_the_cooldown() {
+    let (mut nrsh, _elxr) = linked_pools();
+
+    nrsh.execute_with(|| {
+        set_limits(100, 2_500);
+        assert_ok!(Liquidity::rebalance(RuntimeOrigin::signed(BOB), POOL, ELXR));
+        assert_eq!(reserves(POOL), (7_500, 7_500));
+        assert_eq!(LiquidityShares::<Test>::get(POOL, Liquidity::account_id()), 7_500);
+
+        let submitted = SubmittedOperations::get();
+        let [(target, operation_key, LiquidityOperation::AddLiquidity { provider, pool_id, assets, .. })] =
+            &submitted[..]
+        else {
+            panic!("expected one AddLiquidity operation");
+        };
+        assert_eq!((*target, *provider, *pool_id), (ELXR, Liquidity::account_id(), REMOTE_POOL));
+        assert_eq!(assets, &vec![(BASE, 2_500), (QUOTE, 2_500)]);
+        assert_eq!((balance(BASE, ESCROW), balance(QUOTE, ESCROW)), (2_500, 2_500));
+        assert_eq!(PendingRebalances::<Test>::get(operation_key), Some((POOL, ELXR)));
+
+        assert_noop!(
+            Liquidity::rebalance(RuntimeOrigin::signed(BOB), POOL, ELXR),
+            Error::<Test>::RebalanceCooldown
+        );
+        System::set_block_number(11);
+        assert_ok!(Liquidity::plan_rebalance(POOL, ELXR));
+    });
+}
+
+#[test]
+fn failed_and_timed_out_rebalances_return_to_the_pool() {
+    for reason in [RefundReason::Failed, RefundReason::TimedOut] {
+        let (mut nrsh, _elxr) = linked_pools();
+
+        nrsh.execute_with(|| {
+            set_limits(100, 2_500);
+            assert_ok!(Liquidity::rebalance(RuntimeOrigin::signed(BOB), POOL, ELXR));
+            let operation_key = SubmittedOperations::get()[0].1;
+
+            settle_operation(operation_key, Some(reason));
+            assert_eq!(reserves(POOL), (10_000, 10_000));
+            assert_eq!(LiquidityShares::<Test>::get(POOL, Liquidity::account_id()), 10_000);
+            assert_eq!((balance(BASE, ESCROW), balance(QUOTE, ESCROW)), (0, 0));
+            assert_eq!(PendingRebalances::<Test>::get(operation_key), None);
+            System::assert_last_event(
+                Event::RebalanceReturned { pool_id: POOL, parachain_id: ELXR, shares: 2_500 }.into(),
+            );
+        });
+    }
+}
+
+#[test]
+fn completed_rebalance_stays_on_the_remote_chain() {
+    let (mut nrsh, _elxr) = linked_pools();
+
+    nrsh.execute_with(|| {
+        set_limits(100, 2_500);
+        assert_ok!(Liquidity::rebalance(RuntimeOrigin::signed(BOB), POOL, ELXR));
+        let operation_key = SubmittedOperations::get()[0].1;
+
+        settle_operation(operation_key, None);
+        assert_eq!(reserves(POOL), (7_500, 7_500));
+        assert_eq!(PendingRebalances::<Test>::get(operation_key), None);
+    });
+}
+
+#[test]
+fn refund_that_cannot_be_returned_stays_with_the_pallet_account() {
+    let (mut nrsh, _elxr) = linked_pools();
+
+    nrsh.execute_with(|| {
+        set_limits(100, 2_500);
+        assert_ok!(Liquidity::rebalance(RuntimeOrigin::signed(BOB), POOL, ELXR));
+        let operation_key = SubmittedOperations::get()[0].1;
+        LiquidityPools::<Test>::mutate(POOL, |pool| pool.as_mut().unwrap().state = PoolState::Paused);
+
+        settle_operation(operation_key, Some(RefundReason::Failed));
+        assert_eq!(reserves(POOL), (7_500, 7_500));
+        let account = Liquidity::account_id();
+        assert_eq!((balance(BASE, account), balance(QUOTE, account)), (2_500, 2_500));
+        System::assert_last_event(
+            Event::RebalanceReturnFailed {
+                pool_id: POOL,
+                parachain_id: ELXR,
+                error: Error::<Test>::PoolNotActive.into(),
+            }
+            .into(),
+        );
+    });
+}
+
+/// NRSH, ELXR and IMRT with BASE and QUOTE making up the unified pool
+fn unified_chains() -> (Chain, Chain, Chain) {
+    let (mut nrsh, mut elxr, mut imrt) = new_test_chains();
+    for chain in [&mut nrsh, &mut elxr, &mut imrt] {
+        chain.execute_with(|| {
+            assert_ok!(Liquidity::set_unified_pool_assets(
+                RuntimeOrigin::root(),
+                vec![BASE, QUOTE].try_into().unwrap()
+            ));
+        });
+    }
+    (nrsh, elxr, imrt)
+}
+
+fn unified_balances(who: AccountId) -> (u64, u64) {
+    (balance(BASE, who), balance(QUOTE, who))
+}
+
+/// Error of the last unified pool request the hub rejected
+fn last_rejection() -> Option<DispatchError> {
+    System::events().into_iter().rev().find_map(|record| match record.event {
+        RuntimeEvent::Liquidity(Event::UnifiedRequestRejected { error, .. }) => Some(error),
+        _ => None,
+    })
+}
+
+#[test]
+fn unified_deposits_and_withdrawals_move_the_assets() {
+    let (mut nrsh, mut elxr, mut imrt) = unified_chains();
+
+    nrsh.execute_with(|| {
+        assert_ok!(Liquidity::deposit_unified(RuntimeOrigin::signed(ALICE), vec![(BASE, 1_000), (QUOTE, 2_000)]));
+        assert_eq!(unified_balances(ALICE), (INITIAL_BALANCE - 1_000, INITIAL_BALANCE - 2_000));
+        assert_eq!(unified_balances(Liquidity::unified_account()), (1_000, 2_000));
+        // Deposits awaiting the hub are not reserves yet
+        assert_eq!(UnifiedReserves::<Test>::get(BASE), 0);
+    });
+    elxr.execute_with(|| {
+        assert_ok!(Liquidity::deposit_unified(RuntimeOrigin::signed(BOB), vec![(BASE, 500), (QUOTE, 1_000)]));
+    });
+    relay(&mut [&mut nrsh, &mut elxr, &mut imrt]);
+
+    imrt.execute_with(|| {
+        assert_eq!((UnifiedShares::<Test>::get(ALICE), UnifiedShares::<Test>::get(BOB)), (1_000, 500));
+        assert_eq!(SpokeReserves::<Test>::get(NRSH, QUOTE), 2_000);
+        assert_eq!(GlobalReserves::<Test>::get(QUOTE), 3_000);
+        assert_ok!(Liquidity::check_unified_invariants());
+    });
+    nrsh.execute_with(|| {
+        assert_eq!((UnifiedReserves::<Test>::get(BASE), UnifiedReserves::<Test>::get(QUOTE)), (1_000, 2_000));
+        assert_eq!(PendingUnifiedRequests::<Test>::iter().count(), 0);
+
+        assert_ok!(Liquidity::withdraw_unified(RuntimeOrigin::signed(ALICE), 400));
+    });
+    relay(&mut [&mut nrsh, &mut elxr, &mut imrt]);
+
+    nrsh.execute_with(|| {
+        assert_eq!(unified_balances(ALICE), (INITIAL_BALANCE - 600, INITIAL_BALANCE - 1_200));
+        assert_eq!(unified_balances(Liquidity::unified_account()), (600, 1_200));
+        assert_eq!((UnifiedReserves::<Test>::get(BASE), UnifiedReserves::<Test>::get(QUOTE)), (600, 1_200));
+        assert_ok!(Liquidity::check_unified_invariants());
+
+        assert_ok!(Liquidity::report_unified_reserves(RuntimeOrigin::signed(BOB)));
+    });
+    relay(&mut [&mut nrsh, &mut elxr, &mut imrt]);
+
+    imrt.execute_with(|| {
+        assert_eq!((UnifiedShares::<Test>::get(ALICE), TotalUnifiedShares::<Test>::get()), (600, 1_100));
+        assert_eq!(SpokeReserves::<Test>::get(NRSH, BASE), 600);
+        assert_ok!(Liquidity::check_unified_invariants());
+        System::assert_last_event(Event::UnifiedReservesVerified { spoke_parachain_id: NRSH }.into());
+    });
+}
+
+#[test]
+fn rejected_unified_requests_are_refunded() {
+    let (mut nrsh, mut elxr, mut imrt) = unified_chains();
+
+    imrt.execute_with(|| {
+        assert_ok!(Liquidity::set_unified_pool_paused(RuntimeOrigin::root(), true));
+    });
+    nrsh.execute_with(|| {
+        assert_ok!(Liquidity::deposit_unified(RuntimeOrigin::signed(ALICE), vec![(BASE, 1_000), (QUOTE, 2_000)]));
+    });
+    relay(&mut [&mut nrsh, &mut elxr, &mut imrt]);
+
+    nrsh.execute_with(|| {
+        assert_eq!(last_rejection(), Some(Error::<Test>::UnifiedPoolPaused.into()));
+        assert_eq!(unified_balances(ALICE), (INITIAL_BALANCE, INITIAL_BALANCE));
+        assert_eq!(unified_balances(Liquidity::unified_account()), (0, 0));
+        assert_eq!(PendingUnifiedRequests::<Test>::iter().count(), 0);
+    });
+
+    imrt.execute_with(|| {
+        assert_ok!(Liquidity::set_unified_pool_paused(RuntimeOrigin::root(), false));
+        assert_eq!(TotalUnifiedShares::<Test>::get(), 0);
+    });
+    nrsh.execute_with(|| {
+        assert_ok!(Liquidity::deposit_unified(RuntimeOrigin::signed(ALICE), vec![(BASE, 1_000), (QUOTE, 2_000)]));
+    });
+    relay(&mut [&mut nrsh, &mut elxr, &mut imrt]);
+
+    // ELXR holds none of the reserves backing ALICE's shares, and BOB holds no shares
+    elxr.execute_with(|| {
+        assert_ok!(Liquidity::withdraw_unified(RuntimeOrigin::signed(ALICE), 100));
+        assert_ok!(Liquidity::withdraw_unified(RuntimeOrigin::signed(BOB), 100));
+    });
+    relay(&mut [&mut nrsh, &mut elxr, &mut imrt]);
+
+    elxr.execute_with(|| {
+        let rejections: Vec<_> = System::events()
+            .into_iter()
+            .filter_map(|record| match record.event {
+                RuntimeEvent::Liquidity(Event::UnifiedRequestRejected { provider, error, .. }) => Some((provider, error)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            rejections,
+            vec![
+                (ALICE, Error::<Test>::InsufficientLiquidity.into()),
+                (BOB, Error::<Test>::InsufficientShares.into())
+            ]
+        );
+        assert_eq!(unified_balances(ALICE), (INITIAL_BALANCE, INITIAL_BALANCE));
+    });
+    imrt.execute_with(|| {
+        assert_eq!(UnifiedShares::<Test>::get(ALICE), 1_000);
+        assert_ok!(Liquidity::check_unified_invariants());
+    });
+}
+
+#[test]
+fn unified_pool_is_paused_when_a_spoke_holds_less_than_accounted() {
+    let (mut nrsh, mut elxr, mut imrt) = unified_chains();
+
+    nrsh.execute_with(|| {
+        assert_ok!(Liquidity::deposit_unified(RuntimeOrigin::signed(ALICE), vec![(BASE, 1_000), (QUOTE, 2_000)]));
+        // Sent before the hub's answer was applied, so the hub ignores it
+        assert_ok!(Liquidity::report_unified_reserves(RuntimeOrigin::signed(BOB)));
+    });
+    relay(&mut [&mut nrsh, &mut elxr, &mut imrt]);
+    imrt.execute_with(|| {
+        assert!(!System::events().iter().any(|record| matches!(
+            record.event,
+            RuntimeEvent::Liquidity(Event::UnifiedReservesVerified { .. } | Event::UnifiedReserveMismatch { .. })
+        )));
+    });
+
+    // Assets sent to the unified pool account outside the pool are not a mismatch
+    nrsh.execute_with(|| {
+        assert_ok!(<Assets as Mutate<_>>::transfer(
+            BASE,
+            &BOB,
+            &Liquidity::unified_account(),
+            5,
+            Preservation::Expendable
+        ));
+        assert_ok!(Liquidity::report_unified_reserves(RuntimeOrigin::signed(BOB)));
+    });
+    relay(&mut [&mut nrsh, &mut elxr, &mut imrt]);
+    imrt.execute_with(|| {
+        System::assert_last_event(Event::UnifiedReservesVerified { spoke_parachain_id: NRSH }.into());
+        assert!(!UnifiedPaused::<Test>::get());
+    });
+
+    nrsh.execute_with(|| {
+        assert_ok!(<Assets as Mutate<_>>::transfer(
+            BASE,
+            &Liquidity::unified_account(),
+            &BOB,
+            10,
+            Preservation::Expendable
+        ));
+        assert_eq!(
+            Liquidity::check_unified_invariants(),
+            Err("Unified pool account holds less than its reserves")
+        );
+        assert_ok!(Liquidity::report_unified_reserves(RuntimeOrigin::signed(BOB)));
+    });
+    relay(&mut [&mut nrsh, &mut elxr, &mut imrt]);
+    imrt.execute_with(|| {
+        System::assert_last_event(
+            Event::UnifiedReserveMismatch { spoke_parachain_id: NRSH, asset_id: BASE, accounted: 1_000, reported: 995 }
+                .into(),
+        );
+        assert!(UnifiedPaused::<Test>::get());
+    });
+
+    nrsh.execute_with(|| {
+        assert_ok!(Liquidity::withdraw_unified(RuntimeOrigin::signed(ALICE), 100));
+    });
+    relay(&mut [&mut nrsh, &mut elxr, &mut imrt]);
+    nrsh.execute_with(|| {
+        assert_eq!(last_rejection(), Some(Error::<Test>::UnifiedPoolPaused.into()));
+    });
+}
+
+#[test]
+fn unified_invariants_catch_inconsistent_hub_accounting() {
+    let (mut nrsh, mut elxr, mut imrt) = unified_chains();
+
+    nrsh.execute_with(|| {
+        assert_ok!(Liquidity::deposit_unified(RuntimeOrigin::signed(ALICE), vec![(BASE, 1_000), (QUOTE, 2_000)]));
+    });
+    relay(&mut [&mut nrsh, &mut elxr, &mut imrt]);
+
+    imrt.execute_with(|| {
+        assert_ok!(Liquidity::check_unified_invariants());
+
+        TotalUnifiedShares::<Test>::put(1_001);
+        assert_eq!(Liquidity::check_unified_invariants(), Err("Unified shares do not add up to the total"));
+        TotalUnifiedShares::<Test>::put(1_000);
+
+        GlobalReserves::<Test>::insert(QUOTE, 2_001);
+        assert_eq!(
+            Liquidity::check_unified_invariants(),
+            Err("Global reserves do not match the reserves of the spokes")
+        );
+    });
+}
+
+mod rebalance {
+    use crate::rebalance::{deviation_basis_points, equalizing_base_amount, simulate, transfer, PoolReserves};
+    use sp_runtime::FixedU128;
+
+    fn pool(base: u128, quote: u128) -> PoolReserves {
+        PoolReserves { base, quote }
+    }
+
+    /// Largest price deviation between any two pools
+    fn max_deviation(pools: &[PoolReserves]) -> u32 {
+        let prices: Vec<_> = pools.iter().map(|pool| pool.price().unwrap()).collect();
+        prices
+            .iter()
+            .flat_map(|a| prices.iter().map(|b| deviation_basis_points(*a, *b)))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn equalizing_amount_brings_the_destination_to_the_combined_price() {
+        for (source, destination) in [(pool(10_000, 10_000), pool(10_000, 20_000)), (pool(10_000, 20_000), pool(10_000, 10_000))] {
+            let (mut source, mut destination) = (source, destination);
+            let amount = equalizing_base_amount(source, destination).unwrap();
+            assert_eq!(amount, 10_000);
+            transfer(&mut source, &mut destination, amount).unwrap();
+            assert_eq!(destination.price(), Some(FixedU128::from_rational(3, 2)));
+        }
+    }
+
+    #[test]
+    fn equal_prices_and_empty_pools_need_no_transfer() {
+        assert_eq!(equalizing_base_amount(pool(10_000, 20_000), pool(500, 1_000)), None);
+        assert_eq!(equalizing_base_amount(pool(0, 0), pool(10_000, 20_000)), None);
+        assert_eq!(equalizing_base_amount(pool(10_000, 20_000), pool(0, 0)), None);
+    }
+
+    #[test]
+    fn simulation_converges_to_a_common_price() {
+        let pools = vec![pool(10_000, 10_000), pool(10_000, 20_000), pool(40_000, 60_000)];
+        let history = simulate(pools, 10, 5_000, 20);
+        assert_eq!(history.len(), 20);
+        assert!(max_deviation(history.last().unwrap()) < 10);
+
+        // Liquidity is moved between pools, never created
+        for round in history {
+            assert_eq!(round.iter().map(|pool| pool.base).sum::<u128>(), 60_000);
+            assert_eq!(round.iter().map(|pool| pool.quote).sum::<u128>(), 90_000);
+        }
+    }
+
+    #[test]
+    fn simulation_moves_at_most_max_move_per_step() {
+        // Each step moves 1% of the source's base: 100 base from the first pool, then 101 base
+        // at a price of 2 back from the second
+        let history = simulate(vec![pool(10_000, 10_000), pool(10_000, 20_000)], 10, 100, 1);
+        assert_eq!(history, vec![vec![pool(10_001, 10_101), pool(9_999, 19_899)]]);
+
+        let history = simulate(vec![pool(10_000, 10_000), pool(10_000, 20_000), pool(40_000, 60_000)], 10, 100, 20);
+        assert!(max_deviation(history.last().unwrap()) > 1_000);
+    }
+
+    #[test]
+    fn simulation_ignores_deviations_below_the_threshold() {
+        // The prices deviate by 50 basis points
+        let pools = vec![pool(10_000, 10_000), pool(10_000, 10_050)];
+        assert_eq!(simulate(pools.clone(), 100, 5_000, 3), vec![pools.clone(); 3]);
+        assert_ne!(simulate(pools.clone(), 50, 5_000, 1), vec![pools]);
+    }
+}
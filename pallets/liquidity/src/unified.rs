@@ -0,0 +1,83 @@
+//! Virtual unified pool shared by all chains of the ecosystem
+//!
+//! The unified pool follows a hub-and-spoke model. The hub (IMRT) keeps the canonical share
+//! ledger and accounts for the reserves each spoke holds; it never holds reserves itself. Spokes
+//! (NRSH, ELXR) hold the reserves deposited on them in the unified pool account
+//! ([`crate::Pallet::unified_account`]) and forward every deposit and withdrawal to the hub,
+//! which mints or burns shares against the global reserves and answers with the result. The
+//! spoke refunds rejected deposits and pays out approved withdrawals from that account.
+//!
+//! Spokes periodically report the balances of the unified pool account. When the spoke has
+//! applied every message the hub sent it, the hub checks the report against its accounting and
+//! pauses the unified pool if the spoke holds less than accounted for.
+//!
+//! Messages travel as `Custom(custom_codes::IMRT_COORDINATION)` messages of
+//! `pallet-cross-chain`.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime::{DispatchError, RuntimeDebug};
+use sp_std::prelude::*;
+
+/// Role of this chain in the unified pool
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum UnifiedPoolRole {
+    /// Keeps the share ledger
+    Hub,
+    /// Holds reserves, accounted for by the hub on `hub_parachain_id`
+    Spoke {
+        /// Parachain ID of the hub
+        hub_parachain_id: u32,
+    },
+}
+
+/// Message exchanged between the hub and spokes
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum UnifiedPoolMessage<AccountId, AssetId, Balance, Hash> {
+    /// Spoke to hub: `provider` deposited `assets` on the spoke
+    Deposit {
+        /// Spoke request ID
+        request_id: Hash,
+        /// Depositing account
+        provider: AccountId,
+        /// Assets deposited, one entry per unified pool asset
+        assets: Vec<(AssetId, Balance)>,
+    },
+    /// Spoke to hub: `provider` wants to redeem `shares` for reserves held by the spoke
+    Withdraw {
+        /// Spoke request ID
+        request_id: Hash,
+        /// Withdrawing account
+        provider: AccountId,
+        /// Shares to burn
+        shares: Balance,
+    },
+    /// Spoke to hub: reserves held by the spoke
+    ReserveReport {
+        /// Reserve of each unified pool asset
+        reserves: Vec<(AssetId, Balance)>,
+        /// Number of hub messages the spoke had applied when reporting
+        applied_hub_messages: u64,
+    },
+    /// Hub to spoke: a deposit was credited with `shares`
+    DepositCredited {
+        /// Spoke request ID
+        request_id: Hash,
+        /// Shares minted
+        shares: Balance,
+    },
+    /// Hub to spoke: a withdrawal was approved, releasing `assets` from the spoke
+    WithdrawalApproved {
+        /// Spoke request ID
+        request_id: Hash,
+        /// Assets to release
+        assets: Vec<(AssetId, Balance)>,
+    },
+    /// Hub to spoke: a request was rejected and has no effect
+    Rejected {
+        /// Spoke request ID
+        request_id: Hash,
+        /// Reason for the rejection
+        error: DispatchError,
+    },
+}
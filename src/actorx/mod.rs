@@ -1,7 +1,195 @@
 // ActorX module for Matrix-Magiq parachain
 // Contains integrations for ActorX communication frameworks
+//
+// ActorX drives the UnifiedLiquidityPool through a `LiquidityBackend`: the
+// `SubstrateRpcBackend` talks to a deployed contract, the `InMemoryBackend`
+// simulates the pool for off-chain use.
 
-use std::sync::Arc;
-use std::error::Error;
+use core::fmt;
+use scale::{Decode, Encode};
 
-// This will be populated by the ActorX integration scripts
+use crate::unified_liquidity_pool::{self, Error as PoolError};
+
+mod rpc;
+mod simulator;
+
+pub use rpc::SubstrateRpcBackend;
+pub use simulator::InMemoryBackend;
+
+pub type Balance = u128;
+
+// Registry ID of a pool asset, encoded like the contract's `TokenId`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Encode, Decode)]
+pub struct TokenId(pub u32);
+
+impl TokenId {
+    pub const NRSH: Self = Self(unified_liquidity_pool::NRSH);
+    pub const ELXR: Self = Self(unified_liquidity_pool::ELXR);
+    pub const IMRT: Self = Self(unified_liquidity_pool::IMRT);
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ActorXError {
+    // Both sides of a pair or swap are the same token
+    IdenticalTokens,
+    ZeroAmount,
+    // The pool rejected the operation
+    Pool(PoolError),
+    // The node could not be reached or rejected the transaction
+    Rpc(String),
+    // The contract returned data that does not decode, or emitted no event
+    // for a finalized call
+    Decode,
+}
+
+impl fmt::Display for ActorXError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActorXError::IdenticalTokens => write!(f, "identical tokens"),
+            ActorXError::ZeroAmount => write!(f, "zero amount"),
+            ActorXError::Pool(error) => write!(f, "pool error: {:?}", error),
+            ActorXError::Rpc(error) => write!(f, "rpc error: {}", error),
+            ActorXError::Decode => write!(f, "undecodable contract output"),
+        }
+    }
+}
+
+impl std::error::Error for ActorXError {}
+
+impl From<PoolError> for ActorXError {
+    fn from(error: PoolError) -> Self {
+        ActorXError::Pool(error)
+    }
+}
+
+// Messages of the UnifiedLiquidityPool, executed for the backend's account
+pub trait LiquidityBackend {
+    // Deposit `amount` of `token`, returning the shares minted
+    fn add_liquidity(&mut self, token: TokenId, amount: Balance) -> Result<Balance, ActorXError>;
+
+    // Redeem `shares` of `token`, returning the amount paid out
+    fn remove_liquidity(&mut self, token: TokenId, shares: Balance) -> Result<Balance, ActorXError>;
+
+    // Swap `amount_in` of `token_in`, returning the amount of `token_out` received
+    fn swap(
+        &mut self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_in: Balance,
+        min_out: Balance,
+    ) -> Result<Balance, ActorXError>;
+
+    // Single-sided deposit, returning the shares minted of both tokens
+    fn zap_in(
+        &mut self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_in: Balance,
+        min_swap_out: Balance,
+    ) -> Result<(Balance, Balance), ActorXError>;
+
+    // Amount of `token_out` a swap of `amount_in` would currently yield
+    fn quote(&self, token_in: TokenId, token_out: TokenId, amount_in: Balance) -> Result<Balance, ActorXError>;
+}
+
+// ActorX implementation
+pub struct ActorX<B: LiquidityBackend> {
+    backend: B,
+}
+
+impl<B: LiquidityBackend> ActorX<B> {
+    pub fn new(backend: B) -> Self {
+        Self { backend }
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    // Deposit into both pools of a pair, returning the shares minted of each.
+    // The deposits are separate pool operations: if the second one fails the
+    // first one stands.
+    pub fn add_liquidity(&mut self,
+                         token_a: TokenId,
+                         amount_a: Balance,
+                         token_b: TokenId,
+                         amount_b: Balance) -> Result<(Balance, Balance), ActorXError> {
+        if token_a == token_b {
+            return Err(ActorXError::IdenticalTokens);
+        }
+        if amount_a == 0 || amount_b == 0 {
+            return Err(ActorXError::ZeroAmount);
+        }
+
+        let shares_a = self.backend.add_liquidity(token_a, amount_a)?;
+        let shares_b = self.backend.add_liquidity(token_b, amount_b)?;
+        Ok((shares_a, shares_b))
+    }
+
+    // Single-sided deposit: the pool swaps the balancing part of `amount_in`
+    // into `token_out` and mints shares of both tokens
+    pub fn zap_in(&mut self,
+                  token_in: TokenId,
+                  amount_in: Balance,
+                  token_out: TokenId,
+                  min_swap_out: Balance) -> Result<(Balance, Balance), ActorXError> {
+        if token_in == token_out {
+            return Err(ActorXError::IdenticalTokens);
+        }
+        if amount_in == 0 {
+            return Err(ActorXError::ZeroAmount);
+        }
+
+        self.backend.zap_in(token_in, token_out, amount_in, min_swap_out)
+    }
+
+    // Redeem shares of both pools of a pair, returning the amounts paid out
+    pub fn remove_liquidity(&mut self,
+                           token_a: TokenId,
+                           shares_a: Balance,
+                           token_b: TokenId,
+                           shares_b: Balance) -> Result<(Balance, Balance), ActorXError> {
+        if token_a == token_b {
+            return Err(ActorXError::IdenticalTokens);
+        }
+        if shares_a == 0 && shares_b == 0 {
+            return Err(ActorXError::ZeroAmount);
+        }
+
+        let amount_a = match shares_a {
+            0 => 0,
+            shares => self.backend.remove_liquidity(token_a, shares)?,
+        };
+        let amount_b = match shares_b {
+            0 => 0,
+            shares => self.backend.remove_liquidity(token_b, shares)?,
+        };
+        Ok((amount_a, amount_b))
+    }
+
+    pub fn swap(&mut self,
+               token_in: TokenId,
+               amount_in: Balance,
+               token_out: TokenId,
+               min_out: Balance) -> Result<Balance, ActorXError> {
+        if token_in == token_out {
+            return Err(ActorXError::IdenticalTokens);
+        }
+        if amount_in == 0 {
+            return Err(ActorXError::ZeroAmount);
+        }
+
+        self.backend.swap(token_in, token_out, amount_in, min_out)
+    }
+
+    pub fn quote(&self,
+                 token_in: TokenId,
+                 amount_in: Balance,
+                 token_out: TokenId) -> Result<Balance, ActorXError> {
+        if token_in == token_out {
+            return Err(ActorXError::IdenticalTokens);
+        }
+
+        self.backend.quote(token_in, token_out, amount_in)
+    }
+}
@@ -0,0 +1,348 @@
+// UnifiedLiquidityPool backend talking to a node over RPC
+//
+// Every message is dry-run first through `ContractsApi_call`. Errors the pool
+// returns are reported without submitting anything; otherwise the call is
+// submitted as a signed `Contracts::call` with the weight the dry run
+// required. Once the call is finalized its result is read from the event the
+// pool emitted for it, since the state may have moved since the dry run.
+
+use ink::{primitives::AccountId, reflect::ContractEventBase, MessageResult};
+use scale::{Decode, Encode};
+use subxt::{
+    dynamic::Value,
+    ext::{
+        sp_core::{hashing::blake2_256, sr25519},
+        sp_runtime::DispatchError,
+    },
+    tx::PairSigner,
+    utils::AccountId32,
+    OnlineClient, PolkadotConfig,
+};
+use tokio::runtime::Runtime;
+
+use super::{ActorXError, Balance, LiquidityBackend, TokenId};
+use crate::unified_liquidity_pool::{Error as PoolError, UnifiedLiquidityPool};
+
+type Event = <UnifiedLiquidityPool as ContractEventBase>::Type;
+
+// `ReturnFlags` bit set when the contract reverted
+const REVERT_FLAG: u32 = 1;
+
+#[derive(Clone, Copy, Encode, Decode)]
+struct Weight {
+    #[codec(compact)]
+    ref_time: u64,
+    #[codec(compact)]
+    proof_size: u64,
+}
+
+#[derive(Encode, Decode)]
+#[allow(dead_code)]
+enum StorageDeposit {
+    Refund(Balance),
+    Charge(Balance),
+}
+
+#[derive(Encode, Decode)]
+struct ExecReturnValue {
+    flags: u32,
+    data: Vec<u8>,
+}
+
+// Leading fields of `ContractExecResult`; the trailing ones are not needed
+#[derive(Encode, Decode)]
+struct ContractExecResult {
+    _gas_consumed: Weight,
+    gas_required: Weight,
+    _storage_deposit: StorageDeposit,
+    _debug_message: Vec<u8>,
+    result: Result<ExecReturnValue, DispatchError>,
+}
+
+pub struct SubstrateRpcBackend {
+    runtime: Runtime,
+    client: OnlineClient<PolkadotConfig>,
+    signer: PairSigner<PolkadotConfig, sr25519::Pair>,
+    contract: AccountId32,
+}
+
+impl SubstrateRpcBackend {
+    // Connect to the node at `url`, calling `contract` as `signer`
+    pub fn connect(url: &str, signer: sr25519::Pair, contract: AccountId32) -> Result<Self, ActorXError> {
+        let runtime = Runtime::new().map_err(rpc_error)?;
+        let client = runtime
+            .block_on(OnlineClient::<PolkadotConfig>::from_url(url))
+            .map_err(rpc_error)?;
+
+        Ok(Self {
+            runtime,
+            client,
+            signer: PairSigner::new(signer),
+            contract,
+        })
+    }
+
+    // Dry-run `message`, returning its output and the weight it requires
+    fn dry_run<R: Decode>(&self, message: &str, args: impl Encode) -> Result<(R, Weight, Vec<u8>), ActorXError> {
+        let mut input = selector(message).to_vec();
+        args.encode_to(&mut input);
+
+        let request = (
+            self.signer.account_id().clone(),
+            self.contract.clone(),
+            0 as Balance,
+            None::<Weight>,
+            None::<Balance>,
+            input.clone(),
+        )
+            .encode();
+        let exec: ContractExecResult = self.runtime.block_on(async {
+            self.client
+                .runtime_api()
+                .at_latest()
+                .await?
+                .call_raw("ContractsApi_call", Some(&request))
+                .await
+        })
+        .map_err(rpc_error)?;
+
+        let (output, gas_required) = message_output(exec)?;
+        Ok((output, gas_required, input))
+    }
+
+    // Dry-run `message`, then submit it and wait for finalization. The
+    // result is taken by `outcome` from the event the pool emitted for the
+    // signer.
+    fn call<R: Decode, T>(
+        &self,
+        message: &str,
+        args: impl Encode,
+        outcome: impl Fn(Event) -> Option<(AccountId, T)>,
+    ) -> Result<T, ActorXError> {
+        let (_, gas_required, input) = self.dry_run::<R>(message, args)?;
+
+        let call = subxt::dynamic::tx(
+            "Contracts",
+            "call",
+            vec![
+                Value::unnamed_variant("Id", [Value::from_bytes(self.contract.0)]),
+                Value::u128(0),
+                Value::named_composite([
+                    ("ref_time", Value::u128(gas_required.ref_time as u128)),
+                    ("proof_size", Value::u128(gas_required.proof_size as u128)),
+                ]),
+                Value::unnamed_variant("None", []),
+                Value::from_bytes(input),
+            ],
+        );
+        let events = self.runtime.block_on(async {
+            self.client
+                .tx()
+                .sign_and_submit_then_watch_default(&call, &self.signer)
+                .await?
+                .wait_for_finalized_success()
+                .await
+        })
+        .map_err(rpc_error)?;
+
+        let mut emitted = Vec::new();
+        for event in events.iter() {
+            let event = event.map_err(rpc_error)?;
+            if event.pallet_name() == "Contracts" && event.variant_name() == "ContractEmitted" {
+                emitted.push(<(AccountId32, Vec<u8>)>::decode(&mut event.field_bytes())
+                    .map_err(|_| ActorXError::Decode)?);
+            }
+        }
+        emitted_outcome(&self.contract, &self.signer.account_id().0, emitted, outcome)
+    }
+}
+
+impl LiquidityBackend for SubstrateRpcBackend {
+    fn add_liquidity(&mut self, token: TokenId, amount: Balance) -> Result<Balance, ActorXError> {
+        self.call::<Balance, _>("add_liquidity", (token, amount), |event| match event {
+            Event::LiquidityAdded(added) => Some((added.provider, added.shares)),
+            _ => None,
+        })
+    }
+
+    fn remove_liquidity(&mut self, token: TokenId, shares: Balance) -> Result<Balance, ActorXError> {
+        self.call::<Balance, _>("remove_liquidity", (token, shares), |event| match event {
+            Event::LiquidityRemoved(removed) => Some((removed.provider, removed.amount)),
+            _ => None,
+        })
+    }
+
+    fn swap(
+        &mut self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_in: Balance,
+        min_out: Balance,
+    ) -> Result<Balance, ActorXError> {
+        self.call::<Balance, _>("swap", (token_in, token_out, amount_in, min_out), |event| match event {
+            Event::Swapped(swapped) => Some((swapped.trader, swapped.amount_out)),
+            _ => None,
+        })
+    }
+
+    fn zap_in(
+        &mut self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_in: Balance,
+        min_swap_out: Balance,
+    ) -> Result<(Balance, Balance), ActorXError> {
+        self.call::<(Balance, Balance), _>("zap_in", (token_in, token_out, amount_in, min_swap_out), |event| {
+            match event {
+                Event::ZappedIn(zapped) => Some((zapped.provider, (zapped.shares_in, zapped.shares_out))),
+                _ => None,
+            }
+        })
+    }
+
+    fn quote(&self, token_in: TokenId, token_out: TokenId, amount_in: Balance) -> Result<Balance, ActorXError> {
+        self.dry_run("get_quote", (token_in, token_out, amount_in))
+            .map(|(amount_out, _, _)| amount_out)
+    }
+}
+
+// Output of a dry run and the weight it requires, or the error the pool
+// returned
+fn message_output<R: Decode>(exec: ContractExecResult) -> Result<(R, Weight), ActorXError> {
+    let output = exec.result.map_err(|error| ActorXError::Rpc(format!("{:?}", error)))?;
+    let result = MessageResult::<Result<R, PoolError>>::decode(&mut &output.data[..])
+        .map_err(|_| ActorXError::Decode)?
+        .map_err(|error| ActorXError::Rpc(format!("{:?}", error)))?;
+    match result {
+        Ok(value) if output.flags & REVERT_FLAG == 0 => Ok((value, exec.gas_required)),
+        Ok(_) => Err(ActorXError::Decode),
+        Err(error) => Err(ActorXError::Pool(error)),
+    }
+}
+
+// Result `outcome` takes from the first event `contract` emitted for `caller`
+// among the `(contract, data)` pairs of a finalized call
+fn emitted_outcome<T>(
+    contract: &AccountId32,
+    caller: &[u8; 32],
+    emitted: impl IntoIterator<Item = (AccountId32, Vec<u8>)>,
+    outcome: impl Fn(Event) -> Option<(AccountId, T)>,
+) -> Result<T, ActorXError> {
+    emitted
+        .into_iter()
+        .filter(|(emitter, _)| emitter == contract)
+        .filter_map(|(_, data)| Event::decode(&mut &data[..]).ok())
+        .filter_map(outcome)
+        .find(|(who, _)| *who == AccountId::from(*caller))
+        .map(|(_, value)| value)
+        .ok_or(ActorXError::Decode)
+}
+
+// Selector of an inherent ink! message
+fn selector(message: &str) -> [u8; 4] {
+    let hash = blake2_256(message.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn rpc_error(error: impl std::fmt::Display) -> ActorXError {
+    ActorXError::Rpc(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unified_liquidity_pool::{LiquidityAdded, Swapped};
+
+    const POOL: AccountId32 = AccountId32([0xc0; 32]);
+    const SIGNER: [u8; 32] = [0x01; 32];
+    const OTHER: [u8; 32] = [0x02; 32];
+
+    fn exec(result: Result<ExecReturnValue, DispatchError>) -> ContractExecResult {
+        ContractExecResult {
+            _gas_consumed: Weight { ref_time: 1_000, proof_size: 64 },
+            gas_required: Weight { ref_time: 2_000, proof_size: 128 },
+            _storage_deposit: StorageDeposit::Charge(0),
+            _debug_message: Vec::new(),
+            result,
+        }
+    }
+
+    fn returned(flags: u32, output: MessageResult<Result<Balance, PoolError>>) -> ContractExecResult {
+        exec(Ok(ExecReturnValue { flags, data: output.encode() }))
+    }
+
+    fn swapped(trader: [u8; 32], amount_out: Balance) -> Vec<u8> {
+        Event::Swapped(Swapped {
+            trader: trader.into(),
+            token_in: 0,
+            token_out: 1,
+            amount_in: 10_000,
+            amount_out,
+            fee: 30,
+            treasury_fee: 3,
+        })
+        .encode()
+    }
+
+    fn amount_out(event: Event) -> Option<(AccountId, Balance)> {
+        match event {
+            Event::Swapped(swapped) => Some((swapped.trader, swapped.amount_out)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn selectors_match_ink() {
+        assert_eq!(selector("swap"), ink::selector_bytes!("swap"));
+        assert_eq!(selector("zap_in"), ink::selector_bytes!("zap_in"));
+        assert_eq!(selector("get_quote"), ink::selector_bytes!("get_quote"));
+    }
+
+    #[test]
+    fn dry_runs_report_output_and_errors() {
+        let (output, weight) = message_output::<Balance>(returned(0, Ok(Ok(19_743)))).unwrap();
+        assert_eq!((output, weight.ref_time, weight.proof_size), (19_743, 2_000, 128));
+
+        assert_eq!(
+            message_output::<Balance>(returned(REVERT_FLAG, Ok(Err(PoolError::SlippageExceeded)))).err(),
+            Some(ActorXError::Pool(PoolError::SlippageExceeded))
+        );
+        // A successful result from a reverted call is not trusted
+        assert_eq!(message_output::<Balance>(returned(REVERT_FLAG, Ok(Ok(1)))).err(), Some(ActorXError::Decode));
+        assert!(matches!(
+            message_output::<Balance>(returned(0, Err(ink::LangError::CouldNotReadInput))),
+            Err(ActorXError::Rpc(_))
+        ));
+        assert!(matches!(message_output::<Balance>(exec(Err(DispatchError::BadOrigin))), Err(ActorXError::Rpc(_))));
+        assert_eq!(
+            message_output::<Balance>(exec(Ok(ExecReturnValue { flags: 0, data: vec![0xff] }))).err(),
+            Some(ActorXError::Decode)
+        );
+    }
+
+    #[test]
+    fn results_come_from_the_signers_event() {
+        // The dry run may have quoted a different amount: the finalized
+        // event is what counts
+        let emitted = vec![
+            (AccountId32([0xc1; 32]), swapped(SIGNER, 1)),
+            (POOL, vec![0xff]),
+            (POOL, swapped(OTHER, 2)),
+            (POOL, swapped(SIGNER, 19_700)),
+        ];
+        assert_eq!(emitted_outcome(&POOL, &SIGNER, emitted, amount_out), Ok(19_700));
+    }
+
+    #[test]
+    fn calls_without_their_event_fail() {
+        let added = Event::LiquidityAdded(LiquidityAdded {
+            provider: SIGNER.into(),
+            token_id: 0,
+            amount: 100,
+            shares: 100,
+        })
+        .encode();
+        assert_eq!(emitted_outcome(&POOL, &SIGNER, vec![(POOL, added)], amount_out), Err(ActorXError::Decode));
+        assert_eq!(emitted_outcome(&POOL, &SIGNER, Vec::new(), amount_out), Err(ActorXError::Decode));
+    }
+}
@@ -0,0 +1,270 @@
+// In-memory UnifiedLiquidityPool for off-chain simulation
+//
+// Runs the contract's share and pricing math for a single provider. The
+// treasury part of each swap fee leaves the reserves like in the contract;
+// lockups and human verification are not simulated.
+
+use std::collections::BTreeMap;
+
+use super::{ActorXError, Balance, LiquidityBackend, TokenId};
+use crate::unified_liquidity_pool::{
+    balancing_swap_amount, mul_div, quote, Error as PoolError, Quote, RATE_DENOMINATOR,
+};
+
+pub struct InMemoryBackend {
+    // Swap fee, and the treasury part of it, in parts per million
+    fee_rate: Balance,
+    treasury_rate: Balance,
+    reserves: BTreeMap<TokenId, Balance>,
+    treasury: BTreeMap<TokenId, Balance>,
+    total_shares: BTreeMap<TokenId, Balance>,
+    // Shares and wallet balances of the simulated provider
+    shares: BTreeMap<TokenId, Balance>,
+    balances: BTreeMap<TokenId, Balance>,
+}
+
+impl InMemoryBackend {
+    pub fn new(fee_rate: Balance, treasury_rate: Balance) -> Self {
+        Self {
+            fee_rate,
+            treasury_rate,
+            reserves: BTreeMap::new(),
+            treasury: BTreeMap::new(),
+            total_shares: BTreeMap::new(),
+            shares: BTreeMap::new(),
+            balances: BTreeMap::new(),
+        }
+    }
+
+    // Fund the provider's wallet
+    pub fn with_balance(mut self, token: TokenId, amount: Balance) -> Self {
+        *self.balances.entry(token).or_insert(0) += amount;
+        self
+    }
+
+    // Liquidity deposited by other providers, one share per token
+    pub fn with_reserve(mut self, token: TokenId, amount: Balance) -> Self {
+        *self.reserves.entry(token).or_insert(0) += amount;
+        *self.total_shares.entry(token).or_insert(0) += amount;
+        self
+    }
+
+    pub fn balance(&self, token: TokenId) -> Balance {
+        get(&self.balances, token)
+    }
+
+    pub fn shares(&self, token: TokenId) -> Balance {
+        get(&self.shares, token)
+    }
+
+    pub fn reserve(&self, token: TokenId) -> Balance {
+        get(&self.reserves, token)
+    }
+
+    pub fn treasury(&self, token: TokenId) -> Balance {
+        get(&self.treasury, token)
+    }
+
+    fn debit(&mut self, token: TokenId, amount: Balance) -> Result<(), ActorXError> {
+        let balance = self.balances.entry(token).or_insert(0);
+        *balance = balance.checked_sub(amount).ok_or(PoolError::InsufficientLiquidity)?;
+        Ok(())
+    }
+
+    fn mint(&mut self, token: TokenId, amount: Balance) -> Result<Balance, ActorXError> {
+        let reserve = get(&self.reserves, token);
+        let total_shares = get(&self.total_shares, token);
+        let shares = if reserve == 0 || total_shares == 0 {
+            amount
+        } else {
+            mul_div(amount, total_shares, reserve)?
+        };
+        if shares == 0 {
+            return Err(PoolError::ZeroAmount.into());
+        }
+
+        add(&mut self.reserves, token, amount)?;
+        add(&mut self.total_shares, token, shares)?;
+        add(&mut self.shares, token, shares)?;
+        Ok(shares)
+    }
+
+    // Price a swap with the contract's math
+    fn quote_swap(&self, token_in: TokenId, token_out: TokenId, amount_in: Balance) -> Result<Quote, ActorXError> {
+        if token_in == token_out {
+            return Err(PoolError::IdenticalTokens.into());
+        }
+        let (reserve_in, reserve_out) = (get(&self.reserves, token_in), get(&self.reserves, token_out));
+        Ok(quote(reserve_in, reserve_out, amount_in, self.fee_rate, self.treasury_rate)?)
+    }
+
+    // Book a swap whose input was already debited: the treasury fee leaves
+    // the input reserve, the rest of the fee stays for the providers
+    fn apply_swap(&mut self, token_in: TokenId, token_out: TokenId, amount_in: Balance, quote: &Quote) -> Result<(), ActorXError> {
+        add(&mut self.reserves, token_in, amount_in - quote.treasury_fee)?;
+        add(&mut self.treasury, token_in, quote.treasury_fee)?;
+        let reserve_out = self.reserves.entry(token_out).or_insert(0);
+        *reserve_out = reserve_out.checked_sub(quote.amount_out).ok_or(PoolError::InsufficientLiquidity)?;
+        Ok(())
+    }
+}
+
+impl LiquidityBackend for InMemoryBackend {
+    fn add_liquidity(&mut self, token: TokenId, amount: Balance) -> Result<Balance, ActorXError> {
+        self.debit(token, amount)?;
+        self.mint(token, amount)
+    }
+
+    fn remove_liquidity(&mut self, token: TokenId, shares: Balance) -> Result<Balance, ActorXError> {
+        if get(&self.shares, token) < shares {
+            return Err(PoolError::InsufficientShares.into());
+        }
+        let total_shares = get(&self.total_shares, token);
+        let amount = mul_div(shares, get(&self.reserves, token), total_shares)?;
+
+        *self.shares.entry(token).or_insert(0) -= shares;
+        *self.total_shares.entry(token).or_insert(0) -= shares;
+        *self.reserves.entry(token).or_insert(0) -= amount;
+        add(&mut self.balances, token, amount)?;
+        Ok(amount)
+    }
+
+    fn swap(
+        &mut self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_in: Balance,
+        min_out: Balance,
+    ) -> Result<Balance, ActorXError> {
+        let quote = self.quote_swap(token_in, token_out, amount_in)?;
+        if quote.amount_out < min_out {
+            return Err(PoolError::SlippageExceeded.into());
+        }
+
+        self.debit(token_in, amount_in)?;
+        self.apply_swap(token_in, token_out, amount_in, &quote)?;
+        add(&mut self.balances, token_out, quote.amount_out)?;
+        Ok(quote.amount_out)
+    }
+
+    fn zap_in(
+        &mut self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_in: Balance,
+        min_swap_out: Balance,
+    ) -> Result<(Balance, Balance), ActorXError> {
+        // Only the treasury part of the fee leaves the reserve
+        let treasury_rate = mul_div(self.fee_rate, self.treasury_rate, RATE_DENOMINATOR)?;
+        let swap_in = balancing_swap_amount(get(&self.reserves, token_in), amount_in, self.fee_rate, treasury_rate)?;
+
+        let quote = self.quote_swap(token_in, token_out, swap_in)?;
+        if quote.amount_out < min_swap_out {
+            return Err(PoolError::SlippageExceeded.into());
+        }
+
+        self.debit(token_in, amount_in)?;
+        self.apply_swap(token_in, token_out, swap_in, &quote)?;
+        let shares_in = self.mint(token_in, amount_in - swap_in)?;
+        let shares_out = self.mint(token_out, quote.amount_out)?;
+        Ok((shares_in, shares_out))
+    }
+
+    fn quote(&self, token_in: TokenId, token_out: TokenId, amount_in: Balance) -> Result<Balance, ActorXError> {
+        self.quote_swap(token_in, token_out, amount_in).map(|quote| quote.amount_out)
+    }
+}
+
+fn get(map: &BTreeMap<TokenId, Balance>, token: TokenId) -> Balance {
+    map.get(&token).copied().unwrap_or(0)
+}
+
+fn add(map: &mut BTreeMap<TokenId, Balance>, token: TokenId, amount: Balance) -> Result<(), ActorXError> {
+    let value = map.entry(token).or_insert(0);
+    *value = value.checked_add(amount).ok_or(PoolError::ArithmeticError)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actorx::ActorX;
+
+    const NRSH: TokenId = TokenId::NRSH;
+    const ELXR: TokenId = TokenId::ELXR;
+
+    // 1_000_000 NRSH and 4_000_000 ELXR from other providers, a 0.3% fee of
+    // which 10% goes to the treasury, and 1_000_000 of each token in the
+    // wallet
+    fn actorx() -> ActorX<InMemoryBackend> {
+        let backend = InMemoryBackend::new(3_000, 100_000)
+            .with_reserve(NRSH, 1_000_000)
+            .with_reserve(ELXR, 4_000_000)
+            .with_balance(NRSH, 1_000_000)
+            .with_balance(ELXR, 1_000_000);
+        ActorX::new(backend)
+    }
+
+    #[test]
+    fn liquidity_round_trip() {
+        let mut actorx = actorx();
+        assert_eq!(actorx.add_liquidity(NRSH, 100_000, ELXR, 400_000), Ok((100_000, 400_000)));
+
+        assert_eq!(actorx.quote(NRSH, 10_000, ELXR), Ok(39_521));
+        assert_eq!(actorx.swap(NRSH, 10_000, ELXR, 39_522), Err(PoolError::SlippageExceeded.into()));
+        assert_eq!(actorx.swap(NRSH, 10_000, ELXR, 39_521), Ok(39_521));
+        // 3 of the 30 fee go to the treasury
+        assert_eq!((actorx.backend().reserve(NRSH), actorx.backend().reserve(ELXR)), (1_109_997, 4_360_479));
+        assert_eq!(actorx.backend().treasury(NRSH), 3);
+
+        // 48_995 NRSH buy 183_807 ELXR, 14 of the fee going to the
+        // treasury, minting shares of both at the post-swap ratio
+        assert_eq!(actorx.zap_in(NRSH, 100_000, ELXR, 183_808), Err(PoolError::SlippageExceeded.into()));
+        assert_eq!(actorx.zap_in(NRSH, 100_000, ELXR, 183_807), Ok((48_409, 193_635)));
+        let backend = actorx.backend();
+        assert_eq!(backend.treasury(NRSH), 17);
+        assert_eq!((backend.shares(NRSH), backend.shares(ELXR)), (148_409, 593_635));
+        assert_eq!((backend.balance(NRSH), backend.balance(ELXR)), (790_000, 639_521));
+
+        assert_eq!(actorx.remove_liquidity(NRSH, 148_409, ELXR, 593_635), Ok((156_366, 563_504)));
+        let backend = actorx.backend();
+        assert_eq!((backend.shares(NRSH), backend.shares(ELXR)), (0, 0));
+        assert_eq!((backend.balance(NRSH), backend.balance(ELXR)), (946_366, 1_203_025));
+        assert_eq!((backend.reserve(NRSH), backend.reserve(ELXR)), (1_053_617, 3_796_975));
+    }
+
+    #[test]
+    fn zaps_match_the_contract() {
+        let mut actorx = actorx();
+        // 300 ppm of the swap, the treasury part of the fee, leaves the
+        // reserve
+        let swap_in = balancing_swap_amount(1_000_000, 100_000, 3_000, 300).unwrap();
+        let swapped = quote(1_000_000, 4_000_000, swap_in, 3_000, 100_000).unwrap();
+        assert_eq!(actorx.quote(NRSH, swap_in, ELXR), Ok(swapped.amount_out));
+        actorx.zap_in(NRSH, 100_000, ELXR, 0).unwrap();
+
+        // The rest and the output are deposited at the post-swap ratio, up
+        // to a unit of the dearer token
+        let backend = actorx.backend();
+        assert_eq!(backend.treasury(NRSH), swapped.treasury_fee);
+        let (rest, reserve_in) = (100_000 - swap_in, 1_000_000 + swap_in - swapped.treasury_fee);
+        let reserve_out = 4_000_000 - swapped.amount_out;
+        assert_eq!(backend.reserve(NRSH), reserve_in + rest);
+        assert_eq!(backend.reserve(ELXR), 4_000_000);
+        assert!((rest * reserve_out).abs_diff(swapped.amount_out * reserve_in) <= reserve_out);
+    }
+
+    #[test]
+    fn invalid_operations_are_rejected() {
+        let mut actorx = actorx();
+        assert_eq!(actorx.add_liquidity(NRSH, 1_000, NRSH, 1_000), Err(ActorXError::IdenticalTokens));
+        assert_eq!(actorx.swap(NRSH, 0, ELXR, 0), Err(ActorXError::ZeroAmount));
+        assert_eq!(actorx.zap_in(NRSH, 1_000, NRSH, 0), Err(ActorXError::IdenticalTokens));
+        assert_eq!(actorx.remove_liquidity(NRSH, 0, ELXR, 0), Err(ActorXError::ZeroAmount));
+
+        assert_eq!(actorx.remove_liquidity(NRSH, 1, ELXR, 0), Err(PoolError::InsufficientShares.into()));
+        assert_eq!(actorx.swap(NRSH, 1_000_001, ELXR, 0), Err(PoolError::InsufficientLiquidity.into()));
+        assert_eq!(actorx.zap_in(TokenId::IMRT, 1_000, NRSH, 0), Err(PoolError::InsufficientLiquidity.into()));
+        assert_eq!(actorx.backend().balance(NRSH), 1_000_000);
+    }
+}
@@ -0,0 +1,101 @@
+// Human verification for UnifiedLiquidityPool providers
+//
+// Every verifier answers with the time until which its verdict holds, so the
+// pool can cache it. An account counts as human while any configured
+// verifier vouches for it.
+
+use core::marker::PhantomData;
+use ink::env::hash::Blake2x256;
+use ink::env::{
+    call::{build_call, ExecutionInput, Selector},
+    DefaultEnvironment,
+};
+use ink::primitives::AccountId;
+use ink::storage::{traits::StorageKey, Mapping};
+use scale::Decode;
+
+pub type Timestamp = u64;
+
+// Domain separator of attestations signed by an issuer
+pub const ATTESTATION_CONTEXT: &[u8] = b"matrix-magiq/humanity-attestation";
+
+// `ProofOfPersonhood::is_human(account) -> bool`
+const IS_HUMAN_SELECTOR: [u8; 4] = ink::selector_bytes!("ProofOfPersonhood::is_human");
+
+pub trait HumanityVerifier {
+    // Verify `account`, possibly using caller supplied `evidence`. Returns
+    // the time until which the account counts as human, or `None`.
+    fn verify(&self, account: &AccountId, evidence: &[u8], now: Timestamp) -> Option<Timestamp>;
+}
+
+// Accounts listed by the pool owner
+pub struct AllowList<'a, K: StorageKey>(pub &'a Mapping<AccountId, (), K>);
+
+impl<K: StorageKey> HumanityVerifier for AllowList<'_, K> {
+    fn verify(&self, account: &AccountId, _evidence: &[u8], _now: Timestamp) -> Option<Timestamp> {
+        self.0.contains(account).then_some(Timestamp::MAX)
+    }
+}
+
+// Attestation signed by an issuer, identified by its compressed ECDSA key.
+// The evidence is the SCALE encoded `(expires_at, signature)` where the
+// signature covers `blake2_256((ATTESTATION_CONTEXT, account, expires_at))`.
+pub struct AttestationIssuer(pub [u8; 33]);
+
+impl AttestationIssuer {
+    pub fn message_hash(account: &AccountId, expires_at: Timestamp) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        ink::env::hash_encoded::<Blake2x256, _>(&(ATTESTATION_CONTEXT, account, expires_at), &mut hash);
+        hash
+    }
+}
+
+impl HumanityVerifier for AttestationIssuer {
+    fn verify(&self, account: &AccountId, evidence: &[u8], now: Timestamp) -> Option<Timestamp> {
+        let (expires_at, signature) = <(Timestamp, [u8; 65])>::decode(&mut &evidence[..]).ok()?;
+        if expires_at <= now {
+            return None;
+        }
+
+        let mut signer = [0u8; 33];
+        ink::env::ecdsa_recover(&signature, &Self::message_hash(account, expires_at), &mut signer).ok()?;
+        (signer == self.0).then_some(expires_at)
+    }
+}
+
+// Answers `ProofOfPersonhood::is_human` for a registry contract, or `None`
+// if it could not be asked
+pub trait ProofOfPersonhood {
+    fn is_human(registry: AccountId, account: &AccountId) -> Option<bool>;
+}
+
+// Calls to the registry contracts
+pub struct RegistryCalls;
+
+impl ProofOfPersonhood for RegistryCalls {
+    fn is_human(registry: AccountId, account: &AccountId) -> Option<bool> {
+        build_call::<DefaultEnvironment>()
+            .call(registry)
+            .gas_limit(0)
+            .exec_input(ExecutionInput::new(Selector::new(IS_HUMAN_SELECTOR)).push_arg(account))
+            .returns::<bool>()
+            .try_invoke()
+            .ok()?
+            .ok()
+    }
+}
+
+// Proof-of-personhood registry contract, asked through `P`
+pub struct PersonhoodRegistry<P>(pub AccountId, PhantomData<P>);
+
+impl<P> PersonhoodRegistry<P> {
+    pub fn new(registry: AccountId) -> Self {
+        Self(registry, PhantomData)
+    }
+}
+
+impl<P: ProofOfPersonhood> HumanityVerifier for PersonhoodRegistry<P> {
+    fn verify(&self, account: &AccountId, _evidence: &[u8], _now: Timestamp) -> Option<Timestamp> {
+        P::is_human(self.0, account)?.then_some(Timestamp::MAX)
+    }
+}
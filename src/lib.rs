@@ -1,74 +1,480 @@
 // Unified Liquidity Pool Implementation
 // Core component of Matrix-Magiq Liquidity pallet
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
 
-use frame_support::{decl_module, decl_storage, decl_event, ensure};
-use frame_system::{self as system, ensure_signed};
-use sp_std::prelude::*;
-use permaweb_lib::profile::{Profile, Zone, Wallet};
-
-// Unified Liquidity Pool implementation
-#![cfg_attr(not(feature = "std"), no_std)]
-use ink_lang as ink;
-use ink_storage::{
-    traits::SpreadAllocate,
-    Mapping,
-};
-use pqc_kyber::*;
-use pqc_dilithium::*;
-use scale::{Decode, Encode};
+// Human verification of liquidity providers
+pub mod humanity;
+
+// PSP22 token transfers of the pool
+pub mod psp22;
+
+// Stand-ins for the contracts the pool calls, for off-chain unit tests
+#[cfg(test)]
+mod mock;
+
+// Off-chain ActorX integration
+#[cfg(feature = "std")]
+pub mod actorx;
+
+// Off-chain encryption of provider data
+#[cfg(feature = "std")]
+pub mod provider_data;
+
+// Off-chain model of the liquidity pallet's registry and reserves
+#[cfg(feature = "std")]
+#[path = "pallet/liquidity_pallet.rs"]
+pub mod liquidity_pallet;
 
 #[ink::contract]
-mod unified_liquidity_pool {
+pub mod unified_liquidity_pool {
+    use crate::humanity::{
+        AllowList, AttestationIssuer, HumanityVerifier, PersonhoodRegistry,
+    };
+    pub use crate::psp22::PSP22Error;
+    use crate::psp22::{TokenTransport, TransferError};
+    // Token and personhood registry contracts are called on-chain, and
+    // answered from ledgers in unit tests, which cannot call contracts
+    #[cfg(test)]
+    use crate::mock::{Registries, Tokens};
+    #[cfg(not(test))]
+    use crate::{humanity::RegistryCalls as Registries, psp22::Psp22Calls as Tokens};
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use ml_dsa::{EncodedSignature, EncodedVerifyingKey, MlDsa65, Signature, Verifier, VerifyingKey};
+    use scale::{Decode, Encode};
+
+    // Denominator of `fee_rate` and `treasury_rate`, in parts per million
+    pub const RATE_DENOMINATOR: Balance = 1_000_000;
+
+    // Largest encrypted provider data record, in bytes
+    pub const MAX_PROVIDER_DATA_LEN: usize = 4096;
+
+    // Highest swap fee the owner can set, 3%
+    pub const MAX_FEE_RATE: Balance = 30_000;
+
+    // Most lockup tiers the owner can set
+    pub const MAX_LOCKUP_TIERS: usize = 8;
+
+    // Highest lockup tier boost, quadrupling the fee weight of locked shares
+    pub const MAX_LOCKUP_BOOST: Balance = 3 * RATE_DENOMINATOR;
+
+    // Scale of the effective price reported by `ZappedIn`
+    pub const PRICE_PRECISION: Balance = 1_000_000_000_000;
+
+    // Scale of the reserve ratio when sizing the swap of a zap
+    const ZAP_PRECISION: Balance = 1_000_000_000_000;
+
+    // Scale of `boost_fee_per_weight`
+    const BOOST_PRECISION: Balance = 1_000_000_000_000;
+
+    // Delay between proposing and applying new post-quantum keys, in ms
+    pub const KEY_ROTATION_DELAY: Timestamp = 2 * 24 * 60 * 60 * 1000;
+
+    // Length of a Kyber768 public key
+    pub const KYBER_PUBLICKEYBYTES: usize = 1184;
+
+    // Length of an encoded ML-DSA-65 verifying key
+    pub const DILITHIUM_PUBLIC_KEY_LEN: usize = 1952;
+
+    // Domain separator of admin operations signed with the Dilithium key
+    const ADMIN_CONTEXT: &[u8] = b"matrix-magiq/unified-pool-admin";
+
     #[ink(storage)]
-    #[derive(SpreadAllocate)]
     pub struct UnifiedLiquidityPool {
-        // Token reserves for NRSH, ELXR, IMRT
+        // Token reserves of every listed asset
         reserves: Mapping<TokenId, Balance>,
         // Liquidity provider shares
         shares: Mapping<(AccountId, TokenId), Balance>,
-        // Post-quantum encrypted provider data
+        // Total shares issued per token
+        total_shares: Mapping<TokenId, Balance>,
+        // Provider metadata, encrypted off-chain to the provider's Kyber key
         provider_data: Mapping<AccountId, EncryptedData>,
         // Treasury reserves
         treasury: Mapping<TokenId, Balance>,
-        // Protocol parameters
+        // Protocol parameters: swap fee, and the part of it paid to the
+        // treasury, both in parts per million
         fee_rate: Balance,
         treasury_rate: Balance,
-        // Quantum-resistant keys
-        kyber_public_key: KyberPublicKey,
-        dilithium_signature: DilithiumSignature,
+        // Quantum-resistant public keys, supplied by the deployer. Admin
+        // operations can be authorized with the Dilithium key.
+        kyber_public_key: Vec<u8>,
+        dilithium_public_key: Vec<u8>,
+        // Nonce of the next signed admin operation
+        admin_nonce: u64,
+        // Keys proposed by a rotation, and when they may be applied; empty
+        // keys mean no rotation is pending
+        pending_kyber_public_key: Vec<u8>,
+        pending_dilithium_public_key: Vec<u8>,
+        key_rotation_at: Timestamp,
+        // Paused pools reject deposits and swaps; withdrawals stay open
+        paused: bool,
+        // Lockup tiers, the penalty for leaving one early in parts per
+        // million, and the locked shares of each provider
+        lockup_tiers: Vec<LockupTier>,
+        early_exit_penalty: Balance,
+        locks: Mapping<(AccountId, TokenId), Lock>,
+        // Per token: boost weight of all locks, boost fees accrued per unit
+        // of weight (scaled by `BOOST_PRECISION`) and boost fees not yet paid
+        boost_weights: Mapping<TokenId, Balance>,
+        boost_fee_per_weight: Mapping<TokenId, Balance>,
+        boost_fees: Mapping<TokenId, Balance>,
+        // Account allowed to configure the pool
+        owner: AccountId,
+        // Registry of listed assets, and their IDs in listing order
+        assets: Mapping<TokenId, AssetInfo>,
+        asset_ids: Vec<TokenId>,
+        // Human verification: accounts allowed by the owner, compressed
+        // ECDSA keys of attestation issuers and proof-of-personhood
+        // registry contracts
+        human_allow_list: Mapping<AccountId, ()>,
+        attestation_issuers: Vec<[u8; 33]>,
+        personhood_registries: Vec<AccountId>,
+        // Time until which an account was verified, and the longest time a
+        // verification is trusted without asking the verifiers again
+        humanity_cache: Mapping<AccountId, Timestamp>,
+        humanity_ttl: Timestamp,
     }
 
-    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone)]
-    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-    pub enum TokenId {
-        NRSH,
-        ELXR,
-        IMRT,
+    // Registry ID of a listed asset
+    pub type TokenId = u32;
+
+    // IDs of the ecosystem's native tokens
+    pub const NRSH: TokenId = 0;
+    pub const ELXR: TokenId = 1;
+    pub const IMRT: TokenId = 2;
+
+    // Registry entry of a listed asset
+    #[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct AssetInfo {
+        pub id: TokenId,
+        pub symbol: Vec<u8>,
+        pub decimals: u8,
+        // PSP22 contract holding the asset
+        pub contract: AccountId,
+        // Disabled assets can only be withdrawn
+        pub enabled: bool,
     }
 
-    #[derive(Encode, Decode)]
+    #[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
     pub struct EncryptedData {
-        ciphertext: Vec<u8>,
-        nonce: [u8; 24],
+        // Kyber ciphertext followed by the XChaCha20-Poly1305 ciphertext
+        pub ciphertext: Vec<u8>,
+        pub nonce: [u8; 24],
+    }
+
+    // Shares locked for `duration` blocks earn swap fees with their weight
+    // raised by `boost`, in parts per million
+    #[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct LockupTier {
+        pub duration: BlockNumber,
+        pub boost: Balance,
+    }
+
+    // Shares a provider locked in a lockup tier
+    #[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Lock {
+        pub shares: Balance,
+        // `shares` scaled by the tier boost
+        pub boost_weight: Balance,
+        pub unlock_at: BlockNumber,
+        // Boost fees per weight already accounted for, times the weight
+        reward_debt: Balance,
+    }
+
+    // Operations the owner can authorize with a Dilithium signature
+    #[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum AdminOperation {
+        // Propose new keys, applied after `KEY_ROTATION_DELAY`
+        ProposeKeyRotation {
+            kyber_public_key: Vec<u8>,
+            dilithium_public_key: Vec<u8>,
+        },
+        CancelKeyRotation,
+        // Set the swap fee, at most `MAX_FEE_RATE`
+        SetFeeRate { fee_rate: Balance },
+        // Set the treasury part of the swap fee, at most `RATE_DENOMINATOR`
+        SetTreasuryRate { treasury_rate: Balance },
+        // Pay `amount` of the treasury balance of `token_id` to `recipient`
+        WithdrawTreasury {
+            token_id: TokenId,
+            amount: Balance,
+            recipient: AccountId,
+        },
+        SetPaused { paused: bool },
+        TransferOwnership { new_owner: AccountId },
+        // Replace the lockup tiers, at most `MAX_LOCKUP_TIERS` of them in
+        // order of increasing duration; existing locks keep their terms
+        SetLockupTiers {
+            tiers: Vec<LockupTier>,
+            early_exit_penalty: Balance,
+        },
+    }
+
+    // Result of pricing a swap
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Quote {
+        pub amount_out: Balance,
+        pub fee: Balance,
+        // Part of `fee` paid to the treasury
+        pub treasury_fee: Balance,
     }
 
     impl UnifiedLiquidityPool {
         #[ink(constructor)]
-        pub fn new(fee_rate: Balance, treasury_rate: Balance) -> Self {
-            ink_lang::utils::initialize_contract(|contract: &mut Self| {
-                contract.fee_rate = fee_rate;
-                contract.treasury_rate = treasury_rate;
-                
-                // Initialize post-quantum keys
-                let (public_key, _private_key) = kyber_keygen();
-                let (sig_public_key, sig_private_key) = dilithium_keygen();
-                
-                contract.kyber_public_key = public_key;
-                contract.dilithium_signature = dilithium_sign(
-                    &sig_private_key,
-                    &contract.encode()[..]
-                );
-            })
+        pub fn new(
+            fee_rate: Balance,
+            treasury_rate: Balance,
+            kyber_public_key: Vec<u8>,
+            dilithium_public_key: Vec<u8>,
+            assets: Vec<AssetInfo>,
+            humanity_ttl: Timestamp,
+        ) -> Self {
+            assert!(
+                valid_keys(&kyber_public_key, &dilithium_public_key),
+                "invalid post-quantum public keys"
+            );
+            assert!(
+                valid_rates(fee_rate, treasury_rate),
+                "fee_rate or treasury_rate out of bounds"
+            );
+            let mut contract = Self {
+                reserves: Mapping::default(),
+                shares: Mapping::default(),
+                total_shares: Mapping::default(),
+                provider_data: Mapping::default(),
+                treasury: Mapping::default(),
+                fee_rate,
+                treasury_rate,
+                kyber_public_key,
+                dilithium_public_key,
+                admin_nonce: 0,
+                pending_kyber_public_key: Vec::new(),
+                pending_dilithium_public_key: Vec::new(),
+                key_rotation_at: 0,
+                paused: false,
+                lockup_tiers: Vec::new(),
+                early_exit_penalty: 0,
+                locks: Mapping::default(),
+                boost_weights: Mapping::default(),
+                boost_fee_per_weight: Mapping::default(),
+                boost_fees: Mapping::default(),
+                owner: Self::env().caller(),
+                assets: Mapping::default(),
+                asset_ids: Vec::new(),
+                human_allow_list: Mapping::default(),
+                attestation_issuers: Vec::new(),
+                personhood_registries: Vec::new(),
+                humanity_cache: Mapping::default(),
+                humanity_ttl,
+            };
+            for asset in assets {
+                // Duplicate IDs in the genesis list keep the first entry
+                let _ = contract.insert_asset(asset);
+            }
+            contract
+        }
+
+        // Execute `operation`, authorized either by the owner calling or by
+        // a Dilithium signature over the operation and the current nonce
+        #[ink(message)]
+        pub fn execute_admin_operation(
+            &mut self,
+            operation: AdminOperation,
+            signature: Option<Vec<u8>>,
+        ) -> Result<(), Error> {
+            self.do_execute_admin_operation(operation, signature)
+        }
+
+        // Check a Dilithium signature over `operation` bound to `nonce`
+        #[ink(message)]
+        pub fn verify_admin_signature(
+            &self,
+            operation: AdminOperation,
+            nonce: u64,
+            signature: Vec<u8>,
+        ) -> bool {
+            let payload = (ADMIN_CONTEXT, self.env().account_id(), nonce, operation).encode();
+            verify_dilithium(&self.dilithium_public_key, &payload, &signature)
+        }
+
+        #[ink(message)]
+        pub fn get_admin_nonce(&self) -> u64 {
+            self.admin_nonce
+        }
+
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        #[ink(message)]
+        pub fn get_rates(&self) -> (Balance, Balance) {
+            (self.fee_rate, self.treasury_rate)
+        }
+
+        #[ink(message)]
+        pub fn is_paused(&self) -> bool {
+            self.paused
+        }
+
+        // Apply a proposed key rotation once its timelock has passed. Anyone
+        // can call this.
+        #[ink(message)]
+        pub fn apply_key_rotation(&mut self) -> Result<(), Error> {
+            if self.pending_dilithium_public_key.is_empty() {
+                return Err(Error::NoPendingKeyRotation);
+            }
+            if self.env().block_timestamp() < self.key_rotation_at {
+                return Err(Error::TimelockActive);
+            }
+            self.kyber_public_key = core::mem::take(&mut self.pending_kyber_public_key);
+            self.dilithium_public_key = core::mem::take(&mut self.pending_dilithium_public_key);
+            self.key_rotation_at = 0;
+            self.env().emit_event(KeysRotated {});
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_public_keys(&self) -> (Vec<u8>, Vec<u8>) {
+            (self.kyber_public_key.clone(), self.dilithium_public_key.clone())
+        }
+
+        // List a new asset, owner only
+        #[ink(message)]
+        pub fn list_asset(&mut self, asset: AssetInfo) -> Result<(), Error> {
+            self.ensure_owner()?;
+            let id = asset.id;
+            self.insert_asset(asset)?;
+            self.env().emit_event(AssetListed { token_id: id });
+            Ok(())
+        }
+
+        // Point a listed asset at another token contract, owner only
+        #[ink(message)]
+        pub fn set_asset_contract(
+            &mut self,
+            token_id: TokenId,
+            contract: AccountId,
+        ) -> Result<(), Error> {
+            self.ensure_owner()?;
+            let mut asset = self.assets.get(token_id).ok_or(Error::UnknownAsset)?;
+            asset.contract = contract;
+            self.assets.insert(token_id, &asset);
+            Ok(())
+        }
+
+        // Enable or disable a listed asset, owner only. Deposits and swaps
+        // of disabled assets are rejected; providers can still withdraw.
+        #[ink(message)]
+        pub fn set_asset_enabled(
+            &mut self,
+            token_id: TokenId,
+            enabled: bool,
+        ) -> Result<(), Error> {
+            self.ensure_owner()?;
+            let mut asset = self.assets.get(token_id).ok_or(Error::UnknownAsset)?;
+            asset.enabled = enabled;
+            self.assets.insert(token_id, &asset);
+            self.env().emit_event(AssetStatusChanged { token_id, enabled });
+            Ok(())
+        }
+
+        // Allow or disallow `account` as human, owner only
+        #[ink(message)]
+        pub fn set_human_allowed(
+            &mut self,
+            account: AccountId,
+            allowed: bool,
+        ) -> Result<(), Error> {
+            self.ensure_owner()?;
+            if allowed {
+                self.human_allow_list.insert(account, &());
+            } else {
+                self.human_allow_list.remove(account);
+                self.humanity_cache.remove(account);
+            }
+            Ok(())
+        }
+
+        // Configure attestation issuers, registries and the cache lifetime,
+        // owner only. Cached verifications stay valid until they expire.
+        #[ink(message)]
+        pub fn set_humanity_verifiers(
+            &mut self,
+            attestation_issuers: Vec<[u8; 33]>,
+            personhood_registries: Vec<AccountId>,
+            humanity_ttl: Timestamp,
+        ) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.attestation_issuers = attestation_issuers;
+            self.personhood_registries = personhood_registries;
+            self.humanity_ttl = humanity_ttl;
+            Ok(())
+        }
+
+        // Verify the caller with `evidence`, such as an issuer attestation,
+        // returning the time until which the verification is cached
+        #[ink(message)]
+        pub fn verify_humanity(&mut self, evidence: Vec<u8>) -> Result<Timestamp, Error> {
+            let caller = self.env().caller();
+            if !self.verify_human_handprint(&caller, &evidence) {
+                return Err(Error::NotHuman);
+            }
+            self.humanity_cache.get(caller).ok_or(Error::NotHuman)
+        }
+
+        // Time until which `account` is verified as human, if it is
+        #[ink(message)]
+        pub fn get_humanity_expiry(&self, account: AccountId) -> Option<Timestamp> {
+            self.humanity_cache
+                .get(account)
+                .filter(|until| *until > self.env().block_timestamp())
+        }
+
+        // Store or replace the caller's encrypted provider data
+        #[ink(message)]
+        pub fn set_provider_data(&mut self, data: EncryptedData) -> Result<(), Error> {
+            if data.ciphertext.is_empty() {
+                return Err(Error::ZeroAmount);
+            }
+            if data.ciphertext.len() > MAX_PROVIDER_DATA_LEN {
+                return Err(Error::ProviderDataTooLarge);
+            }
+            let provider = self.env().caller();
+            self.provider_data.insert(provider, &data);
+            self.env().emit_event(ProviderDataUpdated { provider, erased: false });
+            Ok(())
+        }
+
+        // Erase the caller's provider data
+        #[ink(message)]
+        pub fn erase_provider_data(&mut self) {
+            let provider = self.env().caller();
+            self.provider_data.remove(provider);
+            self.env().emit_event(ProviderDataUpdated { provider, erased: true });
+        }
+
+        #[ink(message)]
+        pub fn get_provider_data(&self, provider: AccountId) -> Option<EncryptedData> {
+            self.provider_data.get(provider)
+        }
+
+        #[ink(message)]
+        pub fn get_asset(&self, token_id: TokenId) -> Option<AssetInfo> {
+            self.assets.get(token_id)
+        }
+
+        #[ink(message)]
+        pub fn get_assets(&self) -> Vec<AssetInfo> {
+            self.asset_ids
+                .iter()
+                .filter_map(|id| self.assets.get(id))
+                .collect()
         }
 
         #[ink(message)]
@@ -76,23 +482,259 @@ mod unified_liquidity_pool {
             &mut self,
             token_id: TokenId,
             amount: Balance,
+        ) -> Result<Balance, Error> {
+            self.do_add_liquidity(token_id, amount)
+        }
+
+        // Deposit `amount_in` of `token_in` only. The pool swaps the part of
+        // it that balances the deposit into `token_out` at the post-swap
+        // price, and mints shares of both tokens. Fails if the swap yields
+        // less than `min_swap_out`.
+        #[ink(message)]
+        pub fn zap_in(
+            &mut self,
+            token_in: TokenId,
+            token_out: TokenId,
+            amount_in: Balance,
+            min_swap_out: Balance,
+        ) -> Result<(Balance, Balance), Error> {
+            self.do_zap_in(token_in, token_out, amount_in, min_swap_out)
+        }
+
+        #[ink(message)]
+        pub fn remove_liquidity(
+            &mut self,
+            token_id: TokenId,
+            shares: Balance,
+        ) -> Result<Balance, Error> {
+            self.do_remove_liquidity(token_id, shares)
+        }
+
+        #[ink(message)]
+        pub fn swap(
+            &mut self,
+            token_in: TokenId,
+            token_out: TokenId,
+            amount_in: Balance,
+            min_out: Balance,
+        ) -> Result<Balance, Error> {
+            self.do_swap(token_in, token_out, amount_in, min_out)
+        }
+
+        #[ink(message)]
+        pub fn get_quote(
+            &self,
+            token_in: TokenId,
+            token_out: TokenId,
+            amount_in: Balance,
+        ) -> Result<Balance, Error> {
+            self.quote(token_in, token_out, amount_in)
+                .map(|quote| quote.amount_out)
+        }
+
+        #[ink(message)]
+        pub fn get_treasury(&self, token_id: TokenId) -> Balance {
+            self.treasury.get(token_id).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn get_reserve(&self, token_id: TokenId) -> Balance {
+            self.reserves.get(token_id).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn get_total_shares(&self, token_id: TokenId) -> Balance {
+            self.total_shares.get(token_id).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn get_shares(&self, provider: AccountId, token_id: TokenId) -> Balance {
+            self.shares.get((provider, token_id)).unwrap_or(0)
+        }
+
+        // Lock `shares` of the caller in lockup tier `tier`, returning the
+        // block at which they unlock. A provider has one lock per token.
+        #[ink(message)]
+        pub fn lock_liquidity(
+            &mut self,
+            token_id: TokenId,
+            shares: Balance,
+            tier: u32,
+        ) -> Result<BlockNumber, Error> {
+            let caller = self.env().caller();
+            let tier = self.lockup_tiers.get(tier as usize).copied()
+                .ok_or(Error::UnknownLockupTier)?;
+            if shares == 0 {
+                return Err(Error::ZeroAmount);
+            }
+            if self.locks.contains((caller, token_id)) {
+                return Err(Error::LockExists);
+            }
+            if self.shares.get((caller, token_id)).unwrap_or(0) < shares {
+                return Err(Error::InsufficientShares);
+            }
+
+            let boost_weight = mul_div(shares, tier.boost, RATE_DENOMINATOR)?;
+            let unlock_at = self.env().block_number().checked_add(tier.duration)
+                .ok_or(Error::ArithmeticError)?;
+            let reward_debt = mul_div(
+                boost_weight,
+                self.boost_fee_per_weight.get(token_id).unwrap_or(0),
+                BOOST_PRECISION,
+            )?;
+            let total_weight = self.boost_weights.get(token_id).unwrap_or(0)
+                .checked_add(boost_weight)
+                .ok_or(Error::ArithmeticError)?;
+
+            self.locks.insert(
+                (caller, token_id),
+                &Lock { shares, boost_weight, unlock_at, reward_debt },
+            );
+            self.boost_weights.insert(token_id, &total_weight);
+
+            self.env().emit_event(LiquidityLocked {
+                provider: caller,
+                token_id,
+                shares,
+                unlock_at,
+            });
+
+            Ok(unlock_at)
+        }
+
+        // Release the caller's lock, paying out its boost fees. Before the
+        // unlock block, the early exit penalty of the locked shares and the
+        // unpaid boost fees go to the treasury instead.
+        #[ink(message)]
+        pub fn unlock_liquidity(&mut self, token_id: TokenId) -> Result<Balance, Error> {
+            let caller = self.env().caller();
+            self.release_lock(caller, token_id)
+        }
+
+        // Pay out the boost fees of the caller's lock
+        #[ink(message)]
+        pub fn claim_boost_fees(&mut self, token_id: TokenId) -> Result<Balance, Error> {
+            self.do_claim_boost_fees(token_id)
+        }
+
+        #[ink(message)]
+        pub fn get_lock(&self, provider: AccountId, token_id: TokenId) -> Option<Lock> {
+            self.locks.get((provider, token_id))
+        }
+
+        #[ink(message)]
+        pub fn get_pending_boost_fees(&self, provider: AccountId, token_id: TokenId) -> Balance {
+            self.locks.get((provider, token_id))
+                .and_then(|lock| self.pending_boost_fees(token_id, &lock).ok())
+                .unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn get_lockup_tiers(&self) -> (Vec<LockupTier>, Balance) {
+            (self.lockup_tiers.clone(), self.early_exit_penalty)
+        }
+
+        // Helper functions
+        fn do_execute_admin_operation(
+            &mut self,
+            operation: AdminOperation,
+            signature: Option<Vec<u8>>,
+        ) -> Result<(), Error> {
+            match signature {
+                Some(signature) => {
+                    if !self.verify_admin_signature(operation.clone(), self.admin_nonce, signature) {
+                        return Err(Error::InvalidSignature);
+                    }
+                    self.admin_nonce += 1;
+                }
+                None => self.ensure_owner()?,
+            }
+
+            match operation {
+                AdminOperation::ProposeKeyRotation { kyber_public_key, dilithium_public_key } => {
+                    if !valid_keys(&kyber_public_key, &dilithium_public_key) {
+                        return Err(Error::InvalidKey);
+                    }
+                    let effective_at = self.env().block_timestamp()
+                        .saturating_add(KEY_ROTATION_DELAY);
+                    self.pending_kyber_public_key = kyber_public_key;
+                    self.pending_dilithium_public_key = dilithium_public_key;
+                    self.key_rotation_at = effective_at;
+                    self.env().emit_event(KeyRotationProposed { effective_at });
+                }
+                AdminOperation::CancelKeyRotation => {
+                    if self.pending_dilithium_public_key.is_empty() {
+                        return Err(Error::NoPendingKeyRotation);
+                    }
+                    self.clear_key_rotation();
+                }
+                AdminOperation::SetFeeRate { fee_rate } => {
+                    if !valid_rates(fee_rate, self.treasury_rate) {
+                        return Err(Error::InvalidFeeRate);
+                    }
+                    self.fee_rate = fee_rate;
+                    self.env().emit_event(RatesUpdated {
+                        fee_rate,
+                        treasury_rate: self.treasury_rate,
+                    });
+                }
+                AdminOperation::SetTreasuryRate { treasury_rate } => {
+                    if !valid_rates(self.fee_rate, treasury_rate) {
+                        return Err(Error::InvalidFeeRate);
+                    }
+                    self.treasury_rate = treasury_rate;
+                    self.env().emit_event(RatesUpdated {
+                        fee_rate: self.fee_rate,
+                        treasury_rate,
+                    });
+                }
+                AdminOperation::WithdrawTreasury { token_id, amount, recipient } => {
+                    let treasury = self.treasury.get(token_id).unwrap_or(0)
+                        .checked_sub(amount)
+                        .ok_or(Error::InsufficientLiquidity)?;
+                    self.treasury.insert(token_id, &treasury);
+                    self.push_tokens(token_id, recipient, amount)?;
+                    self.env().emit_event(TreasuryWithdrawn { token_id, amount, recipient });
+                }
+                AdminOperation::SetPaused { paused } => {
+                    self.paused = paused;
+                    self.env().emit_event(PauseChanged { paused });
+                }
+                AdminOperation::TransferOwnership { new_owner } => {
+                    let previous_owner = self.owner;
+                    self.owner = new_owner;
+                    self.env().emit_event(OwnershipTransferred { previous_owner, new_owner });
+                }
+                AdminOperation::SetLockupTiers { tiers, early_exit_penalty } => {
+                    if !valid_lockup_tiers(&tiers, early_exit_penalty) {
+                        return Err(Error::InvalidLockupTiers);
+                    }
+                    self.lockup_tiers = tiers;
+                    self.early_exit_penalty = early_exit_penalty;
+                }
+            }
+            Ok(())
+        }
+
+        fn do_add_liquidity(
+            &mut self,
+            token_id: TokenId,
+            amount: Balance,
         ) -> Result<Balance, Error> {
             let caller = self.env().caller();
-            
+
             // Verify humanity protocol handprint
-            if !self.verify_human_handprint(&caller) {
+            if !self.verify_human_handprint(&caller, &[]) {
                 return Err(Error::NotHuman);
             }
 
-            // Calculate shares with post-quantum secure math
-            let shares = self.calculate_shares(token_id, amount)?;
-            
-            // Update reserves with quantum-resistant encryption
-            self.update_reserves(token_id, amount, true)?;
-            
-            // Update provider shares
-            self.shares.insert((caller, token_id), &shares);
-            
+            self.ensure_not_paused()?;
+            self.enabled_asset(token_id)?;
+
+            // Pull the deposit before crediting it
+            self.pull_tokens(token_id, caller, amount)?;
+            let shares = self.mint_shares(caller, token_id, amount)?;
+
             // Emit encrypted event
             self.env().emit_event(LiquidityAdded {
                 provider: caller,
@@ -104,33 +746,46 @@ mod unified_liquidity_pool {
             Ok(shares)
         }
 
-        #[ink(message)]
-        pub fn remove_liquidity(
+        fn do_remove_liquidity(
             &mut self,
             token_id: TokenId,
             shares: Balance,
         ) -> Result<Balance, Error> {
             let caller = self.env().caller();
-            
+
             // Verify ownership of shares
             let provider_shares = self.shares.get((caller, token_id))
                 .ok_or(Error::InsufficientShares)?;
-                
+
             if provider_shares < shares {
                 return Err(Error::InsufficientShares);
             }
 
+            // Locked shares can only be removed once their lock has passed
+            if let Some(lock) = self.locks.get((caller, token_id)) {
+                if provider_shares - lock.shares < shares {
+                    if self.env().block_number() < lock.unlock_at {
+                        return Err(Error::LiquidityLocked);
+                    }
+                    self.release_lock(caller, token_id)?;
+                }
+            }
+
             // Calculate amount with post-quantum secure math
             let amount = self.calculate_withdrawal_amount(token_id, shares)?;
-            
+
             // Update reserves
             self.update_reserves(token_id, amount, false)?;
-            
+
             // Update shares
             self.shares.insert(
                 (caller, token_id),
                 &(provider_shares - shares)
             );
+            self.update_total_shares(token_id, shares, false)?;
+
+            // Pay out once the shares are burnt
+            self.push_tokens(token_id, caller, amount)?;
 
             // Emit encrypted event
             self.env().emit_event(LiquidityRemoved {
@@ -143,59 +798,723 @@ mod unified_liquidity_pool {
             Ok(amount)
         }
 
-        // Helper functions
-        fn verify_human_handprint(&self, account: &AccountId) -> bool {
-            // Integrate with Humanity Protocol for verification
-            true // Simplified for example
+        fn do_swap(
+            &mut self,
+            token_in: TokenId,
+            token_out: TokenId,
+            amount_in: Balance,
+            min_out: Balance,
+        ) -> Result<Balance, Error> {
+            let caller = self.env().caller();
+            self.ensure_not_paused()?;
+            self.enabled_asset(token_in)?;
+            self.enabled_asset(token_out)?;
+            let quote = self.quote(token_in, token_out, amount_in)?;
+            if quote.amount_out < min_out {
+                return Err(Error::SlippageExceeded);
+            }
+
+            self.pull_tokens(token_in, caller, amount_in)?;
+            self.apply_swap(token_in, token_out, amount_in, &quote)?;
+            self.push_tokens(token_out, caller, quote.amount_out)?;
+
+            self.env().emit_event(Swapped {
+                trader: caller,
+                token_in,
+                token_out,
+                amount_in,
+                amount_out: quote.amount_out,
+                fee: quote.fee,
+                treasury_fee: quote.treasury_fee,
+            });
+
+            Ok(quote.amount_out)
         }
 
-        fn calculate_shares(
-            &self,
-            token_id: TokenId,
-            amount: Balance,
-        ) -> Result<Balance, Error> {
-            // Post-quantum secure calculation
-            // Implementation details...
-            Ok(amount) // Simplified for example
+        fn do_zap_in(
+            &mut self,
+            token_in: TokenId,
+            token_out: TokenId,
+            amount_in: Balance,
+            min_swap_out: Balance,
+        ) -> Result<(Balance, Balance), Error> {
+            let caller = self.env().caller();
+            if !self.verify_human_handprint(&caller, &[]) {
+                return Err(Error::NotHuman);
+            }
+            self.ensure_not_paused()?;
+            self.enabled_asset(token_in)?;
+            self.enabled_asset(token_out)?;
+
+            let swap_in = self.zap_swap_amount(token_in, amount_in)?;
+            let quote = self.quote(token_in, token_out, swap_in)?;
+            if quote.amount_out < min_swap_out {
+                return Err(Error::SlippageExceeded);
+            }
+
+            self.pull_tokens(token_in, caller, amount_in)?;
+            self.apply_swap(token_in, token_out, swap_in, &quote)?;
+            let shares_in = self.mint_shares(caller, token_in, amount_in - swap_in)?;
+            let shares_out = self.mint_shares(caller, token_out, quote.amount_out)?;
+
+            self.env().emit_event(ZappedIn {
+                provider: caller,
+                token_in,
+                token_out,
+                amount_in,
+                swap_in,
+                swap_out: quote.amount_out,
+                effective_price: mul_div(quote.amount_out, PRICE_PRECISION, swap_in)?,
+                shares_in,
+                shares_out,
+            });
+
+            Ok((shares_in, shares_out))
         }
 
-        fn update_reserves(
+        // Part of a single-sided deposit of `amount` to swap, counting the
+        // treasury and boost fees that leave the reserve
+        fn zap_swap_amount(&self, token_in: TokenId, amount: Balance) -> Result<Balance, Error> {
+            let reserve = self.reserves.get(token_in).unwrap_or(0);
+            let treasury_rate = mul_div(self.fee_rate, self.treasury_rate, RATE_DENOMINATOR)?;
+            let leaving = treasury_rate + self.boost_fee(token_in, self.fee_rate - treasury_rate)?;
+            balancing_swap_amount(reserve, amount, self.fee_rate, leaving)
+        }
+
+        // Book a quoted swap: the LP part of the fee stays in the input
+        // reserve, except for the boost earned by locked shares
+        fn apply_swap(
             &mut self,
-            token_id: TokenId,
+            token_in: TokenId,
+            token_out: TokenId,
+            amount_in: Balance,
+            quote: &Quote,
+        ) -> Result<(), Error> {
+            let boost_fee = self.boost_fee(token_in, quote.fee - quote.treasury_fee)?;
+            self.update_reserves(token_in, amount_in - quote.treasury_fee - boost_fee, true)?;
+            self.update_reserves(token_out, quote.amount_out, false)?;
+            self.accrue_boost_fee(token_in, boost_fee)?;
+            let treasury = self.treasury.get(token_in).unwrap_or(0)
+                .checked_add(quote.treasury_fee)
+                .ok_or(Error::ArithmeticError)?;
+            self.treasury.insert(token_in, &treasury);
+            Ok(())
+        }
+
+        // Credit `amount`, already received, to `provider`
+        fn mint_shares(
+            &mut self,
+            provider: AccountId,
+            token_id: TokenId,
+            amount: Balance,
+        ) -> Result<Balance, Error> {
+            // Calculate shares with post-quantum secure math
+            let shares = self.calculate_shares(token_id, amount)?;
+            if shares == 0 {
+                return Err(Error::ZeroAmount);
+            }
+
+            // Update reserves with quantum-resistant encryption
+            self.update_reserves(token_id, amount, true)?;
+
+            // Update provider shares
+            let provider_shares = self.shares.get((provider, token_id)).unwrap_or(0)
+                .checked_add(shares)
+                .ok_or(Error::ArithmeticError)?;
+            self.shares.insert((provider, token_id), &provider_shares);
+            self.update_total_shares(token_id, shares, true)?;
+            Ok(shares)
+        }
+
+        fn release_lock(
+            &mut self,
+            provider: AccountId,
+            token_id: TokenId,
+        ) -> Result<Balance, Error> {
+            let lock = self.locks.get((provider, token_id)).ok_or(Error::NoLock)?;
+            let pending = self.pending_boost_fees(token_id, &lock)?;
+
+            self.locks.remove((provider, token_id));
+            let total_weight = self.boost_weights.get(token_id).unwrap_or(0)
+                .checked_sub(lock.boost_weight)
+                .ok_or(Error::ArithmeticError)?;
+            self.boost_weights.insert(token_id, &total_weight);
+            self.update_boost_fees(token_id, pending, false)?;
+
+            let early = self.env().block_number() < lock.unlock_at;
+            let (paid, penalty_shares) = if early {
+                // Burn the penalty shares and move their value, along with
+                // the forfeited boost fees, to the treasury
+                let penalty_shares = mul_div(lock.shares, self.early_exit_penalty, RATE_DENOMINATOR)?;
+                let penalty = self.calculate_withdrawal_amount(token_id, penalty_shares)?;
+                let provider_shares = self.shares.get((provider, token_id)).unwrap_or(0)
+                    .checked_sub(penalty_shares)
+                    .ok_or(Error::ArithmeticError)?;
+                self.shares.insert((provider, token_id), &provider_shares);
+                self.update_total_shares(token_id, penalty_shares, false)?;
+                self.update_reserves(token_id, penalty, false)?;
+
+                let treasury = self.treasury.get(token_id).unwrap_or(0)
+                    .checked_add(penalty)
+                    .and_then(|treasury| treasury.checked_add(pending))
+                    .ok_or(Error::ArithmeticError)?;
+                self.treasury.insert(token_id, &treasury);
+                (0, penalty_shares)
+            } else {
+                if pending > 0 {
+                    self.push_tokens(token_id, provider, pending)?;
+                }
+                (pending, 0)
+            };
+
+            self.env().emit_event(LiquidityUnlocked {
+                provider,
+                token_id,
+                boost_fees: paid,
+                penalty_shares,
+            });
+
+            Ok(paid)
+        }
+
+        fn do_claim_boost_fees(&mut self, token_id: TokenId) -> Result<Balance, Error> {
+            let caller = self.env().caller();
+            let mut lock = self.locks.get((caller, token_id)).ok_or(Error::NoLock)?;
+            let pending = self.pending_boost_fees(token_id, &lock)?;
+            if pending == 0 {
+                return Ok(0);
+            }
+
+            lock.reward_debt = lock.reward_debt.checked_add(pending)
+                .ok_or(Error::ArithmeticError)?;
+            self.locks.insert((caller, token_id), &lock);
+            self.update_boost_fees(token_id, pending, false)?;
+            self.push_tokens(token_id, caller, pending)?;
+            Ok(pending)
+        }
+
+        fn pending_boost_fees(&self, token_id: TokenId, lock: &Lock) -> Result<Balance, Error> {
+            let accrued = mul_div(
+                lock.boost_weight,
+                self.boost_fee_per_weight.get(token_id).unwrap_or(0),
+                BOOST_PRECISION,
+            )?;
+            Ok(accrued.saturating_sub(lock.reward_debt))
+        }
+
+        // Part of `lp_fee` earned by the boost of locked shares: every share
+        // counts once, and locked shares additionally count their boost weight
+        fn boost_fee(&self, token_id: TokenId, lp_fee: Balance) -> Result<Balance, Error> {
+            let boost_weight = self.boost_weights.get(token_id).unwrap_or(0);
+            if boost_weight == 0 {
+                return Ok(0);
+            }
+            let total_weight = self.total_shares.get(token_id).unwrap_or(0)
+                .checked_add(boost_weight)
+                .ok_or(Error::ArithmeticError)?;
+            mul_div(lp_fee, boost_weight, total_weight)
+        }
+
+        fn accrue_boost_fee(&mut self, token_id: TokenId, fee: Balance) -> Result<(), Error> {
+            if fee == 0 {
+                return Ok(());
+            }
+            let boost_weight = self.boost_weights.get(token_id).unwrap_or(0);
+            let per_weight = self.boost_fee_per_weight.get(token_id).unwrap_or(0)
+                .checked_add(mul_div(fee, BOOST_PRECISION, boost_weight)?)
+                .ok_or(Error::ArithmeticError)?;
+            self.boost_fee_per_weight.insert(token_id, &per_weight);
+            self.update_boost_fees(token_id, fee, true)
+        }
+
+        fn update_boost_fees(
+            &mut self,
+            token_id: TokenId,
+            amount: Balance,
+            is_addition: bool,
+        ) -> Result<(), Error> {
+            let current = self.boost_fees.get(token_id).unwrap_or(0);
+
+            let new_amount = if is_addition {
+                current.checked_add(amount)
+            } else {
+                current.checked_sub(amount)
+            }.ok_or(Error::ArithmeticError)?;
+
+            self.boost_fees.insert(token_id, &new_amount);
+            Ok(())
+        }
+
+        fn clear_key_rotation(&mut self) {
+            self.pending_kyber_public_key = Vec::new();
+            self.pending_dilithium_public_key = Vec::new();
+            self.key_rotation_at = 0;
+        }
+
+        fn ensure_not_paused(&self) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            Ok(())
+        }
+
+        fn ensure_owner(&self) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            Ok(())
+        }
+
+        fn insert_asset(&mut self, asset: AssetInfo) -> Result<(), Error> {
+            if self.assets.contains(asset.id) {
+                return Err(Error::AssetAlreadyListed);
+            }
+            self.asset_ids.push(asset.id);
+            self.assets.insert(asset.id, &asset);
+            Ok(())
+        }
+
+        fn enabled_asset(&self, token_id: TokenId) -> Result<AssetInfo, Error> {
+            let asset = self.assets.get(token_id).ok_or(Error::UnknownAsset)?;
+            if !asset.enabled {
+                return Err(Error::AssetDisabled);
+            }
+            Ok(asset)
+        }
+
+        fn token_contract(&self, token_id: TokenId) -> Result<AccountId, Error> {
+            self.assets.get(token_id)
+                .map(|asset| asset.contract)
+                .ok_or(Error::UnknownAsset)
+        }
+
+        // Pull `amount` of `token_id` from `from` into the pool
+        fn pull_tokens(
+            &self,
+            token_id: TokenId,
+            from: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            let token = self.token_contract(token_id)?;
+            Tokens::transfer_from(token, from, self.env().account_id(), amount)
+                .map_err(Error::from)
+        }
+
+        // Pay `amount` of `token_id` from the pool to `to`
+        fn push_tokens(
+            &self,
+            token_id: TokenId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            let token = self.token_contract(token_id)?;
+            Tokens::transfer(token, to, amount).map_err(Error::from)
+        }
+
+        // Check the cache, then every configured verifier. A positive result
+        // is cached for at most `humanity_ttl`.
+        fn verify_human_handprint(&mut self, account: &AccountId, evidence: &[u8]) -> bool {
+            let now = self.env().block_timestamp();
+            if self.humanity_cache.get(account).is_some_and(|until| until > now) {
+                return true;
+            }
+
+            let mut verified_until = AllowList(&self.human_allow_list).verify(account, evidence, now);
+            for issuer in &self.attestation_issuers {
+                verified_until = verified_until
+                    .max(AttestationIssuer(*issuer).verify(account, evidence, now));
+            }
+            for registry in &self.personhood_registries {
+                verified_until = verified_until
+                    .max(PersonhoodRegistry::<Registries>::new(*registry).verify(account, evidence, now));
+            }
+
+            match verified_until.map(|until| until.min(now.saturating_add(self.humanity_ttl))) {
+                Some(until) if until > now => {
+                    self.humanity_cache.insert(account, &until);
+                    true
+                }
+                _ => false,
+            }
+        }
+
+        // Shares are minted in proportion to the reserve, so fees left in
+        // `reserves` raise the value of every share
+        fn calculate_shares(
+            &self,
+            token_id: TokenId,
+            amount: Balance,
+        ) -> Result<Balance, Error> {
+            let reserve = self.reserves.get(token_id).unwrap_or(0);
+            let total_shares = self.total_shares.get(token_id).unwrap_or(0);
+
+            // The first deposit mints one share per token
+            if total_shares == 0 || reserve == 0 {
+                return Ok(amount);
+            }
+            mul_div(amount, total_shares, reserve)
+        }
+
+        fn calculate_withdrawal_amount(
+            &self,
+            token_id: TokenId,
+            shares: Balance,
+        ) -> Result<Balance, Error> {
+            let reserve = self.reserves.get(token_id).unwrap_or(0);
+            let total_shares = self.total_shares.get(token_id).unwrap_or(0);
+            if shares > total_shares {
+                return Err(Error::InsufficientLiquidity);
+            }
+            mul_div(shares, reserve, total_shares)
+        }
+
+        // Price a swap against the constant product of the three reserves.
+        // Only the input and output reserves move, so this is the pairwise
+        // product of those two.
+        fn quote(
+            &self,
+            token_in: TokenId,
+            token_out: TokenId,
+            amount_in: Balance,
+        ) -> Result<Quote, Error> {
+            if token_in == token_out {
+                return Err(Error::IdenticalTokens);
+            }
+            quote(
+                self.reserves.get(token_in).unwrap_or(0),
+                self.reserves.get(token_out).unwrap_or(0),
+                amount_in,
+                self.fee_rate,
+                self.treasury_rate,
+            )
+        }
+
+        fn update_total_shares(
+            &mut self,
+            token_id: TokenId,
+            shares: Balance,
+            is_addition: bool,
+        ) -> Result<(), Error> {
+            let current = self.total_shares.get(token_id).unwrap_or(0);
+
+            let new_total = if is_addition {
+                current.checked_add(shares)
+            } else {
+                current.checked_sub(shares)
+            }.ok_or(Error::ArithmeticError)?;
+
+            self.total_shares.insert(token_id, &new_total);
+            Ok(())
+        }
+
+        fn update_reserves(
+            &mut self,
+            token_id: TokenId,
             amount: Balance,
             is_addition: bool,
         ) -> Result<(), Error> {
             let current = self.reserves.get(token_id)
                 .unwrap_or(0);
-            
+
             let new_amount = if is_addition {
                 current.checked_add(amount)
             } else {
                 current.checked_sub(amount)
             }.ok_or(Error::ArithmeticError)?;
-            
+
             self.reserves.insert(token_id, &new_amount);
             Ok(())
         }
     }
 
+    // Integer square root, rounded down
+    fn sqrt(n: Balance) -> Balance {
+        if n < 2 {
+            return n;
+        }
+        let mut x = n;
+        let mut y = x.div_ceil(2);
+        while y < x {
+            x = y;
+            y = (x + n / x) / 2;
+        }
+        x
+    }
+
+    // Part of a single-sided deposit of `amount` to swap so the rest and
+    // the swap output are worth the same at the post-swap price. With
+    // reserve `r`, fee `f` and `k = (1 - f) (1 - l)`, where `l` is the part
+    // of the swap leaving the reserve, this solves
+    // `k s^2 + r (2 - f) s - a r = 0`, i.e.
+    // `s = (sqrt(r^2 (2 - f)^2 + 4 k a r) - r (2 - f)) / (2 k)`,
+    // computed on `a / r` to stay within 128 bits. Rates are in parts per
+    // million.
+    pub fn balancing_swap_amount(
+        reserve: Balance,
+        amount: Balance,
+        fee_rate: Balance,
+        leaving_rate: Balance,
+    ) -> Result<Balance, Error> {
+        if reserve == 0 {
+            return Err(Error::InsufficientLiquidity);
+        }
+        let k = mul_div(
+            RATE_DENOMINATOR - fee_rate,
+            RATE_DENOMINATOR - leaving_rate,
+            RATE_DENOMINATOR,
+        )?;
+        let h = 2 * RATE_DENOMINATOR - fee_rate;
+
+        // Dividing the radicand by r^2 and scaling it by ZAP_PRECISION
+        // scales the root by sqrt(ZAP_PRECISION)
+        let root_scale = sqrt(ZAP_PRECISION);
+        let ratio = mul_div(amount, ZAP_PRECISION, reserve)?;
+        let square = h.checked_mul(h).and_then(|h2| h2.checked_mul(ZAP_PRECISION));
+        let linear = (4 * k * RATE_DENOMINATOR).checked_mul(ratio);
+        let radicand = square.zip(linear)
+            .and_then(|(square, linear)| square.checked_add(linear))
+            .ok_or(Error::ArithmeticError)?;
+        let numerator = sqrt(radicand)
+            .checked_sub(h * root_scale)
+            .ok_or(Error::ArithmeticError)?;
+
+        let swap_in = mul_div(reserve, numerator, 2 * k * root_scale)?;
+        if swap_in == 0 || swap_in >= amount {
+            return Err(Error::ZeroAmount);
+        }
+        Ok(swap_in)
+    }
+
+    fn valid_rates(fee_rate: Balance, treasury_rate: Balance) -> bool {
+        fee_rate <= MAX_FEE_RATE && treasury_rate <= RATE_DENOMINATOR
+    }
+
+    // Every tier locks for some blocks with a boost of at most
+    // `MAX_LOCKUP_BOOST`, and each tier locks longer than the one before
+    fn valid_lockup_tiers(tiers: &[LockupTier], early_exit_penalty: Balance) -> bool {
+        tiers.len() <= MAX_LOCKUP_TIERS &&
+            tiers.iter().all(|tier| tier.duration > 0 && tier.boost <= MAX_LOCKUP_BOOST) &&
+            tiers.windows(2).all(|pair| pair[0].duration < pair[1].duration) &&
+            early_exit_penalty <= RATE_DENOMINATOR
+    }
+
+    // Check an ML-DSA-65 (standardized Dilithium3) signature with an empty context
+    fn verify_dilithium(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        let (Ok(public_key), Ok(signature)) = (
+            EncodedVerifyingKey::<MlDsa65>::try_from(public_key),
+            EncodedSignature::<MlDsa65>::try_from(signature),
+        ) else {
+            return false;
+        };
+        Signature::<MlDsa65>::decode(&signature).is_some_and(|signature| {
+            VerifyingKey::<MlDsa65>::decode(&public_key).verify(message, &signature).is_ok()
+        })
+    }
+
+    fn valid_keys(kyber_public_key: &[u8], dilithium_public_key: &[u8]) -> bool {
+        kyber_public_key.len() == KYBER_PUBLICKEYBYTES &&
+            dilithium_public_key.len() == DILITHIUM_PUBLIC_KEY_LEN
+    }
+
+    // Price a swap of `amount_in` against the constant product of
+    // `reserve_in` and `reserve_out`. `fee_rate` of the input is charged as
+    // a fee, `treasury_rate` of which goes to the treasury; rates are in
+    // parts per million.
+    pub fn quote(
+        reserve_in: Balance,
+        reserve_out: Balance,
+        amount_in: Balance,
+        fee_rate: Balance,
+        treasury_rate: Balance,
+    ) -> Result<Quote, Error> {
+        if amount_in == 0 {
+            return Err(Error::ZeroAmount);
+        }
+
+        let fee = mul_div(amount_in, fee_rate, RATE_DENOMINATOR)?;
+        let treasury_fee = mul_div(fee, treasury_rate, RATE_DENOMINATOR)?;
+        let amount_in_after_fee = amount_in.checked_sub(fee).ok_or(Error::ArithmeticError)?;
+
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err(Error::InsufficientLiquidity);
+        }
+
+        let amount_out = mul_div(
+            reserve_out,
+            amount_in_after_fee,
+            reserve_in.checked_add(amount_in_after_fee).ok_or(Error::ArithmeticError)?,
+        )?;
+        if amount_out == 0 || amount_out >= reserve_out {
+            return Err(Error::InsufficientLiquidity);
+        }
+
+        Ok(Quote { amount_out, fee, treasury_fee })
+    }
+
+    // `a * b / c`, rounded down. The product is taken in 256 bits, so this
+    // only fails when `c` is zero or the result does not fit.
+    pub fn mul_div(a: Balance, b: Balance, c: Balance) -> Result<Balance, Error> {
+        if c == 0 {
+            return Err(Error::ArithmeticError);
+        }
+        if let Some(product) = a.checked_mul(b) {
+            return Ok(product / c);
+        }
+
+        let (high, low) = widening_mul(a, b);
+        if high >= c {
+            return Err(Error::ArithmeticError);
+        }
+        // Long division of the 256-bit product, one bit of `low` at a time.
+        // The remainder stays below `c`; `carry` is its bit shifted past 128.
+        let (mut remainder, mut quotient) = (high, 0);
+        for bit in (0..Balance::BITS).rev() {
+            let carry = remainder >> (Balance::BITS - 1);
+            remainder = (remainder << 1) | ((low >> bit) & 1);
+            quotient <<= 1;
+            if carry == 1 || remainder >= c {
+                remainder = remainder.wrapping_sub(c);
+                quotient |= 1;
+            }
+        }
+        Ok(quotient)
+    }
+
+    // Full product of `a` and `b` as its high and low 128 bits
+    fn widening_mul(a: Balance, b: Balance) -> (Balance, Balance) {
+        const LOW: Balance = u64::MAX as Balance;
+        let (a_high, a_low) = (a >> 64, a & LOW);
+        let (b_high, b_low) = (b >> 64, b & LOW);
+
+        let low_low = a_low * b_low;
+        let high_low = a_high * b_low;
+        let low_high = a_low * b_high;
+        let cross = (low_low >> 64) + (high_low & LOW) + (low_high & LOW);
+
+        let low = (cross << 64) | (low_low & LOW);
+        let high = a_high * b_high + (high_low >> 64) + (low_high >> 64) + (cross >> 64);
+        (high, low)
+    }
+
     // Events
     #[ink(event)]
     pub struct LiquidityAdded {
+        #[ink(topic)]
+        pub provider: AccountId,
+        pub token_id: TokenId,
+        pub amount: Balance,
+        pub shares: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LiquidityRemoved {
+        #[ink(topic)]
+        pub provider: AccountId,
+        pub token_id: TokenId,
+        pub amount: Balance,
+        pub shares: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Swapped {
+        #[ink(topic)]
+        pub trader: AccountId,
+        pub token_in: TokenId,
+        pub token_out: TokenId,
+        pub amount_in: Balance,
+        pub amount_out: Balance,
+        pub fee: Balance,
+        pub treasury_fee: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AssetListed {
+        #[ink(topic)]
+        token_id: TokenId,
+    }
+
+    #[ink(event)]
+    pub struct AssetStatusChanged {
+        #[ink(topic)]
+        token_id: TokenId,
+        enabled: bool,
+    }
+
+    #[ink(event)]
+    pub struct ProviderDataUpdated {
+        #[ink(topic)]
+        provider: AccountId,
+        erased: bool,
+    }
+
+    #[ink(event)]
+    pub struct ZappedIn {
+        #[ink(topic)]
+        pub provider: AccountId,
+        pub token_in: TokenId,
+        pub token_out: TokenId,
+        pub amount_in: Balance,
+        // Part of `amount_in` swapped, and what it bought
+        pub swap_in: Balance,
+        pub swap_out: Balance,
+        // `swap_out` per `swap_in`, scaled by `PRICE_PRECISION`
+        pub effective_price: Balance,
+        pub shares_in: Balance,
+        pub shares_out: Balance,
+    }
+
+    #[ink(event)]
+    pub struct LiquidityLocked {
         #[ink(topic)]
         provider: AccountId,
         token_id: TokenId,
-        amount: Balance,
         shares: Balance,
+        unlock_at: BlockNumber,
     }
 
     #[ink(event)]
-    pub struct LiquidityRemoved {
+    pub struct LiquidityUnlocked {
         #[ink(topic)]
         provider: AccountId,
         token_id: TokenId,
+        boost_fees: Balance,
+        // Shares burnt for leaving before the unlock block
+        penalty_shares: Balance,
+    }
+
+    #[ink(event)]
+    pub struct KeyRotationProposed {
+        effective_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct KeysRotated {}
+
+    #[ink(event)]
+    pub struct RatesUpdated {
+        fee_rate: Balance,
+        treasury_rate: Balance,
+    }
+
+    #[ink(event)]
+    pub struct TreasuryWithdrawn {
+        #[ink(topic)]
+        token_id: TokenId,
         amount: Balance,
-        shares: Balance,
+        recipient: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct PauseChanged {
+        paused: bool,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous_owner: AccountId,
+        #[ink(topic)]
+        new_owner: AccountId,
     }
 
     // Custom errors
@@ -203,63 +1522,822 @@ mod unified_liquidity_pool {
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub enum Error {
         InsufficientShares,
+        InsufficientLiquidity,
         ArithmeticError,
+        ZeroAmount,
+        IdenticalTokens,
+        SlippageExceeded,
         NotHuman,
+        NotOwner,
+        UnknownAsset,
+        AssetAlreadyListed,
+        AssetDisabled,
+        ProviderDataTooLarge,
+        InvalidKey,
+        InvalidSignature,
+        NoPendingKeyRotation,
+        TimelockActive,
+        InvalidFeeRate,
+        Paused,
+        UnknownLockupTier,
+        InvalidLockupTiers,
+        LockExists,
+        NoLock,
+        LiquidityLocked,
+        TokenCallFailed,
+        TransferFailed,
         // Add more error types as needed
     }
-}
 
+    impl From<TransferError> for Error {
+        fn from(error: TransferError) -> Self {
+            match error {
+                TransferError::CallFailed => Error::TokenCallFailed,
+                TransferError::Rejected => Error::TransferFailed,
+            }
+        }
+    }
 
-// ActorX implementation with permaweb integration
-pub struct ActorX {
-    profile: Profile,
-    zone: Zone,
-    wallet: Wallet,
-}
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::mock::{psp22, registry};
+        use ink::env::{test, DefaultEnvironment};
+        use secp256k1::{Message, PublicKey, SecretKey, SECP256K1};
 
-impl ActorX {
-    pub fn new() -> Self {
-        let profile = Profile::new("Liquidity-Pool");
-        let zone = Zone::new(&profile);
-        let wallet = Wallet::new(&profile);
-        
-        Self {
-            profile,
-            zone,
-            wallet,
+        fn accounts() -> test::DefaultAccounts<DefaultEnvironment> {
+            test::default_accounts::<DefaultEnvironment>()
+        }
+
+        fn asset(id: TokenId, symbol: &[u8]) -> AssetInfo {
+            AssetInfo {
+                id,
+                symbol: symbol.to_vec(),
+                decimals: 12,
+                contract: AccountId::from([0x10 + id as u8; 32]),
+                enabled: true,
+            }
+        }
+
+        // Pool owned by Alice with a 0.3% fee, a tenth of it for the
+        // treasury, and the three native tokens listed. The pool runs under
+        // its own account rather than the default, Alice's.
+        fn pool() -> UnifiedLiquidityPool {
+            test::set_callee::<DefaultEnvironment>(AccountId::from([0xc0; 32]));
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            UnifiedLiquidityPool::new(
+                3_000,
+                100_000,
+                vec![0; KYBER_PUBLICKEYBYTES],
+                vec![0; DILITHIUM_PUBLIC_KEY_LEN],
+                vec![asset(NRSH, b"NRSH"), asset(ELXR, b"ELXR"), asset(IMRT, b"IMRT")],
+                60_000,
+            )
+        }
+
+        // Book a deposit as if its tokens had been transferred
+        fn seed(pool: &mut UnifiedLiquidityPool, provider: AccountId, token_id: TokenId, amount: Balance) {
+            pool.mint_shares(provider, token_id, amount).unwrap();
+        }
+
+        // PSP22 contract of a listed asset
+        fn token(token_id: TokenId) -> AccountId {
+            asset(token_id, b"").contract
+        }
+
+        fn balance_of(token_id: TokenId, owner: AccountId) -> Balance {
+            psp22::balance_of(token(token_id), owner)
+        }
+
+        // Allow `account` as human and make it the caller
+        fn allow_human(pool: &mut UnifiedLiquidityPool, account: AccountId) {
+            test::set_caller::<DefaultEnvironment>(pool.get_owner());
+            pool.set_human_allowed(account, true).unwrap();
+            test::set_caller::<DefaultEnvironment>(account);
+        }
+
+        // Give `provider` `amount` of `token_id` and deposit it, returning the
+        // shares minted. The caller is left set to `provider`.
+        fn deposit(pool: &mut UnifiedLiquidityPool, provider: AccountId, token_id: TokenId, amount: Balance) -> Balance {
+            allow_human(pool, provider);
+            psp22::mint(token(token_id), provider, amount);
+            pool.add_liquidity(token_id, amount).unwrap()
+        }
+
+        // A 100 block tier boosting by half and a 1_000 block tier doubling
+        // the fee weight; leaving early costs a tenth of the locked shares
+        fn set_lockup_tiers(pool: &mut UnifiedLiquidityPool) {
+            test::set_caller::<DefaultEnvironment>(pool.get_owner());
+            let tiers = vec![
+                LockupTier { duration: 100, boost: 500_000 },
+                LockupTier { duration: 1_000, boost: 1_000_000 },
+            ];
+            let operation = AdminOperation::SetLockupTiers { tiers, early_exit_penalty: 100_000 };
+            pool.execute_admin_operation(operation, None).unwrap();
+        }
+
+        fn advance_blocks(blocks: BlockNumber) {
+            for _ in 0..blocks {
+                test::advance_block::<DefaultEnvironment>();
+            }
+        }
+
+        // Key of an attestation issuer derived from `seed`
+        fn issuer(seed: u8) -> (SecretKey, [u8; 33]) {
+            let secret = SecretKey::from_slice(&[seed; 32]).unwrap();
+            (secret, PublicKey::from_secret_key(SECP256K1, &secret).serialize())
+        }
+
+        // Evidence of `account` being human until `expires_at`, signed by `secret`
+        fn attestation(secret: &SecretKey, account: AccountId, expires_at: Timestamp) -> Vec<u8> {
+            let hash = AttestationIssuer::message_hash(&account, expires_at);
+            let message = Message::from_slice(&hash).unwrap();
+            let (recovery_id, compact) = SECP256K1.sign_ecdsa_recoverable(&message, secret).serialize_compact();
+            let mut signature = [0u8; 65];
+            signature[..64].copy_from_slice(&compact);
+            signature[64] = recovery_id.to_i32() as u8;
+            (expires_at, signature).encode()
+        }
+
+        type Event = <UnifiedLiquidityPool as ink::reflect::ContractEventBase>::Type;
+
+        fn last_event() -> Event {
+            let event = test::recorded_events().last().expect("no event emitted");
+            <Event as Decode>::decode(&mut &event.data[..]).expect("undecodable event")
+        }
+
+        #[ink::test]
+        fn new_lists_assets_in_order() {
+            let pool = pool();
+            assert_eq!(pool.get_owner(), accounts().alice);
+            assert_eq!(pool.get_rates(), (3_000, 100_000));
+            let ids: Vec<TokenId> = pool.get_assets().iter().map(|asset| asset.id).collect();
+            assert_eq!(ids, vec![NRSH, ELXR, IMRT]);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "invalid post-quantum public keys")]
+        fn new_rejects_malformed_keys() {
+            UnifiedLiquidityPool::new(3_000, 0, vec![0; 10], vec![0; DILITHIUM_PUBLIC_KEY_LEN], Vec::new(), 0);
+        }
+
+        #[ink::test]
+        fn registry_changes_are_owner_only() {
+            let mut pool = pool();
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(pool.list_asset(asset(3, b"DOT")), Err(Error::NotOwner));
+            assert_eq!(pool.set_asset_enabled(NRSH, false), Err(Error::NotOwner));
+
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            assert_eq!(pool.list_asset(asset(3, b"DOT")), Ok(()));
+            assert_eq!(pool.set_asset_enabled(NRSH, false), Ok(()));
+            assert_eq!(pool.get_asset(NRSH).map(|asset| asset.enabled), Some(false));
+        }
+
+        #[ink::test]
+        fn admin_operations_check_bounds() {
+            let mut pool = pool();
+            let too_high = AdminOperation::SetFeeRate { fee_rate: MAX_FEE_RATE + 1 };
+            assert_eq!(pool.execute_admin_operation(too_high, None), Err(Error::InvalidFeeRate));
+
+            let fee_rate = AdminOperation::SetFeeRate { fee_rate: MAX_FEE_RATE };
+            assert_eq!(pool.execute_admin_operation(fee_rate, None), Ok(()));
+            assert_eq!(pool.get_rates(), (MAX_FEE_RATE, 100_000));
+
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            let pause = AdminOperation::SetPaused { paused: true };
+            assert_eq!(pool.execute_admin_operation(pause, None), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn signed_admin_operations_are_bound_to_the_nonce() {
+            let keys = ml_dsa::SigningKey::<MlDsa65>::from_seed(&[7; 32].into());
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            let mut pool = UnifiedLiquidityPool::new(
+                3_000,
+                0,
+                vec![0; KYBER_PUBLICKEYBYTES],
+                ml_dsa::Keypair::verifying_key(&keys).encode().to_vec(),
+                Vec::new(),
+                0,
+            );
+            let operation = AdminOperation::SetPaused { paused: true };
+            let payload = (ADMIN_CONTEXT, test::callee::<DefaultEnvironment>(), 0u64, operation.clone()).encode();
+            let signature = ml_dsa::Signer::sign(&keys, &payload).encode().to_vec();
+
+            // Anyone can submit a signed operation, but only once
+            test::set_caller::<DefaultEnvironment>(accounts().eve);
+            assert_eq!(pool.execute_admin_operation(operation.clone(), Some(signature.clone())), Ok(()));
+            assert!(pool.is_paused());
+            assert_eq!(pool.get_admin_nonce(), 1);
+            assert_eq!(
+                pool.execute_admin_operation(operation, Some(signature)),
+                Err(Error::InvalidSignature)
+            );
+        }
+
+        #[ink::test]
+        fn key_rotation_waits_for_the_timelock() {
+            let mut pool = pool();
+            let rotation = AdminOperation::ProposeKeyRotation {
+                kyber_public_key: vec![1; KYBER_PUBLICKEYBYTES],
+                dilithium_public_key: vec![1; DILITHIUM_PUBLIC_KEY_LEN],
+            };
+            assert_eq!(pool.apply_key_rotation(), Err(Error::NoPendingKeyRotation));
+            assert_eq!(pool.execute_admin_operation(rotation, None), Ok(()));
+            assert_eq!(pool.apply_key_rotation(), Err(Error::TimelockActive));
+
+            test::set_block_timestamp::<DefaultEnvironment>(KEY_ROTATION_DELAY);
+            assert_eq!(pool.apply_key_rotation(), Ok(()));
+            assert_eq!(pool.get_public_keys().0, vec![1; KYBER_PUBLICKEYBYTES]);
+        }
+
+        #[ink::test]
+        fn deposits_require_a_verified_human() {
+            let mut pool = pool();
+            let bob = accounts().bob;
+            test::set_caller::<DefaultEnvironment>(bob);
+            assert_eq!(pool.add_liquidity(NRSH, 1_000), Err(Error::NotHuman));
+
+            test::set_caller::<DefaultEnvironment>(accounts().alice);
+            pool.set_human_allowed(bob, true).unwrap();
+            let pause = AdminOperation::SetPaused { paused: true };
+            pool.execute_admin_operation(pause, None).unwrap();
+
+            // Verification passes, so the pause is what rejects the deposit
+            test::set_caller::<DefaultEnvironment>(bob);
+            assert_eq!(pool.add_liquidity(NRSH, 1_000), Err(Error::Paused));
+            assert_eq!(pool.get_humanity_expiry(bob), Some(60_000));
+        }
+
+        #[ink::test]
+        fn attestations_verify_until_they_expire() {
+            let mut pool = pool();
+            let (bob, charlie) = (accounts().bob, accounts().charlie);
+            let (secret, issuer_key) = issuer(7);
+            let (forger, _) = issuer(8);
+            pool.set_humanity_verifiers(vec![issuer_key], Vec::new(), 60_000).unwrap();
+            test::set_block_timestamp::<DefaultEnvironment>(1_000);
+
+            // Expired, signed by another key, issued for another account or
+            // malformed
+            test::set_caller::<DefaultEnvironment>(bob);
+            for evidence in [
+                attestation(&secret, bob, 1_000),
+                attestation(&forger, bob, 30_000),
+                attestation(&secret, charlie, 30_000),
+                30_000u64.encode(),
+            ] {
+                assert_eq!(pool.verify_humanity(evidence), Err(Error::NotHuman));
+            }
+
+            assert_eq!(pool.verify_humanity(attestation(&secret, bob, 30_000)), Ok(30_000));
+            assert_eq!(pool.get_humanity_expiry(bob), Some(30_000));
+
+            // The cache holds a verification for at most `humanity_ttl`
+            test::set_caller::<DefaultEnvironment>(charlie);
+            assert_eq!(pool.verify_humanity(attestation(&secret, charlie, Timestamp::MAX)), Ok(61_000));
+
+            test::set_block_timestamp::<DefaultEnvironment>(30_000);
+            assert_eq!(pool.get_humanity_expiry(bob), None);
+            test::set_caller::<DefaultEnvironment>(bob);
+            assert_eq!(pool.add_liquidity(NRSH, 1_000), Err(Error::NotHuman));
+        }
+
+        #[ink::test]
+        fn registry_revocations_apply_once_the_cache_expires() {
+            let mut pool = pool();
+            let bob = accounts().bob;
+            let personhood = AccountId::from([0x20; 32]);
+            pool.set_humanity_verifiers(Vec::new(), vec![personhood], 60_000).unwrap();
+
+            test::set_caller::<DefaultEnvironment>(bob);
+            assert_eq!(pool.verify_humanity(Vec::new()), Err(Error::NotHuman));
+            registry::set_human(personhood, bob, true);
+            assert_eq!(pool.verify_humanity(Vec::new()), Ok(60_000));
+
+            registry::set_human(personhood, bob, false);
+            test::set_block_timestamp::<DefaultEnvironment>(59_999);
+            assert_eq!(pool.verify_humanity(Vec::new()), Ok(60_000));
+            test::set_block_timestamp::<DefaultEnvironment>(60_000);
+            assert_eq!(pool.verify_humanity(Vec::new()), Err(Error::NotHuman));
+            assert_eq!(pool.get_humanity_expiry(bob), None);
+        }
+
+        #[ink::test]
+        fn humanity_verifiers_are_owner_only() {
+            let mut pool = pool();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+            let (_, issuer_key) = issuer(7);
+
+            test::set_caller::<DefaultEnvironment>(bob);
+            assert_eq!(pool.set_humanity_verifiers(vec![issuer_key], Vec::new(), 0), Err(Error::NotOwner));
+            assert_eq!(pool.set_human_allowed(bob, true), Err(Error::NotOwner));
+            assert_eq!(pool.verify_humanity(Vec::new()), Err(Error::NotHuman));
+
+            // Removing an account from the allow list also drops its cached
+            // verification
+            test::set_caller::<DefaultEnvironment>(alice);
+            pool.set_human_allowed(bob, true).unwrap();
+            test::set_caller::<DefaultEnvironment>(bob);
+            assert_eq!(pool.verify_humanity(Vec::new()), Ok(60_000));
+            test::set_caller::<DefaultEnvironment>(alice);
+            pool.set_human_allowed(bob, false).unwrap();
+            assert_eq!(pool.get_humanity_expiry(bob), None);
+            test::set_caller::<DefaultEnvironment>(bob);
+            assert_eq!(pool.verify_humanity(Vec::new()), Err(Error::NotHuman));
+        }
+
+        #[ink::test]
+        fn shares_track_the_reserve() {
+            let mut pool = pool();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+            seed(&mut pool, alice, NRSH, 1_000);
+            assert_eq!(pool.get_shares(alice, NRSH), 1_000);
+
+            // Fees left in the reserve raise the value of every share
+            pool.update_reserves(NRSH, 1_000, true).unwrap();
+            seed(&mut pool, bob, NRSH, 1_000);
+            assert_eq!(pool.get_shares(bob, NRSH), 500);
+            assert_eq!(pool.calculate_withdrawal_amount(NRSH, 500), Ok(1_000));
+        }
+
+        #[ink::test]
+        fn quotes_follow_the_constant_product() {
+            let mut pool = pool();
+            let alice = accounts().alice;
+            assert_eq!(pool.get_quote(NRSH, ELXR, 1_000), Err(Error::InsufficientLiquidity));
+            assert_eq!(pool.get_quote(NRSH, NRSH, 1_000), Err(Error::IdenticalTokens));
+
+            seed(&mut pool, alice, NRSH, 1_000_000);
+            seed(&mut pool, alice, ELXR, 2_000_000);
+            // 10_000 in, 30 of it fee: 2_000_000 * 9_970 / 1_009_970
+            assert_eq!(pool.get_quote(NRSH, ELXR, 10_000), Ok(19_743));
+        }
+
+        #[ink::test]
+        fn swaps_split_the_fee_between_treasury_and_providers() {
+            let mut pool = pool();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+            deposit(&mut pool, alice, NRSH, 1_000_000);
+            deposit(&mut pool, alice, ELXR, 2_000_000);
+            psp22::mint(token(NRSH), bob, 10_000);
+            test::set_caller::<DefaultEnvironment>(bob);
+
+            assert_eq!(pool.swap(NRSH, ELXR, 10_000, 19_744), Err(Error::SlippageExceeded));
+            assert_eq!(pool.swap(NRSH, ELXR, 10_000, 19_743), Ok(19_743));
+            assert_eq!((balance_of(NRSH, bob), balance_of(ELXR, bob)), (0, 19_743));
+
+            // Of the 30 fee, 3 go to the treasury and 27 stay in the reserve
+            // for the providers
+            assert_eq!(pool.get_treasury(NRSH), 3);
+            assert_eq!(pool.get_reserve(NRSH), 1_009_997);
+            assert_eq!(pool.get_reserve(ELXR), 1_980_257);
+            assert_eq!(pool.calculate_withdrawal_amount(NRSH, 1_000_000), Ok(1_009_997));
+
+            let Event::Swapped(swapped) = last_event() else {
+                panic!("expected Swapped");
+            };
+            assert_eq!(
+                (swapped.trader, swapped.token_in, swapped.token_out, swapped.amount_in, swapped.amount_out),
+                (bob, NRSH, ELXR, 10_000, 19_743)
+            );
+            assert_eq!((swapped.fee, swapped.treasury_fee), (30, 3));
+        }
+
+        #[ink::test]
+        fn zaps_into_an_unbalanced_pool() {
+            let mut pool = pool();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+            deposit(&mut pool, alice, NRSH, 1_000_000);
+            deposit(&mut pool, alice, ELXR, 4_000_000);
+            allow_human(&mut pool, bob);
+            psp22::mint(token(NRSH), bob, 100_000);
+
+            assert_eq!(pool.zap_in(NRSH, ELXR, 100_000, 185_885), Err(Error::SlippageExceeded));
+            assert_eq!(pool.zap_in(NRSH, ELXR, 100_000, 185_884), Ok((48_736, 194_943)));
+            assert_eq!((pool.get_shares(bob, NRSH), pool.get_shares(bob, ELXR)), (48_736, 194_943));
+            assert_eq!(balance_of(NRSH, bob), 0);
+
+            // 48_882 NRSH buy 185_884 ELXR, paying a 146 fee of which 14 go
+            // to the treasury; both halves of the zap stay in the pools
+            assert_eq!(pool.get_treasury(NRSH), 14);
+            assert_eq!((pool.get_reserve(NRSH), pool.get_reserve(ELXR)), (1_099_986, 4_000_000));
+
+            let Event::ZappedIn(zapped) = last_event() else {
+                panic!("expected ZappedIn");
+            };
+            assert_eq!(
+                (zapped.provider, zapped.token_in, zapped.token_out, zapped.amount_in),
+                (bob, NRSH, ELXR, 100_000)
+            );
+            assert_eq!((zapped.swap_in, zapped.swap_out), (48_882, 185_884));
+            assert_eq!(zapped.effective_price, 3_802_708_563_479);
+            assert_eq!((zapped.shares_in, zapped.shares_out), (48_736, 194_943));
+        }
+
+        #[test]
+        fn zaps_leave_at_most_a_unit_of_dust() {
+            // Reserves, zapped amount and whether the input shares are locked,
+            // boosting a part of the fee out of the reserve
+            let cases = [
+                (1_000_000, 4_000_000, 100_000, false),
+                (4_000_000, 1_000_000, 100_000, false),
+                (5_000_000, 1_000_000, 777_777, false),
+                (1_000_000, 1_000_000, 1_000_000, false),
+                (1_000_000, 4_000_000, 100_000, true),
+                (1_000_000, 1_000_000, 1_000_000, true),
+            ];
+            // Each zap runs against a fresh off-chain environment
+            for (reserve_in, reserve_out, amount, locked) in cases {
+                test::run_test::<DefaultEnvironment, _>(|_| {
+                    let mut pool = pool();
+                    let (alice, bob) = (accounts().alice, accounts().bob);
+                    deposit(&mut pool, alice, ELXR, reserve_out);
+                    if locked {
+                        set_lockup_tiers(&mut pool);
+                    }
+                    deposit(&mut pool, alice, NRSH, reserve_in);
+                    if locked {
+                        pool.lock_liquidity(NRSH, reserve_in, 1).unwrap();
+                    }
+                    allow_human(&mut pool, bob);
+                    psp22::mint(token(NRSH), bob, amount);
+                    pool.zap_in(NRSH, ELXR, amount, 0).unwrap();
+
+                    // Against the post-swap reserves, the rest and the output
+                    // are off by `dust` over the other token's reserve in units
+                    // of either token; allow one unit of the dearer token
+                    let Event::ZappedIn(zapped) = last_event() else {
+                        panic!("expected ZappedIn");
+                    };
+                    let (rest, out) = (amount - zapped.swap_in, zapped.swap_out);
+                    let reserve_in = pool.get_reserve(NRSH) - rest;
+                    let reserve_out = pool.get_reserve(ELXR) - out;
+                    let dust = (rest * reserve_out).abs_diff(out * reserve_in);
+                    assert!(dust <= reserve_in.max(reserve_out), "zap of {amount}: dust {dust}");
+                    Ok(())
+                }).unwrap();
+            }
+        }
+
+        #[test]
+        fn mul_div_widens_the_product() {
+            assert_eq!(mul_div(7, 3, 2), Ok(10));
+            assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX), Ok(u128::MAX));
+            assert_eq!(mul_div(u128::MAX, 6, 3), Err(Error::ArithmeticError));
+            assert_eq!(mul_div(u128::MAX, 3, 6), Ok(u128::MAX / 2));
+            // 2^127 * 10^12 / 10^18, computed past 128 bits
+            assert_eq!(mul_div(1 << 127, 1_000_000_000_000, 1_000_000_000_000_000_000), Ok((1 << 127) / 1_000_000));
+            assert_eq!(mul_div(u128::MAX - 1, u128::MAX - 2, u128::MAX), Ok(u128::MAX - 3));
+            assert_eq!(mul_div(1, 1, 0), Err(Error::ArithmeticError));
+        }
+
+        #[ink::test]
+        fn swaps_reject_empty_and_circular_trades() {
+            let mut pool = pool();
+            let alice = accounts().alice;
+            deposit(&mut pool, alice, NRSH, 1_000_000);
+            deposit(&mut pool, alice, ELXR, 2_000_000);
+
+            assert_eq!(pool.swap(NRSH, ELXR, 0, 0), Err(Error::ZeroAmount));
+            assert_eq!(pool.swap(NRSH, NRSH, 10_000, 0), Err(Error::IdenticalTokens));
+            assert_eq!(pool.swap(NRSH, 3, 10_000, 0), Err(Error::UnknownAsset));
+            assert_eq!((pool.get_reserve(NRSH), pool.get_treasury(NRSH)), (1_000_000, 0));
+        }
+
+        #[ink::test]
+        fn lockup_tiers_are_bounded() {
+            let mut pool = pool();
+            let alice = accounts().alice;
+            deposit(&mut pool, alice, NRSH, 1_000);
+            assert_eq!(pool.lock_liquidity(NRSH, 600, 0), Err(Error::UnknownLockupTier));
+
+            let tier = |duration, boost| LockupTier { duration, boost };
+            let tiers = |tiers: &[LockupTier], early_exit_penalty| {
+                AdminOperation::SetLockupTiers { tiers: tiers.to_vec(), early_exit_penalty }
+            };
+            test::set_caller::<DefaultEnvironment>(accounts().bob);
+            assert_eq!(pool.execute_admin_operation(tiers(&[], 0), None), Err(Error::NotOwner));
+            test::set_caller::<DefaultEnvironment>(alice);
+
+            let too_many: Vec<LockupTier> = (1..=MAX_LOCKUP_TIERS as BlockNumber + 1)
+                .map(|duration| tier(duration, 0))
+                .collect();
+            let rejected = [
+                tiers(&[], RATE_DENOMINATOR + 1),
+                tiers(&[tier(0, 500_000)], 0),
+                tiers(&[tier(100, MAX_LOCKUP_BOOST + 1)], 0),
+                tiers(&[tier(1_000, 1_000_000), tier(100, 500_000)], 0),
+                tiers(&[tier(100, 500_000), tier(100, 1_000_000)], 0),
+                tiers(&too_many, 0),
+            ];
+            for operation in rejected {
+                assert_eq!(pool.execute_admin_operation(operation, None), Err(Error::InvalidLockupTiers));
+            }
+            assert_eq!(pool.get_lockup_tiers(), (Vec::new(), 0));
+
+            // The bounds themselves are allowed
+            let most = &too_many[..MAX_LOCKUP_TIERS];
+            assert_eq!(pool.execute_admin_operation(tiers(most, RATE_DENOMINATOR), None), Ok(()));
+            assert_eq!(pool.execute_admin_operation(tiers(&[tier(1, MAX_LOCKUP_BOOST)], 0), None), Ok(()));
+
+            set_lockup_tiers(&mut pool);
+            assert_eq!(pool.get_lockup_tiers().1, 100_000);
+            assert_eq!(pool.lock_liquidity(NRSH, 600, 2), Err(Error::UnknownLockupTier));
+            assert_eq!(pool.lock_liquidity(NRSH, 0, 0), Err(Error::ZeroAmount));
+            assert_eq!(pool.lock_liquidity(NRSH, 1_001, 0), Err(Error::InsufficientShares));
+            assert_eq!(pool.lock_liquidity(NRSH, 600, 0), Ok(100));
+            assert_eq!(pool.lock_liquidity(NRSH, 400, 1), Err(Error::LockExists));
+            assert_eq!(pool.get_lock(alice, NRSH).map(|lock| lock.boost_weight), Some(300));
+        }
+
+        #[ink::test]
+        fn locked_shares_are_removed_only_after_the_lock() {
+            let mut pool = pool();
+            let alice = accounts().alice;
+            set_lockup_tiers(&mut pool);
+            deposit(&mut pool, alice, NRSH, 1_000);
+            pool.lock_liquidity(NRSH, 600, 0).unwrap();
+
+            assert_eq!(pool.remove_liquidity(NRSH, 500), Err(Error::LiquidityLocked));
+            assert_eq!(pool.remove_liquidity(NRSH, 400), Ok(400));
+
+            // Removing locked shares after the lock releases it
+            advance_blocks(100);
+            assert_eq!(pool.remove_liquidity(NRSH, 600), Ok(600));
+            assert_eq!(pool.get_lock(alice, NRSH), None);
+            assert_eq!(balance_of(NRSH, alice), 1_000);
+        }
+
+        #[ink::test]
+        fn leaving_a_lock_early_pays_the_penalty_to_the_treasury() {
+            let mut pool = pool();
+            let alice = accounts().alice;
+            set_lockup_tiers(&mut pool);
+            deposit(&mut pool, alice, NRSH, 1_000);
+            pool.lock_liquidity(NRSH, 600, 0).unwrap();
+
+            advance_blocks(99);
+            assert_eq!(pool.unlock_liquidity(NRSH), Ok(0));
+            let Event::LiquidityUnlocked(unlocked) = last_event() else {
+                panic!("expected LiquidityUnlocked");
+            };
+            assert_eq!((unlocked.boost_fees, unlocked.penalty_shares), (0, 60));
+
+            // A tenth of the 600 locked shares is burnt and its value moves
+            // from the reserve to the treasury
+            assert_eq!(pool.get_shares(alice, NRSH), 940);
+            assert_eq!((pool.get_total_shares(NRSH), pool.get_reserve(NRSH)), (940, 940));
+            assert_eq!(pool.get_treasury(NRSH), 60);
+            assert_eq!(pool.get_lock(alice, NRSH), None);
+            assert_eq!(pool.remove_liquidity(NRSH, 940), Ok(940));
+        }
+
+        #[ink::test]
+        fn unlocking_after_the_lock_keeps_every_share() {
+            let mut pool = pool();
+            let alice = accounts().alice;
+            set_lockup_tiers(&mut pool);
+            deposit(&mut pool, alice, NRSH, 1_000);
+            assert_eq!(pool.lock_liquidity(NRSH, 600, 1), Ok(1_000));
+
+            advance_blocks(1_000);
+            assert_eq!(pool.unlock_liquidity(NRSH), Ok(0));
+            assert_eq!((pool.get_shares(alice, NRSH), pool.get_treasury(NRSH)), (1_000, 0));
+            assert_eq!(pool.unlock_liquidity(NRSH), Err(Error::NoLock));
+        }
+
+        #[ink::test]
+        fn locked_shares_earn_boosted_fees() {
+            let mut pool = pool();
+            let (alice, bob, charlie) = (accounts().alice, accounts().bob, accounts().charlie);
+            set_lockup_tiers(&mut pool);
+            deposit(&mut pool, bob, NRSH, 1_000_000);
+            deposit(&mut pool, alice, ELXR, 2_000_000);
+            deposit(&mut pool, alice, NRSH, 1_000_000);
+            pool.lock_liquidity(NRSH, 1_000_000, 1).unwrap();
+
+            // Of the 270 providers' fee, the lock's 1_000_000 boost weight
+            // earns a third on top of the 2_000_000 shares
+            psp22::mint(token(NRSH), charlie, 200_000);
+            test::set_caller::<DefaultEnvironment>(charlie);
+            pool.swap(NRSH, ELXR, 100_000, 0).unwrap();
+            assert_eq!(pool.get_reserve(NRSH), 2_099_880);
+            assert_eq!(pool.get_pending_boost_fees(alice, NRSH), 90);
+            assert_eq!(pool.get_pending_boost_fees(bob, NRSH), 0);
+
+            // Locked and unlocked shares keep the same value in the reserve
+            assert_eq!(pool.calculate_withdrawal_amount(NRSH, 1_000_000), Ok(1_049_940));
+
+            test::set_caller::<DefaultEnvironment>(alice);
+            assert_eq!(pool.claim_boost_fees(NRSH), Ok(90));
+            assert_eq!(balance_of(NRSH, alice), 90);
+            assert_eq!(pool.claim_boost_fees(NRSH), Ok(0));
+            test::set_caller::<DefaultEnvironment>(bob);
+            assert_eq!(pool.claim_boost_fees(NRSH), Err(Error::NoLock));
+
+            // Fees accrued since the claim are paid when the lock ends
+            test::set_caller::<DefaultEnvironment>(charlie);
+            pool.swap(NRSH, ELXR, 100_000, 0).unwrap();
+            advance_blocks(1_000);
+            test::set_caller::<DefaultEnvironment>(alice);
+            assert_eq!(pool.unlock_liquidity(NRSH), Ok(90));
+            assert_eq!(balance_of(NRSH, alice), 180);
+        }
+
+        #[ink::test]
+        fn provider_data_is_bounded() {
+            let mut pool = pool();
+            let data = EncryptedData { ciphertext: vec![7; MAX_PROVIDER_DATA_LEN + 1], nonce: [0; 24] };
+            assert_eq!(pool.set_provider_data(data), Err(Error::ProviderDataTooLarge));
+
+            let data = EncryptedData { ciphertext: vec![7; 64], nonce: [1; 24] };
+            assert_eq!(pool.set_provider_data(data.clone()), Ok(()));
+            assert_eq!(pool.get_provider_data(accounts().alice), Some(data));
+            pool.erase_provider_data();
+            assert_eq!(pool.get_provider_data(accounts().alice), None);
+        }
+
+        #[ink::test]
+        fn deposits_and_withdrawals_move_tokens() {
+            let mut pool = pool();
+            let (alice, contract) = (accounts().alice, test::callee::<DefaultEnvironment>());
+            assert_eq!(deposit(&mut pool, alice, NRSH, 1_000), 1_000);
+            assert_eq!((balance_of(NRSH, alice), balance_of(NRSH, contract)), (0, 1_000));
+
+            assert_eq!(pool.remove_liquidity(NRSH, 400), Ok(400));
+            assert_eq!((balance_of(NRSH, alice), balance_of(NRSH, contract)), (400, 600));
+            assert_eq!((pool.get_shares(alice, NRSH), pool.get_reserve(NRSH)), (600, 600));
+        }
+
+        #[ink::test]
+        fn failed_token_transfers_credit_nothing() {
+            let mut pool = pool();
+            let (alice, bob) = (accounts().alice, accounts().bob);
+            deposit(&mut pool, alice, NRSH, 1_000_000);
+            deposit(&mut pool, alice, ELXR, 2_000_000);
+
+            // Bob holds no tokens, so the token contract rejects pulling them
+            // before the pool books anything
+            allow_human(&mut pool, bob);
+            assert_eq!(pool.add_liquidity(NRSH, 1_000), Err(Error::TransferFailed));
+            assert_eq!(pool.swap(NRSH, ELXR, 10_000, 0), Err(Error::TransferFailed));
+            assert_eq!(pool.zap_in(NRSH, ELXR, 10_000, 0), Err(Error::TransferFailed));
+            assert_eq!(pool.get_shares(bob, NRSH), 0);
+            assert_eq!((pool.get_reserve(NRSH), pool.get_reserve(ELXR)), (1_000_000, 2_000_000));
+            assert_eq!((pool.get_total_shares(NRSH), pool.get_treasury(NRSH)), (1_000_000, 0));
+        }
+
+        #[ink::test]
+        fn failed_payouts_fail_the_withdrawal() {
+            let mut pool = pool();
+            let alice = accounts().alice;
+            deposit(&mut pool, alice, NRSH, 1_000);
+
+            // The shares are burnt before paying out; returning the error
+            // makes the runtime revert the message, burn included (see
+            // `e2e_tests::failed_messages_revert`)
+            psp22::set_failing(true);
+            assert_eq!(pool.remove_liquidity(NRSH, 400), Err(Error::TransferFailed));
+            assert_eq!(balance_of(NRSH, alice), 0);
         }
     }
-    
-    pub fn add_liquidity(&self, 
-                         token_a: &[u8], 
-                         amount_a: u128, 
-                         token_b: &[u8], 
-                         amount_b: u128) -> Result<u128, &'static str> {
-        // Implementation for adding liquidity
-        Ok(0)
-    }
-    
-    pub fn remove_liquidity(&self,
-                           token_a: &[u8],
-                           token_b: &[u8],
-                           lp_amount: u128) -> Result<(u128, u128), &'static str> {
-        // Implementation for removing liquidity
-        Ok((0, 0))
-    }
-    
-    pub fn swap(&self,
-               token_in: &[u8],
-               amount_in: u128,
-               token_out: &[u8]) -> Result<u128, &'static str> {
-        // Implementation for token swap
-        Ok(0)
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink_e2e::build_message;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        macro_rules! constructor {
+            () => {
+                UnifiedLiquidityPoolRef::new(
+                    3_000,
+                    100_000,
+                    vec![0; KYBER_PUBLICKEYBYTES],
+                    vec![0; DILITHIUM_PUBLIC_KEY_LEN],
+                    Vec::new(),
+                    60_000,
+                )
+            };
+        }
+
+        #[ink_e2e::test]
+        async fn owner_manages_the_registry(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let pool = client
+                .instantiate("matrix_magiq_unified_liquidity_pool", &ink_e2e::alice(), constructor!(), 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let asset = AssetInfo {
+                id: NRSH,
+                symbol: b"NRSH".to_vec(),
+                decimals: 12,
+                contract: AccountId::from([0x42; 32]),
+                enabled: true,
+            };
+
+            let list = build_message::<UnifiedLiquidityPoolRef>(pool.clone())
+                .call(|pool| pool.list_asset(asset.clone()));
+            let rejected = client.call_dry_run(&ink_e2e::bob(), &list, 0, None).await;
+            assert_eq!(rejected.return_value(), Err(Error::NotOwner));
+            client.call(&ink_e2e::alice(), list, 0, None).await.expect("list_asset failed");
+
+            let get = build_message::<UnifiedLiquidityPoolRef>(pool.clone())
+                .call(|pool| pool.get_asset(NRSH));
+            let listed = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
+            assert_eq!(listed.return_value(), Some(asset));
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn failed_messages_revert(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let pool = client
+                .instantiate("matrix_magiq_unified_liquidity_pool", &ink_e2e::alice(), constructor!(), 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+
+            let allow = build_message::<UnifiedLiquidityPoolRef>(pool.clone())
+                .call(|pool| pool.set_human_allowed(bob, true));
+            client.call(&ink_e2e::alice(), allow, 0, None).await.expect("set_human_allowed failed");
+
+            // The deposit verifies Bob before failing on the unknown asset;
+            // the cached verification is reverted with it
+            let deposit = build_message::<UnifiedLiquidityPoolRef>(pool.clone())
+                .call(|pool| pool.add_liquidity(NRSH, 1_000));
+            assert!(client.call(&ink_e2e::bob(), deposit, 0, None).await.is_err());
+
+            let expiry = build_message::<UnifiedLiquidityPoolRef>(pool.clone())
+                .call(|pool| pool.get_humanity_expiry(bob));
+            let expiry = client.call_dry_run(&ink_e2e::bob(), &expiry, 0, None).await;
+            assert_eq!(expiry.return_value(), None);
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn deposits_call_the_token_contract(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let pool = client
+                .instantiate("matrix_magiq_unified_liquidity_pool", &ink_e2e::alice(), constructor!(), 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+
+            // The asset's PSP22 contract is an account without code
+            let asset = AssetInfo {
+                id: NRSH,
+                symbol: b"NRSH".to_vec(),
+                decimals: 12,
+                contract: AccountId::from([0x42; 32]),
+                enabled: true,
+            };
+            let list = build_message::<UnifiedLiquidityPoolRef>(pool.clone())
+                .call(|pool| pool.list_asset(asset));
+            client.call(&ink_e2e::alice(), list, 0, None).await.expect("list_asset failed");
+            let allow = build_message::<UnifiedLiquidityPoolRef>(pool.clone())
+                .call(|pool| pool.set_human_allowed(bob, true));
+            client.call(&ink_e2e::alice(), allow, 0, None).await.expect("set_human_allowed failed");
+
+            let deposit = build_message::<UnifiedLiquidityPoolRef>(pool.clone())
+                .call(|pool| pool.add_liquidity(NRSH, 1_000));
+            let deposit = client.call_dry_run(&ink_e2e::bob(), &deposit, 0, None).await;
+            assert_eq!(deposit.return_value(), Err(Error::TokenCallFailed));
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn unreachable_registries_do_not_verify(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let pool = client
+                .instantiate("matrix_magiq_unified_liquidity_pool", &ink_e2e::alice(), constructor!(), 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // The registry is an account without code: the failed call
+            // counts as no verdict rather than trapping the deposit
+            let verifiers = build_message::<UnifiedLiquidityPoolRef>(pool.clone())
+                .call(|pool| pool.set_humanity_verifiers(Vec::new(), vec![AccountId::from([0x43; 32])], 60_000));
+            client.call(&ink_e2e::alice(), verifiers, 0, None).await.expect("set_humanity_verifiers failed");
+
+            let deposit = build_message::<UnifiedLiquidityPoolRef>(pool.clone())
+                .call(|pool| pool.add_liquidity(NRSH, 1_000));
+            let deposit = client.call_dry_run(&ink_e2e::bob(), &deposit, 0, None).await;
+            assert_eq!(deposit.return_value(), Err(Error::NotHuman));
+            Ok(())
+        }
     }
 }
 
 // Error correction integrations
-mod error_correction {
+pub mod error_correction {
     // Classical error correction
     pub mod classical {
+        use ink::prelude::vec::Vec;
+
         pub fn correct_errors(data: &[u8]) -> Vec<u8> {
             // Reed-Solomon implementation
             data.to_vec()
@@ -268,6 +2346,8 @@ mod error_correction {
     
     // Bridge error correction
     pub mod bridge {
+        use ink::prelude::vec::Vec;
+
         pub fn correct_interface_errors(data: &[u8]) -> Vec<u8> {
             // Bridge protocol implementation
             data.to_vec()
@@ -276,6 +2356,8 @@ mod error_correction {
     
     // Quantum error correction
     pub mod quantum {
+        use ink::prelude::vec::Vec;
+
         pub fn correct_quantum_errors(data: &[u8]) -> Vec<u8> {
             // Surface code implementation
             data.to_vec()
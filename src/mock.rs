@@ -0,0 +1,98 @@
+// Off-chain stand-ins for the contracts the UnifiedLiquidityPool calls
+//
+// Unit tests run in ink!'s off-chain environment, which cannot call other
+// contracts. The pool is built against these instead; the calls themselves
+// are covered by the e2e tests.
+
+use ink::env::DefaultEnvironment;
+use ink::primitives::AccountId;
+
+use crate::humanity::ProofOfPersonhood;
+use crate::psp22::{Balance, TokenTransport, TransferError};
+
+// PSP22 balances of every token contract
+pub mod psp22 {
+    use super::*;
+    use std::{cell::RefCell, collections::BTreeMap};
+
+    thread_local! {
+        static BALANCES: RefCell<BTreeMap<(AccountId, AccountId), Balance>> = RefCell::default();
+        static FAILING: RefCell<bool> = RefCell::default();
+    }
+
+    pub fn transfer(token: AccountId, from: AccountId, to: AccountId, amount: Balance) -> Result<(), TransferError> {
+        if FAILING.with(|failing| *failing.borrow()) {
+            return Err(TransferError::Rejected);
+        }
+        BALANCES.with(|balances| {
+            let mut balances = balances.borrow_mut();
+            let from_balance = balances.get(&(token, from)).copied().unwrap_or(0)
+                .checked_sub(amount)
+                .ok_or(TransferError::Rejected)?;
+            balances.insert((token, from), from_balance);
+            *balances.entry((token, to)).or_insert(0) += amount;
+            Ok(())
+        })
+    }
+
+    pub fn mint(token: AccountId, to: AccountId, amount: Balance) {
+        BALANCES.with(|balances| *balances.borrow_mut().entry((token, to)).or_insert(0) += amount);
+    }
+
+    pub fn balance_of(token: AccountId, owner: AccountId) -> Balance {
+        BALANCES.with(|balances| balances.borrow().get(&(token, owner)).copied().unwrap_or(0))
+    }
+
+    // Make every transfer fail, as a token contract rejecting it would
+    pub fn set_failing(failing: bool) {
+        FAILING.with(|flag| *flag.borrow_mut() = failing);
+    }
+}
+
+// Token transfers booked on the `psp22` ledger, paying from the contract
+// under test
+pub struct Tokens;
+
+impl TokenTransport for Tokens {
+    fn transfer_from(token: AccountId, from: AccountId, to: AccountId, amount: Balance) -> Result<(), TransferError> {
+        psp22::transfer(token, from, to, amount)
+    }
+
+    fn transfer(token: AccountId, to: AccountId, amount: Balance) -> Result<(), TransferError> {
+        psp22::transfer(token, ink::env::account_id::<DefaultEnvironment>(), to, amount)
+    }
+}
+
+// Humans listed by every registry contract
+pub mod registry {
+    use super::*;
+    use std::{cell::RefCell, collections::BTreeSet};
+
+    thread_local! {
+        static HUMANS: RefCell<BTreeSet<(AccountId, AccountId)>> = RefCell::default();
+    }
+
+    pub fn set_human(registry: AccountId, account: AccountId, human: bool) {
+        HUMANS.with(|humans| {
+            let mut humans = humans.borrow_mut();
+            if human {
+                humans.insert((registry, account));
+            } else {
+                humans.remove(&(registry, account));
+            }
+        });
+    }
+
+    pub fn is_human(registry: AccountId, account: &AccountId) -> bool {
+        HUMANS.with(|humans| humans.borrow().contains(&(registry, *account)))
+    }
+}
+
+// Registries answering from the `registry` entries
+pub struct Registries;
+
+impl ProofOfPersonhood for Registries {
+    fn is_human(registry: AccountId, account: &AccountId) -> Option<bool> {
+        Some(registry::is_human(registry, account))
+    }
+}
@@ -1,64 +1,178 @@
 // liquidity_pallet.rs
 // Unified liquidity solution across all chains
+//
+// In-memory model of the asset registry and reserves of the liquidity
+// pallet, used off-chain
+
+use std::collections::BTreeMap;
+
+// Registry ID of a listed asset
+pub type AssetId = u32;
+
+// Account allowed to manage the asset registry
+pub type GovernanceId = [u8; 32];
+
+// IDs of the ecosystem's native tokens
+pub const NRSH: AssetId = 0;
+pub const ELXR: AssetId = 1;
+pub const IMRT: AssetId = 2;
+
+// Registry entry of a listed asset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetInfo {
+    pub id: AssetId,
+    pub symbol: Vec<u8>,
+    pub decimals: u8,
+    // Contract or on-chain asset backing the token
+    pub address: Vec<u8>,
+    // Disabled assets can only be withdrawn
+    pub enabled: bool,
+}
 
 pub struct LiquidityPool {
-    nrsh_balance: u128,
-    elxr_balance: u128,
-    imrt_balance: u128,
+    governance: GovernanceId,
+    assets: BTreeMap<AssetId, AssetInfo>,
+    balances: BTreeMap<AssetId, u128>,
 }
 
 impl LiquidityPool {
-    pub fn new() -> Self {
+    pub fn new(governance: GovernanceId) -> Self {
         LiquidityPool {
-            nrsh_balance: 0,
-            elxr_balance: 0,
-            imrt_balance: 0,
+            governance,
+            assets: BTreeMap::new(),
+            balances: BTreeMap::new(),
         }
     }
-    
-    pub fn add_liquidity(&mut self, token_type: TokenType, amount: u128) -> Result<(), &'static str> {
-        match token_type {
-            TokenType::NRSH => self.nrsh_balance += amount,
-            TokenType::ELXR => self.elxr_balance += amount,
-            TokenType::IMRT => self.imrt_balance += amount,
+
+    pub fn list_asset(&mut self, origin: &GovernanceId, asset: AssetInfo) -> Result<(), &'static str> {
+        self.ensure_governance(origin)?;
+        if self.assets.contains_key(&asset.id) {
+            return Err("Asset already listed");
         }
-        
+        self.assets.insert(asset.id, asset);
+
+        Ok(())
+    }
+
+    pub fn set_asset_enabled(&mut self, origin: &GovernanceId, asset_id: AssetId, enabled: bool) -> Result<(), &'static str> {
+        self.ensure_governance(origin)?;
+        let asset = self.assets.get_mut(&asset_id).ok_or("Unknown asset")?;
+        asset.enabled = enabled;
+
         Ok(())
     }
-    
-    pub fn remove_liquidity(&mut self, token_type: TokenType, amount: u128) -> Result<(), &'static str> {
-        match token_type {
-            TokenType::NRSH => {
-                if self.nrsh_balance < amount {
-                    return Err("Insufficient balance");
-                }
-                self.nrsh_balance -= amount;
-            },
-            TokenType::ELXR => {
-                if self.elxr_balance < amount {
-                    return Err("Insufficient balance");
-                }
-                self.elxr_balance -= amount;
-            },
-            TokenType::IMRT => {
-                if self.imrt_balance < amount {
-                    return Err("Insufficient balance");
-                }
-                self.imrt_balance -= amount;
-            },
+
+    pub fn asset(&self, asset_id: AssetId) -> Option<&AssetInfo> {
+        self.assets.get(&asset_id)
+    }
+
+    pub fn assets(&self) -> impl Iterator<Item = &AssetInfo> {
+        self.assets.values()
+    }
+
+    pub fn balance(&self, asset_id: AssetId) -> u128 {
+        self.balances.get(&asset_id).copied().unwrap_or(0)
+    }
+
+    pub fn add_liquidity(&mut self, asset_id: AssetId, amount: u128) -> Result<(), &'static str> {
+        self.ensure_enabled(asset_id)?;
+        let balance = self.balances.entry(asset_id).or_insert(0);
+        *balance = balance.checked_add(amount).ok_or("Balance overflow")?;
+
+        Ok(())
+    }
+
+    pub fn remove_liquidity(&mut self, asset_id: AssetId, amount: u128) -> Result<(), &'static str> {
+        if !self.assets.contains_key(&asset_id) {
+            return Err("Unknown asset");
         }
-        
+        let balance = self.balances.get_mut(&asset_id)
+            .filter(|balance| **balance >= amount)
+            .ok_or("Insufficient balance")?;
+        *balance -= amount;
+
         Ok(())
     }
-    
-    pub fn swap(&mut self, from_token: TokenType, to_token: TokenType, amount: u128) -> Result<u128, &'static str> {
+
+    pub fn swap(&mut self, from_asset: AssetId, to_asset: AssetId, amount: u128) -> Result<u128, &'static str> {
+        self.ensure_enabled(from_asset)?;
+        self.ensure_enabled(to_asset)?;
         // Implementation would perform the swap with proper pricing
         Ok(amount) // Simplified return
     }
+
+    fn ensure_governance(&self, origin: &GovernanceId) -> Result<(), &'static str> {
+        if *origin != self.governance {
+            return Err("Not governance");
+        }
+        Ok(())
+    }
+
+    fn ensure_enabled(&self, asset_id: AssetId) -> Result<(), &'static str> {
+        match self.assets.get(&asset_id) {
+            Some(asset) if asset.enabled => Ok(()),
+            Some(_) => Err("Asset disabled"),
+            None => Err("Unknown asset"),
+        }
+    }
 }
 
-pub enum TokenType {
-    NRSH,
-    ELXR,
-    IMRT,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOVERNANCE: GovernanceId = [1; 32];
+
+    fn asset(id: AssetId) -> AssetInfo {
+        AssetInfo { id, symbol: Vec::new(), decimals: 12, address: Vec::new(), enabled: true }
+    }
+
+    // Pool listing the native tokens, holding `nrsh` and `elxr`
+    fn pool(nrsh: u128, elxr: u128) -> LiquidityPool {
+        let mut pool = LiquidityPool::new(GOVERNANCE);
+        for id in [NRSH, ELXR, IMRT] {
+            pool.list_asset(&GOVERNANCE, asset(id)).unwrap();
+        }
+        pool.add_liquidity(NRSH, nrsh).unwrap();
+        pool.add_liquidity(ELXR, elxr).unwrap();
+        pool
+    }
+
+    #[test]
+    fn registry_changes_are_governance_only() {
+        let mut pool = pool(0, 0);
+        assert_eq!(pool.list_asset(&[2; 32], asset(3)), Err("Not governance"));
+        assert_eq!(pool.list_asset(&GOVERNANCE, asset(NRSH)), Err("Asset already listed"));
+        assert_eq!(pool.set_asset_enabled(&GOVERNANCE, 3, false), Err("Unknown asset"));
+
+        assert_eq!(pool.set_asset_enabled(&GOVERNANCE, NRSH, false), Ok(()));
+        assert_eq!(pool.add_liquidity(NRSH, 1), Err("Asset disabled"));
+        let ids: Vec<AssetId> = pool.assets().map(|asset| asset.id).collect();
+        assert_eq!(ids, vec![NRSH, ELXR, IMRT]);
+    }
+
+    #[test]
+    fn remove_liquidity_needs_a_balance() {
+        let mut pool = pool(1_000, 0);
+        assert_eq!(pool.remove_liquidity(NRSH, 1_001), Err("Insufficient balance"));
+        assert_eq!(pool.remove_liquidity(NRSH, 400), Ok(()));
+        assert_eq!(pool.balance(NRSH), 600);
+
+        // Nothing is recorded for assets that never held liquidity
+        assert_eq!(pool.remove_liquidity(IMRT, 1), Err("Insufficient balance"));
+        assert!(!pool.balances.contains_key(&IMRT));
+        assert_eq!(pool.remove_liquidity(3, 0), Err("Unknown asset"));
+    }
+
+    #[test]
+    fn swaps_need_listed_enabled_assets() {
+        let mut pool = pool(1_000, 1_000);
+        assert_eq!(pool.swap(NRSH, 3, 100), Err("Unknown asset"));
+        assert_eq!(pool.swap(3, NRSH, 100), Err("Unknown asset"));
+
+        assert_eq!(pool.set_asset_enabled(&GOVERNANCE, ELXR, false), Ok(()));
+        assert_eq!(pool.swap(NRSH, ELXR, 100), Err("Asset disabled"));
+        assert_eq!(pool.set_asset_enabled(&GOVERNANCE, ELXR, true), Ok(()));
+        assert_eq!(pool.swap(NRSH, ELXR, 100), Ok(100));
+    }
 }
@@ -1,201 +0,0 @@
-#![cfg_attr(not(feature = "std"), no_std)]
-use ink_lang as ink;
-use ink_storage::{
-    traits::SpreadAllocate,
-    Mapping,
-};
-use pqc_kyber::*;
-use pqc_dilithium::*;
-use scale::{Decode, Encode};
-
-#[ink::contract]
-mod unified_liquidity_pool {
-    #[ink(storage)]
-    #[derive(SpreadAllocate)]
-    pub struct UnifiedLiquidityPool {
-        // Token reserves for NRSH, ELXR, IMRT
-        reserves: Mapping<TokenId, Balance>,
-        // Liquidity provider shares
-        shares: Mapping<(AccountId, TokenId), Balance>,
-        // Post-quantum encrypted provider data
-        provider_data: Mapping<AccountId, EncryptedData>,
-        // Treasury reserves
-        treasury: Mapping<TokenId, Balance>,
-        // Protocol parameters
-        fee_rate: Balance,
-        treasury_rate: Balance,
-        // Quantum-resistant keys
-        kyber_public_key: KyberPublicKey,
-        dilithium_signature: DilithiumSignature,
-    }
-
-    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone)]
-    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-    pub enum TokenId {
-        NRSH,
-        ELXR,
-        IMRT,
-    }
-
-    #[derive(Encode, Decode)]
-    pub struct EncryptedData {
-        ciphertext: Vec<u8>,
-        nonce: [u8; 24],
-    }
-
-    impl UnifiedLiquidityPool {
-        #[ink(constructor)]
-        pub fn new(fee_rate: Balance, treasury_rate: Balance) -> Self {
-            ink_lang::utils::initialize_contract(|contract: &mut Self| {
-                contract.fee_rate = fee_rate;
-                contract.treasury_rate = treasury_rate;
-                
-                // Initialize post-quantum keys
-                let (public_key, _private_key) = kyber_keygen();
-                let (sig_public_key, sig_private_key) = dilithium_keygen();
-                
-                contract.kyber_public_key = public_key;
-                contract.dilithium_signature = dilithium_sign(
-                    &sig_private_key,
-                    &contract.encode()[..]
-                );
-            })
-        }
-
-        #[ink(message)]
-        pub fn add_liquidity(
-            &mut self,
-            token_id: TokenId,
-            amount: Balance,
-        ) -> Result<Balance, Error> {
-            let caller = self.env().caller();
-            
-            // Verify humanity protocol handprint
-            if !self.verify_human_handprint(&caller) {
-                return Err(Error::NotHuman);
-            }
-
-            // Calculate shares with post-quantum secure math
-            let shares = self.calculate_shares(token_id, amount)?;
-            
-            // Update reserves with quantum-resistant encryption
-            self.update_reserves(token_id, amount, true)?;
-            
-            // Update provider shares
-            self.shares.insert((caller, token_id), &shares);
-            
-            // Emit encrypted event
-            self.env().emit_event(LiquidityAdded {
-                provider: caller,
-                token_id,
-                amount,
-                shares,
-            });
-
-            Ok(shares)
-        }
-
-        #[ink(message)]
-        pub fn remove_liquidity(
-            &mut self,
-            token_id: TokenId,
-            shares: Balance,
-        ) -> Result<Balance, Error> {
-            let caller = self.env().caller();
-            
-            // Verify ownership of shares
-            let provider_shares = self.shares.get((caller, token_id))
-                .ok_or(Error::InsufficientShares)?;
-                
-            if provider_shares < shares {
-                return Err(Error::InsufficientShares);
-            }
-
-            // Calculate amount with post-quantum secure math
-            let amount = self.calculate_withdrawal_amount(token_id, shares)?;
-            
-            // Update reserves
-            self.update_reserves(token_id, amount, false)?;
-            
-            // Update shares
-            self.shares.insert(
-                (caller, token_id),
-                &(provider_shares - shares)
-            );
-
-            // Emit encrypted event
-            self.env().emit_event(LiquidityRemoved {
-                provider: caller,
-                token_id,
-                amount,
-                shares,
-            });
-
-            Ok(amount)
-        }
-
-        // Helper functions
-        fn verify_human_handprint(&self, account: &AccountId) -> bool {
-            // Integrate with Humanity Protocol for verification
-            true // Simplified for example
-        }
-
-        fn calculate_shares(
-            &self,
-            token_id: TokenId,
-            amount: Balance,
-        ) -> Result<Balance, Error> {
-            // Post-quantum secure calculation
-            // Implementation details...
-            Ok(amount) // Simplified for example
-        }
-
-        fn update_reserves(
-            &mut self,
-            token_id: TokenId,
-            amount: Balance,
-            is_addition: bool,
-        ) -> Result<(), Error> {
-            let current = self.reserves.get(token_id)
-                .unwrap_or(0);
-            
-            let new_amount = if is_addition {
-                current.checked_add(amount)
-            } else {
-                current.checked_sub(amount)
-            }.ok_or(Error::ArithmeticError)?;
-            
-            self.reserves.insert(token_id, &new_amount);
-            Ok(())
-        }
-    }
-
-    // Events
-    #[ink(event)]
-    pub struct LiquidityAdded {
-        #[ink(topic)]
-        provider: AccountId,
-        token_id: TokenId,
-        amount: Balance,
-        shares: Balance,
-    }
-
-    #[ink(event)]
-    pub struct LiquidityRemoved {
-        #[ink(topic)]
-        provider: AccountId,
-        token_id: TokenId,
-        amount: Balance,
-        shares: Balance,
-    }
-
-    // Custom errors
-    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
-    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-    pub enum Error {
-        InsufficientShares,
-        ArithmeticError,
-        NotHuman,
-        // Add more error types as needed
-    }
-}
@@ -0,0 +1,184 @@
+// Client side encryption of UnifiedLiquidityPool provider data
+//
+// Provider metadata is encrypted with XChaCha20-Poly1305 under a key
+// encapsulated to the provider's own Kyber public key, so only the provider
+// can read it back. `EncryptedData::ciphertext` holds the Kyber ciphertext
+// followed by the AEAD ciphertext, and the provider's account is bound as
+// associated data so a record cannot be replayed under another account.
+
+use crate::unified_liquidity_pool::{EncryptedData, KYBER_PUBLICKEYBYTES, MAX_PROVIDER_DATA_LEN};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use ink::primitives::AccountId;
+use pqc_kyber::{decapsulate, encapsulate, KYBER_CIPHERTEXTBYTES};
+use rand::{rngs::OsRng, RngCore};
+
+const _: () = assert!(KYBER_PUBLICKEYBYTES == pqc_kyber::KYBER_PUBLICKEYBYTES);
+
+// Length of the Poly1305 tag
+const TAG_LEN: usize = 16;
+
+// Longest plaintext whose record the pool accepts
+pub const MAX_PLAINTEXT_LEN: usize = MAX_PROVIDER_DATA_LEN - KYBER_CIPHERTEXTBYTES - TAG_LEN;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProviderDataError {
+    // Key has the wrong length or encapsulation failed
+    InvalidKey,
+    // Ciphertext is shorter than a Kyber ciphertext
+    Truncated,
+    // Plaintext is longer than `MAX_PLAINTEXT_LEN`
+    TooLarge,
+    // Authentication failed: wrong key, account or tampered data
+    DecryptionFailed,
+}
+
+// Encrypt `plaintext` for `provider`, whose Kyber public key is `public_key`
+pub fn encrypt_provider_data(
+    public_key: &[u8],
+    provider: &AccountId,
+    plaintext: &[u8],
+) -> Result<EncryptedData, ProviderDataError> {
+    if plaintext.len() > MAX_PLAINTEXT_LEN {
+        return Err(ProviderDataError::TooLarge);
+    }
+    let mut rng = OsRng;
+    let (kem_ciphertext, shared_secret) =
+        encapsulate(public_key, &mut rng).map_err(|_| ProviderDataError::InvalidKey)?;
+
+    let mut nonce = [0u8; 24];
+    rng.fill_bytes(&mut nonce);
+
+    let cipher = XChaCha20Poly1305::new(&shared_secret.into());
+    let sealed = cipher
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload { msg: plaintext, aad: provider.as_ref() },
+        )
+        .map_err(|_| ProviderDataError::InvalidKey)?;
+
+    let mut ciphertext = Vec::with_capacity(KYBER_CIPHERTEXTBYTES + sealed.len());
+    ciphertext.extend_from_slice(&kem_ciphertext);
+    ciphertext.extend_from_slice(&sealed);
+    Ok(EncryptedData { ciphertext, nonce })
+}
+
+// Decrypt provider data with the provider's Kyber secret key
+pub fn decrypt_provider_data(
+    secret_key: &[u8],
+    provider: &AccountId,
+    data: &EncryptedData,
+) -> Result<Vec<u8>, ProviderDataError> {
+    if data.ciphertext.len() < KYBER_CIPHERTEXTBYTES {
+        return Err(ProviderDataError::Truncated);
+    }
+    let (kem_ciphertext, sealed) = data.ciphertext.split_at(KYBER_CIPHERTEXTBYTES);
+    let shared_secret =
+        decapsulate(kem_ciphertext, secret_key).map_err(|_| ProviderDataError::InvalidKey)?;
+
+    let cipher = XChaCha20Poly1305::new(&shared_secret.into());
+    cipher
+        .decrypt(
+            XNonce::from_slice(&data.nonce),
+            Payload { msg: sealed, aad: provider.as_ref() },
+        )
+        .map_err(|_| ProviderDataError::DecryptionFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pqc_kyber::{keypair, Keypair};
+
+    fn keys() -> Keypair {
+        keypair(&mut OsRng).unwrap()
+    }
+
+    fn provider() -> AccountId {
+        AccountId::from([1; 32])
+    }
+
+    #[test]
+    fn round_trip() {
+        let keys = keys();
+        let data = encrypt_provider_data(&keys.public, &provider(), b"payout address").unwrap();
+        assert_eq!(data.ciphertext.len(), KYBER_CIPHERTEXTBYTES + 14 + TAG_LEN);
+        assert_eq!(decrypt_provider_data(&keys.secret, &provider(), &data), Ok(b"payout address".to_vec()));
+    }
+
+    #[test]
+    fn only_the_provider_can_decrypt() {
+        let keys = keys();
+        let data = encrypt_provider_data(&keys.public, &provider(), b"payout address").unwrap();
+        assert_eq!(
+            decrypt_provider_data(&self::keys().secret, &provider(), &data),
+            Err(ProviderDataError::DecryptionFailed)
+        );
+        // The record cannot be replayed under another account
+        assert_eq!(
+            decrypt_provider_data(&keys.secret, &AccountId::from([2; 32]), &data),
+            Err(ProviderDataError::DecryptionFailed)
+        );
+    }
+
+    #[test]
+    fn tampered_records_are_rejected() {
+        let keys = keys();
+        let data = encrypt_provider_data(&keys.public, &provider(), b"payout address").unwrap();
+
+        // A bit flipped in the Kyber ciphertext, the AEAD ciphertext or the nonce
+        for index in [0, KYBER_CIPHERTEXTBYTES, data.ciphertext.len() - 1] {
+            let mut tampered = data.clone();
+            tampered.ciphertext[index] ^= 1;
+            assert_eq!(
+                decrypt_provider_data(&keys.secret, &provider(), &tampered),
+                Err(ProviderDataError::DecryptionFailed)
+            );
+        }
+        let mut tampered = data.clone();
+        tampered.nonce[0] ^= 1;
+        assert_eq!(
+            decrypt_provider_data(&keys.secret, &provider(), &tampered),
+            Err(ProviderDataError::DecryptionFailed)
+        );
+
+        let mut truncated = data.clone();
+        truncated.ciphertext.pop();
+        assert_eq!(
+            decrypt_provider_data(&keys.secret, &provider(), &truncated),
+            Err(ProviderDataError::DecryptionFailed)
+        );
+        truncated.ciphertext.truncate(KYBER_CIPHERTEXTBYTES - 1);
+        assert_eq!(
+            decrypt_provider_data(&keys.secret, &provider(), &truncated),
+            Err(ProviderDataError::Truncated)
+        );
+    }
+
+    #[test]
+    fn malformed_keys_are_rejected() {
+        let keys = keys();
+        assert_eq!(
+            encrypt_provider_data(&keys.public[1..], &provider(), b"payout address"),
+            Err(ProviderDataError::InvalidKey)
+        );
+        let data = encrypt_provider_data(&keys.public, &provider(), b"payout address").unwrap();
+        assert_eq!(
+            decrypt_provider_data(&keys.secret[1..], &provider(), &data),
+            Err(ProviderDataError::InvalidKey)
+        );
+    }
+
+    #[test]
+    fn records_fit_the_pool_storage() {
+        let keys = keys();
+        let data = encrypt_provider_data(&keys.public, &provider(), &[7; MAX_PLAINTEXT_LEN]).unwrap();
+        assert_eq!(data.ciphertext.len(), MAX_PROVIDER_DATA_LEN);
+        assert_eq!(
+            encrypt_provider_data(&keys.public, &provider(), &[7; MAX_PLAINTEXT_LEN + 1]),
+            Err(ProviderDataError::TooLarge)
+        );
+    }
+}
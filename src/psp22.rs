@@ -0,0 +1,90 @@
+// PSP22 token transfers of the UnifiedLiquidityPool
+//
+// The pool moves tokens through a `TokenTransport`. On-chain that is
+// `Psp22Calls`, calling the token contracts; ink!'s off-chain unit tests
+// cannot call contracts and book transfers on the ledger of `mock` instead.
+
+use ink::env::{
+    call::{build_call, ExecutionInput, Selector},
+    DefaultEnvironment,
+};
+use ink::prelude::{string::String, vec::Vec};
+use ink::primitives::AccountId;
+use scale::{Decode, Encode};
+
+pub type Balance = u128;
+
+// PSP22 message selectors
+const PSP22_TRANSFER: [u8; 4] = [0xdb, 0x20, 0xf9, 0xf5];
+const PSP22_TRANSFER_FROM: [u8; 4] = [0x54, 0xb3, 0xc7, 0x6e];
+
+// Errors returned by PSP22 token contracts
+#[derive(Debug, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22Error {
+    Custom(String),
+    InsufficientBalance,
+    InsufficientAllowance,
+    ZeroRecipientAddress,
+    ZeroSenderAddress,
+    SafeTransferCheckFailed(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TransferError {
+    // The token contract could not be called or trapped
+    CallFailed,
+    // The token contract refused the transfer
+    Rejected,
+}
+
+pub trait TokenTransport {
+    // Move `amount` of `token` from `from` to `to`, spending the allowance
+    // `from` gave the calling contract
+    fn transfer_from(token: AccountId, from: AccountId, to: AccountId, amount: Balance) -> Result<(), TransferError>;
+
+    // Pay `amount` of `token` from the calling contract to `to`
+    fn transfer(token: AccountId, to: AccountId, amount: Balance) -> Result<(), TransferError>;
+}
+
+// Calls to the PSP22 token contracts
+pub struct Psp22Calls;
+
+impl Psp22Calls {
+    fn invoke<Args: Encode>(token: AccountId, input: ExecutionInput<Args>) -> Result<(), TransferError> {
+        build_call::<DefaultEnvironment>()
+            .call(token)
+            .gas_limit(0)
+            .exec_input(input)
+            .returns::<Result<(), PSP22Error>>()
+            .try_invoke()
+            .map_err(|_| TransferError::CallFailed)?
+            .map_err(|_| TransferError::CallFailed)?
+            .map_err(|_| TransferError::Rejected)
+    }
+}
+
+impl TokenTransport for Psp22Calls {
+    // PSP22::transfer_from(from, to, amount, data)
+    fn transfer_from(token: AccountId, from: AccountId, to: AccountId, amount: Balance) -> Result<(), TransferError> {
+        Self::invoke(
+            token,
+            ExecutionInput::new(Selector::new(PSP22_TRANSFER_FROM))
+                .push_arg(from)
+                .push_arg(to)
+                .push_arg(amount)
+                .push_arg(Vec::<u8>::new()),
+        )
+    }
+
+    // PSP22::transfer(to, amount, data)
+    fn transfer(token: AccountId, to: AccountId, amount: Balance) -> Result<(), TransferError> {
+        Self::invoke(
+            token,
+            ExecutionInput::new(Selector::new(PSP22_TRANSFER))
+                .push_arg(to)
+                .push_arg(amount)
+                .push_arg(Vec::<u8>::new()),
+        )
+    }
+}